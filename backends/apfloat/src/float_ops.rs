@@ -0,0 +1,234 @@
+use crate::backend::SoftFloatBackend;
+use crate::float::{F32, F64};
+use crate::trunc;
+use ::rustc_apfloat::ieee::{Double, Single};
+use ::rustc_apfloat::{Float, FloatConvert, Round, StatusAnd};
+
+/// The backend based on `rustc_apfloat`
+pub struct Apfloat;
+
+impl SoftFloatBackend for Apfloat {
+    fn f_32_ceil(v: F32) -> F32 {
+        quiet_32(single(v).round_to_integral(Round::TowardPositive).value)
+    }
+    fn f_32_floor(v: F32) -> F32 {
+        quiet_32(single(v).round_to_integral(Round::TowardNegative).value)
+    }
+    fn f_32_trunc(v: F32) -> F32 {
+        quiet_32(single(v).round_to_integral(Round::TowardZero).value)
+    }
+    fn f_32_nearest(v: F32) -> F32 {
+        quiet_32(single(v).round_to_integral(Round::NearestTiesToEven).value)
+    }
+    // `rustc_apfloat` has no square root
+    fn f_32_sqrt(v: F32) -> F32 {
+        v.sqrt()
+    }
+    fn f_64_ceil(v: F64) -> F64 {
+        quiet_64(double(v).round_to_integral(Round::TowardPositive).value)
+    }
+    fn f_64_floor(v: F64) -> F64 {
+        quiet_64(double(v).round_to_integral(Round::TowardNegative).value)
+    }
+    fn f_64_trunc(v: F64) -> F64 {
+        quiet_64(double(v).round_to_integral(Round::TowardZero).value)
+    }
+    fn f_64_nearest(v: F64) -> F64 {
+        quiet_64(double(v).round_to_integral(Round::NearestTiesToEven).value)
+    }
+    fn f_64_sqrt(v: F64) -> F64 {
+        v.sqrt()
+    }
+    fn i_32_trunc_sf_32(v: F32) -> i32 {
+        trunc::check_f32::<i32>(v.to_bits());
+        single(v).to_i128_r(32, Round::TowardZero, &mut false).value as i32
+    }
+    fn i_32_trunc_uf_32(v: F32) -> u32 {
+        trunc::check_f32::<u32>(v.to_bits());
+        single(v).to_u128_r(32, Round::TowardZero, &mut false).value as u32
+    }
+    fn i_32_trunc_sf_64(v: F64) -> i32 {
+        trunc::check_f64::<i32>(v.to_bits());
+        double(v).to_i128_r(32, Round::TowardZero, &mut false).value as i32
+    }
+    fn i_32_trunc_uf_64(v: F64) -> u32 {
+        trunc::check_f64::<u32>(v.to_bits());
+        double(v).to_u128_r(32, Round::TowardZero, &mut false).value as u32
+    }
+    fn i_64_trunc_sf_32(v: F32) -> i64 {
+        trunc::check_f32::<i64>(v.to_bits());
+        single(v).to_i128_r(64, Round::TowardZero, &mut false).value as i64
+    }
+    fn i_64_trunc_uf_32(v: F32) -> u64 {
+        trunc::check_f32::<u64>(v.to_bits());
+        single(v).to_u128_r(64, Round::TowardZero, &mut false).value as u64
+    }
+    fn i_64_trunc_sf_64(v: F64) -> i64 {
+        trunc::check_f64::<i64>(v.to_bits());
+        double(v).to_i128_r(64, Round::TowardZero, &mut false).value as i64
+    }
+    fn i_64_trunc_uf_64(v: F64) -> u64 {
+        trunc::check_f64::<u64>(v.to_bits());
+        double(v).to_u128_r(64, Round::TowardZero, &mut false).value as u64
+    }
+    fn f_32_convert_si_32(v: i32) -> F32 {
+        bits_32(Single::from_i128(v as i128).value)
+    }
+    fn f_32_convert_ui_32(v: u32) -> F32 {
+        bits_32(Single::from_u128(v as u128).value)
+    }
+    fn f_32_convert_si_64(v: i64) -> F32 {
+        bits_32(Single::from_i128(v as i128).value)
+    }
+    fn f_32_convert_ui_64(v: u64) -> F32 {
+        bits_32(Single::from_u128(v as u128).value)
+    }
+    fn f_32_demote_f_64(v: F64) -> F32 {
+        let v = double(v);
+        let res: StatusAnd<Single> = v.convert(&mut false);
+        if v.is_nan() {
+            // `rustc_apfloat` drops the low bits of the payload without quieting the NaN,
+            // which would turn a signaling NaN like 0x7ff0_0000_0000_0001 into infinity
+            F32::from_bits(res.value.to_bits() as u32 | 0x0040_0000)
+        } else {
+            bits_32(res.value)
+        }
+    }
+    fn f_64_convert_si_32(v: i32) -> F64 {
+        bits_64(Double::from_i128(v as i128).value)
+    }
+    fn f_64_convert_ui_32(v: u32) -> F64 {
+        bits_64(Double::from_u128(v as u128).value)
+    }
+    fn f_64_convert_si_64(v: i64) -> F64 {
+        bits_64(Double::from_i128(v as i128).value)
+    }
+    fn f_64_convert_ui_64(v: u64) -> F64 {
+        bits_64(Double::from_u128(v as u128).value)
+    }
+    fn f_64_promote_f_32(v: F32) -> F64 {
+        let res: StatusAnd<Double> = single(v).convert(&mut false);
+        quiet_64(res.value)
+    }
+    // without the checks the conversions saturate and convert NaN to zero, which is what the saturating
+    // versions do (rustc implements `as` casts with them)
+    fn i_32_trunc_s_sat_f_32(v: F32) -> i32 {
+        single(v).to_i128_r(32, Round::TowardZero, &mut false).value as i32
+    }
+    fn i_32_trunc_u_sat_f_32(v: F32) -> u32 {
+        single(v).to_u128_r(32, Round::TowardZero, &mut false).value as u32
+    }
+    fn i_32_trunc_s_sat_f_64(v: F64) -> i32 {
+        double(v).to_i128_r(32, Round::TowardZero, &mut false).value as i32
+    }
+    fn i_32_trunc_u_sat_f_64(v: F64) -> u32 {
+        double(v).to_u128_r(32, Round::TowardZero, &mut false).value as u32
+    }
+    fn i_64_trunc_s_sat_f_32(v: F32) -> i64 {
+        single(v).to_i128_r(64, Round::TowardZero, &mut false).value as i64
+    }
+    fn i_64_trunc_u_sat_f_32(v: F32) -> u64 {
+        single(v).to_u128_r(64, Round::TowardZero, &mut false).value as u64
+    }
+    fn i_64_trunc_s_sat_f_64(v: F64) -> i64 {
+        double(v).to_i128_r(64, Round::TowardZero, &mut false).value as i64
+    }
+    fn i_64_trunc_u_sat_f_64(v: F64) -> u64 {
+        double(v).to_u128_r(64, Round::TowardZero, &mut false).value as u64
+    }
+    fn f_32_lt(a: F32, b: F32) -> bool {
+        single(a) < single(b)
+    }
+    fn f_32_gt(a: F32, b: F32) -> bool {
+        single(a) > single(b)
+    }
+    fn f_32_le(a: F32, b: F32) -> bool {
+        single(a) <= single(b)
+    }
+    fn f_32_ge(a: F32, b: F32) -> bool {
+        single(a) >= single(b)
+    }
+    fn f_64_lt(a: F64, b: F64) -> bool {
+        double(a) < double(b)
+    }
+    fn f_64_gt(a: F64, b: F64) -> bool {
+        double(a) > double(b)
+    }
+    fn f_64_le(a: F64, b: F64) -> bool {
+        double(a) <= double(b)
+    }
+    fn f_64_ge(a: F64, b: F64) -> bool {
+        double(a) >= double(b)
+    }
+    fn f_32_add(a: F32, b: F32) -> F32 {
+        quiet_32((single(a) + single(b)).value)
+    }
+    fn f_32_sub(a: F32, b: F32) -> F32 {
+        quiet_32((single(a) - single(b)).value)
+    }
+    fn f_32_mul(a: F32, b: F32) -> F32 {
+        quiet_32((single(a) * single(b)).value)
+    }
+    fn f_32_div(a: F32, b: F32) -> F32 {
+        quiet_32((single(a) / single(b)).value)
+    }
+    fn f_32_min(a: F32, b: F32) -> F32 {
+        // `rustc_apfloat` implements IEEE 754 `minNum` and `maxNum`,
+        // which ignore NaN operands and do not order the zeroes
+        a.min(b)
+    }
+    fn f_32_max(a: F32, b: F32) -> F32 {
+        a.max(b)
+    }
+    fn f_64_add(a: F64, b: F64) -> F64 {
+        quiet_64((double(a) + double(b)).value)
+    }
+    fn f_64_sub(a: F64, b: F64) -> F64 {
+        quiet_64((double(a) - double(b)).value)
+    }
+    fn f_64_mul(a: F64, b: F64) -> F64 {
+        quiet_64((double(a) * double(b)).value)
+    }
+    fn f_64_div(a: F64, b: F64) -> F64 {
+        quiet_64((double(a) / double(b)).value)
+    }
+    fn f_64_min(a: F64, b: F64) -> F64 {
+        a.min(b)
+    }
+    fn f_64_max(a: F64, b: F64) -> F64 {
+        a.max(b)
+    }
+}
+
+fn single(v: F32) -> Single {
+    Single::from_bits(v.to_bits() as u128)
+}
+
+fn double(v: F64) -> Double {
+    Double::from_bits(v.to_bits() as u128)
+}
+
+fn bits_32(v: Single) -> F32 {
+    F32::from_bits(v.to_bits() as u32)
+}
+
+fn bits_64(v: Double) -> F64 {
+    F64::from_bits(v.to_bits() as u64)
+}
+
+/// `v` with the quiet bit set if it is NaN.
+/// `rustc_apfloat` passes signaling NaN operands through as they are, but wasm only returns quiet NaNs.
+fn quiet_32(v: Single) -> F32 {
+    let bits = v.to_bits() as u32;
+    F32::from_bits(if v.is_nan() { bits | 0x0040_0000 } else { bits })
+}
+
+/// The `f64` version of [`quiet_32`]
+fn quiet_64(v: Double) -> F64 {
+    let bits = v.to_bits() as u64;
+    F64::from_bits(if v.is_nan() {
+        bits | 0x0008_0000_0000_0000
+    } else {
+        bits
+    })
+}
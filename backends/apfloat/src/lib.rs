@@ -1,6 +1,7 @@
-mod ops;
+mod float_ops;
 
-// reexport basic operations
+// reexport the utils and the backend
+pub use float_ops::Apfloat;
 pub use wasm_soft_float_utils::*;
-// export rustc_apfloat based operations
-pub use ops::*;
+#[cfg(feature = "export")]
+wasm_soft_float_utils::export_backend!(Apfloat);
@@ -0,0 +1,12 @@
+use wasm_soft_float_apfloat::Apfloat;
+use wasm_soft_float_utils::{golden, implementations};
+
+/// Fails if any result of this backend changed, e.g. after updating a dependency or the compiler.
+/// Run `./golden-vectors.sh` to regenerate the vectors after an intended change.
+#[test]
+fn golden_vectors() {
+    golden::check(
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden.bin"),
+        &implementations!(Apfloat),
+    );
+}
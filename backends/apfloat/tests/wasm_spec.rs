@@ -1,3 +1,3 @@
-use wasm_soft_float_apfloat::*;
+use wasm_soft_float_apfloat::Apfloat;
 
-wasm_soft_float_utils::impl_tests!();
+wasm_soft_float_utils::impl_tests!(Apfloat);
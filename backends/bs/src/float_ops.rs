@@ -0,0 +1,213 @@
+use softfloat_c::*;
+use wasm_soft_float_utils::backend::SoftFloatBackend;
+use wasm_soft_float_utils::float::{F32, F64};
+use wasm_soft_float_utils::trunc;
+
+// const SOFTFLOAT_ROUND_ODD: u8 = 6;
+// const SOFTFLOAT_ROUND_NEAR_MAX_MAG: u8 = 4;
+const SOFTFLOAT_ROUND_MAX: u8 = 3;
+const SOFTFLOAT_ROUND_MIN: u8 = 2;
+const SOFTFLOAT_ROUND_MIN_MAG: u8 = 1;
+const SOFTFLOAT_ROUND_NEAR_EVEN: u8 = 0;
+
+/// The backend based on the Berkeley SoftFloat library
+pub struct BerkeleySoftFloat;
+
+impl SoftFloatBackend for BerkeleySoftFloat {
+    fn f_32_ceil(v: F32) -> F32 {
+        bits_32(unsafe { f32_roundToInt(soft_32(v), SOFTFLOAT_ROUND_MAX, false) })
+    }
+    fn f_32_floor(v: F32) -> F32 {
+        bits_32(unsafe { f32_roundToInt(soft_32(v), SOFTFLOAT_ROUND_MIN, false) })
+    }
+    fn f_32_trunc(v: F32) -> F32 {
+        // TODO: softfloat_round_minMag or softfloat_round_near_maxMag?
+        bits_32(unsafe { f32_roundToInt(soft_32(v), SOFTFLOAT_ROUND_MIN_MAG, false) })
+    }
+    fn f_32_nearest(v: F32) -> F32 {
+        bits_32(unsafe { f32_roundToInt(soft_32(v), SOFTFLOAT_ROUND_NEAR_EVEN, false) })
+    }
+    fn f_32_sqrt(v: F32) -> F32 {
+        bits_32(unsafe { f32_sqrt(soft_32(v)) })
+    }
+    fn f_64_ceil(v: F64) -> F64 {
+        bits_64(unsafe { f64_roundToInt(soft_64(v), SOFTFLOAT_ROUND_MAX, false) })
+    }
+    fn f_64_floor(v: F64) -> F64 {
+        bits_64(unsafe { f64_roundToInt(soft_64(v), SOFTFLOAT_ROUND_MIN, false) })
+    }
+    fn f_64_trunc(v: F64) -> F64 {
+        // TODO: softfloat_round_minMag or softfloat_round_near_maxMag?
+        bits_64(unsafe { f64_roundToInt(soft_64(v), SOFTFLOAT_ROUND_MIN_MAG, false) })
+    }
+    fn f_64_nearest(v: F64) -> F64 {
+        bits_64(unsafe { f64_roundToInt(soft_64(v), SOFTFLOAT_ROUND_NEAR_EVEN, false) })
+    }
+    fn f_64_sqrt(v: F64) -> F64 {
+        bits_64(unsafe { f64_sqrt(soft_64(v)) })
+    }
+    fn i_32_trunc_sf_32(v: F32) -> i32 {
+        trunc::check_f32::<i32>(v.to_bits());
+        unsafe { f32_to_i32(soft_32(v), SOFTFLOAT_ROUND_MIN_MAG, false) as i32 }
+    }
+    fn i_32_trunc_uf_32(v: F32) -> u32 {
+        trunc::check_f32::<u32>(v.to_bits());
+        unsafe { f32_to_ui32(soft_32(v), SOFTFLOAT_ROUND_MIN_MAG, false) as u32 }
+    }
+    fn i_32_trunc_sf_64(v: F64) -> i32 {
+        trunc::check_f64::<i32>(v.to_bits());
+        unsafe { f64_to_i32(soft_64(v), SOFTFLOAT_ROUND_MIN_MAG, false) as i32 }
+    }
+    fn i_32_trunc_uf_64(v: F64) -> u32 {
+        trunc::check_f64::<u32>(v.to_bits());
+        unsafe { f64_to_ui32(soft_64(v), SOFTFLOAT_ROUND_MIN_MAG, false) as u32 }
+    }
+    fn i_64_trunc_sf_32(v: F32) -> i64 {
+        trunc::check_f32::<i64>(v.to_bits());
+        unsafe { f32_to_i64(soft_32(v), SOFTFLOAT_ROUND_MIN_MAG, false) }
+    }
+    fn i_64_trunc_uf_32(v: F32) -> u64 {
+        trunc::check_f32::<u64>(v.to_bits());
+        unsafe { f32_to_ui64(soft_32(v), SOFTFLOAT_ROUND_MIN_MAG, false) }
+    }
+    fn i_64_trunc_sf_64(v: F64) -> i64 {
+        trunc::check_f64::<i64>(v.to_bits());
+        unsafe { f64_to_i64(soft_64(v), SOFTFLOAT_ROUND_MIN_MAG, false) }
+    }
+    fn i_64_trunc_uf_64(v: F64) -> u64 {
+        trunc::check_f64::<u64>(v.to_bits());
+        unsafe { f64_to_ui64(soft_64(v), SOFTFLOAT_ROUND_MIN_MAG, false) }
+    }
+    fn f_32_convert_si_32(v: i32) -> F32 {
+        bits_32(unsafe { i32_to_f32(v) })
+    }
+    fn f_32_convert_ui_32(v: u32) -> F32 {
+        bits_32(unsafe { ui32_to_f32(v) })
+    }
+    fn f_32_convert_si_64(v: i64) -> F32 {
+        bits_32(unsafe { i64_to_f32(v) })
+    }
+    fn f_32_convert_ui_64(v: u64) -> F32 {
+        bits_32(unsafe { ui64_to_f32(v) })
+    }
+    fn f_32_demote_f_64(v: F64) -> F32 {
+        bits_32(unsafe { f64_to_f32(soft_64(v)) })
+    }
+    fn f_64_convert_si_32(v: i32) -> F64 {
+        bits_64(unsafe { i32_to_f64(v) })
+    }
+    fn f_64_convert_ui_32(v: u32) -> F64 {
+        bits_64(unsafe { ui32_to_f64(v) })
+    }
+    fn f_64_convert_si_64(v: i64) -> F64 {
+        bits_64(unsafe { i64_to_f64(v) })
+    }
+    fn f_64_convert_ui_64(v: u64) -> F64 {
+        bits_64(unsafe { ui64_to_f64(v) })
+    }
+    fn f_64_promote_f_32(v: F32) -> F64 {
+        bits_64(unsafe { f32_to_f64(soft_32(v)) })
+    }
+    // SoftFloat returns implementation specific values for NaN and out of range operands
+    fn i_32_trunc_s_sat_f_32(v: F32) -> i32 {
+        trunc::f32_to_int_sat(v.to_bits())
+    }
+    fn i_32_trunc_u_sat_f_32(v: F32) -> u32 {
+        trunc::f32_to_int_sat(v.to_bits())
+    }
+    fn i_32_trunc_s_sat_f_64(v: F64) -> i32 {
+        trunc::f64_to_int_sat(v.to_bits())
+    }
+    fn i_32_trunc_u_sat_f_64(v: F64) -> u32 {
+        trunc::f64_to_int_sat(v.to_bits())
+    }
+    fn i_64_trunc_s_sat_f_32(v: F32) -> i64 {
+        trunc::f32_to_int_sat(v.to_bits())
+    }
+    fn i_64_trunc_u_sat_f_32(v: F32) -> u64 {
+        trunc::f32_to_int_sat(v.to_bits())
+    }
+    fn i_64_trunc_s_sat_f_64(v: F64) -> i64 {
+        trunc::f64_to_int_sat(v.to_bits())
+    }
+    fn i_64_trunc_u_sat_f_64(v: F64) -> u64 {
+        trunc::f64_to_int_sat(v.to_bits())
+    }
+    fn f_32_lt(a: F32, b: F32) -> bool {
+        unsafe { f32_lt(soft_32(a), soft_32(b)) }
+    }
+    fn f_32_gt(a: F32, b: F32) -> bool {
+        unsafe { f32_lt(soft_32(b), soft_32(a)) }
+    }
+    fn f_32_le(a: F32, b: F32) -> bool {
+        unsafe { f32_le(soft_32(a), soft_32(b)) }
+    }
+    fn f_32_ge(a: F32, b: F32) -> bool {
+        unsafe { f32_le(soft_32(b), soft_32(a)) }
+    }
+    fn f_64_lt(a: F64, b: F64) -> bool {
+        unsafe { f64_lt(soft_64(a), soft_64(b)) }
+    }
+    fn f_64_gt(a: F64, b: F64) -> bool {
+        unsafe { f64_lt(soft_64(b), soft_64(a)) }
+    }
+    fn f_64_le(a: F64, b: F64) -> bool {
+        unsafe { f64_le(soft_64(a), soft_64(b)) }
+    }
+    fn f_64_ge(a: F64, b: F64) -> bool {
+        unsafe { f64_le(soft_64(b), soft_64(a)) }
+    }
+    fn f_32_add(a: F32, b: F32) -> F32 {
+        bits_32(unsafe { f32_add(soft_32(a), soft_32(b)) })
+    }
+    fn f_32_sub(a: F32, b: F32) -> F32 {
+        bits_32(unsafe { f32_sub(soft_32(a), soft_32(b)) })
+    }
+    fn f_32_mul(a: F32, b: F32) -> F32 {
+        bits_32(unsafe { f32_mul(soft_32(a), soft_32(b)) })
+    }
+    fn f_32_div(a: F32, b: F32) -> F32 {
+        bits_32(unsafe { f32_div(soft_32(a), soft_32(b)) })
+    }
+    fn f_32_min(a: F32, b: F32) -> F32 {
+        // SoftFloat has no wasm compatible minimum and maximum
+        a.min(b)
+    }
+    fn f_32_max(a: F32, b: F32) -> F32 {
+        a.max(b)
+    }
+    fn f_64_add(a: F64, b: F64) -> F64 {
+        bits_64(unsafe { f64_add(soft_64(a), soft_64(b)) })
+    }
+    fn f_64_sub(a: F64, b: F64) -> F64 {
+        bits_64(unsafe { f64_sub(soft_64(a), soft_64(b)) })
+    }
+    fn f_64_mul(a: F64, b: F64) -> F64 {
+        bits_64(unsafe { f64_mul(soft_64(a), soft_64(b)) })
+    }
+    fn f_64_div(a: F64, b: F64) -> F64 {
+        bits_64(unsafe { f64_div(soft_64(a), soft_64(b)) })
+    }
+    fn f_64_min(a: F64, b: F64) -> F64 {
+        a.min(b)
+    }
+    fn f_64_max(a: F64, b: F64) -> F64 {
+        a.max(b)
+    }
+}
+
+fn soft_32(v: F32) -> float32_t {
+    float32_t::from_bits(v.to_bits())
+}
+
+fn soft_64(v: F64) -> float64_t {
+    float64_t::from_bits(v.to_bits())
+}
+
+fn bits_32(v: float32_t) -> F32 {
+    F32::from_bits(v.to_bits())
+}
+
+fn bits_64(v: float64_t) -> F64 {
+    F64::from_bits(v.to_bits())
+}
@@ -1,3 +1,3 @@
-use wasm_soft_float_bs::*;
+use wasm_soft_float_bs::BerkeleySoftFloat;
 
-wasm_soft_float_utils::impl_tests!();
+wasm_soft_float_utils::impl_tests!(BerkeleySoftFloat);
@@ -0,0 +1,190 @@
+//! Software float backend that evaluates every operation with two other backends and compares the results.
+//!
+//! The two backends are chosen by enabling exactly two of the `bs`, `apfloat` and `softfp` features.
+//! If the results differ, the module traps with `unreachable`. Float results are considered equal if they
+//! have the same bits or are both NaN, since the backends are free to choose the NaN payload.
+//! With the `import` feature, the `wasm_soft_float_checked.mismatch` import is called instead, with the name
+//! of the operation and both results. If it returns, the result of the first backend is used.
+//!
+//! The sign, equality and reinterpret operations use the defaults of
+//! [`SoftFloatBackend`](backend::SoftFloatBackend) in all backends, so they are not checked.
+
+#[cfg(all(feature = "bs", feature = "apfloat", feature = "softfp"))]
+compile_error!("only two of the `bs`, `apfloat` and `softfp` features can be enabled");
+#[cfg(not(any(
+    all(feature = "bs", feature = "apfloat"),
+    all(feature = "bs", feature = "softfp"),
+    all(feature = "apfloat", feature = "softfp"),
+)))]
+compile_error!("two of the `bs`, `apfloat` and `softfp` features must be enabled");
+
+#[cfg(all(feature = "bs", feature = "apfloat"))]
+pub use {
+    wasm_soft_float_apfloat::Apfloat as Second, wasm_soft_float_bs::BerkeleySoftFloat as First,
+};
+#[cfg(all(feature = "apfloat", feature = "softfp"))]
+pub use {wasm_soft_float_apfloat::Apfloat as First, wasm_soft_float_softfp::SoftFp as Second};
+#[cfg(all(feature = "bs", feature = "softfp"))]
+pub use {
+    wasm_soft_float_bs::BerkeleySoftFloat as First, wasm_soft_float_softfp::SoftFp as Second,
+};
+
+// reexport the utils and export the checked backends
+pub use wasm_soft_float_utils::*;
+wasm_soft_float_utils::export_backend!(Checked<First, Second>);
+
+use core::marker::PhantomData;
+
+use wasm_soft_float_utils::{
+    backend::SoftFloatBackend,
+    float::{F32, F64},
+};
+
+/// The backend evaluating every operation with `A` and `B`, returning the result of `A`.
+/// The trapping conversions of `A` trap before `B` is called.
+pub struct Checked<A, B>(PhantomData<(A, B)>);
+
+/// Implements the given operations by calling both backends and comparing their results
+macro_rules! checked {
+    ($($name: ident($($arg: ident: $t: ty),*) -> $ret: ty;)*) => {
+        $(
+            fn $name($($arg: $t),*) -> $ret {
+                let first = A::$name($($arg),*);
+                let second = B::$name($($arg),*);
+                if !first.same(second) {
+                    mismatch(stringify!($name), first.widen(), second.widen());
+                }
+                first
+            }
+        )*
+    };
+}
+
+impl<A: SoftFloatBackend, B: SoftFloatBackend> SoftFloatBackend for Checked<A, B> {
+    checked!(
+        f_32_lt(a: F32, b: F32) -> bool;
+        f_32_gt(a: F32, b: F32) -> bool;
+        f_32_le(a: F32, b: F32) -> bool;
+        f_32_ge(a: F32, b: F32) -> bool;
+        f_64_lt(a: F64, b: F64) -> bool;
+        f_64_gt(a: F64, b: F64) -> bool;
+        f_64_le(a: F64, b: F64) -> bool;
+        f_64_ge(a: F64, b: F64) -> bool;
+        f_32_ceil(v: F32) -> F32;
+        f_32_floor(v: F32) -> F32;
+        f_32_trunc(v: F32) -> F32;
+        f_32_nearest(v: F32) -> F32;
+        f_32_sqrt(v: F32) -> F32;
+        f_32_add(a: F32, b: F32) -> F32;
+        f_32_sub(a: F32, b: F32) -> F32;
+        f_32_mul(a: F32, b: F32) -> F32;
+        f_32_div(a: F32, b: F32) -> F32;
+        f_32_min(a: F32, b: F32) -> F32;
+        f_32_max(a: F32, b: F32) -> F32;
+        f_64_ceil(v: F64) -> F64;
+        f_64_floor(v: F64) -> F64;
+        f_64_trunc(v: F64) -> F64;
+        f_64_nearest(v: F64) -> F64;
+        f_64_sqrt(v: F64) -> F64;
+        f_64_add(a: F64, b: F64) -> F64;
+        f_64_sub(a: F64, b: F64) -> F64;
+        f_64_mul(a: F64, b: F64) -> F64;
+        f_64_div(a: F64, b: F64) -> F64;
+        f_64_min(a: F64, b: F64) -> F64;
+        f_64_max(a: F64, b: F64) -> F64;
+        i_32_trunc_sf_32(v: F32) -> i32;
+        i_32_trunc_uf_32(v: F32) -> u32;
+        i_32_trunc_sf_64(v: F64) -> i32;
+        i_32_trunc_uf_64(v: F64) -> u32;
+        i_64_trunc_sf_32(v: F32) -> i64;
+        i_64_trunc_uf_32(v: F32) -> u64;
+        i_64_trunc_sf_64(v: F64) -> i64;
+        i_64_trunc_uf_64(v: F64) -> u64;
+        f_32_convert_si_32(v: i32) -> F32;
+        f_32_convert_ui_32(v: u32) -> F32;
+        f_32_convert_si_64(v: i64) -> F32;
+        f_32_convert_ui_64(v: u64) -> F32;
+        f_32_demote_f_64(v: F64) -> F32;
+        f_64_convert_si_32(v: i32) -> F64;
+        f_64_convert_ui_32(v: u32) -> F64;
+        f_64_convert_si_64(v: i64) -> F64;
+        f_64_convert_ui_64(v: u64) -> F64;
+        f_64_promote_f_32(v: F32) -> F64;
+        i_32_trunc_s_sat_f_32(v: F32) -> i32;
+        i_32_trunc_u_sat_f_32(v: F32) -> u32;
+        i_32_trunc_s_sat_f_64(v: F64) -> i32;
+        i_32_trunc_u_sat_f_64(v: F64) -> u32;
+        i_64_trunc_s_sat_f_32(v: F32) -> i64;
+        i_64_trunc_u_sat_f_32(v: F32) -> u64;
+        i_64_trunc_s_sat_f_64(v: F64) -> i64;
+        i_64_trunc_u_sat_f_64(v: F64) -> u64;
+    );
+}
+
+/// The results of the operations
+trait Result: Copy {
+    /// Whether both backends agree. NaNs are the same, since the backends are free to choose their payload.
+    fn same(self, other: Self) -> bool;
+
+    /// The result passed to [`mismatch`]
+    fn widen(self) -> u64;
+}
+
+macro_rules! exact_result {
+    ($($t: ty),*) => {
+        $(
+            impl Result for $t {
+                fn same(self, other: Self) -> bool {
+                    self == other
+                }
+
+                fn widen(self) -> u64 {
+                    self as u64
+                }
+            }
+        )*
+    };
+}
+
+exact_result!(bool, i32, u32, i64, u64);
+
+macro_rules! float_result {
+    ($($t: ty),*) => {
+        $(
+            impl Result for $t {
+                fn same(self, other: Self) -> bool {
+                    self.to_bits() == other.to_bits() || (self.is_nan() && other.is_nan())
+                }
+
+                fn widen(self) -> u64 {
+                    self.to_bits() as u64
+                }
+            }
+        )*
+    };
+}
+
+float_result!(F32, F64);
+
+#[cfg(feature = "import")]
+#[link(wasm_import_module = "wasm_soft_float_checked")]
+extern "C" {
+    /// Called with the name of the operation and the results of both backends if they disagree.
+    /// The results are widened to 64 bits with `as u64`, which zero-extends unsigned results
+    /// and sign-extends the `i32` results of the signed truncations.
+    #[link_name = "mismatch"]
+    fn mismatch_import(op: *const u8, op_len: usize, first: u64, second: u64);
+}
+
+#[cold]
+#[inline(never)]
+fn mismatch(op: &str, first: u64, second: u64) {
+    #[cfg(feature = "import")]
+    unsafe {
+        mismatch_import(op.as_ptr(), op.len(), first, second)
+    }
+    #[cfg(all(not(feature = "import"), target_arch = "wasm32"))]
+    core::arch::wasm32::unreachable();
+    #[cfg(all(not(feature = "import"), not(target_arch = "wasm32")))]
+    panic!("backends disagree on {}: {:#x} != {:#x}", op, first, second);
+}
@@ -0,0 +1,3 @@
+use wasm_soft_float_checked::{Checked, First, Second};
+
+wasm_soft_float_utils::impl_tests!(Checked<First, Second>);
@@ -0,0 +1,60 @@
+//! Compares the fast paths with the backend on integer-valued operands,
+//! and on operands with inexact results, where the fast paths only add overhead.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use wasm_soft_float_fast::{
+    backend::SoftFloatBackend,
+    float::{F32, F64},
+    Backend, Fast,
+};
+
+type Binop64 = fn(F64, F64) -> F64;
+type Binop32 = fn(F32, F32) -> F32;
+
+fn bench64(c: &mut Criterion, name: &str, fast: Binop64, backend: Binop64) {
+    let mut group = c.benchmark_group(format!("f64 {}", name));
+    for (input, a, b) in [("integers", 1234567.0f64, 64.0f64), ("inexact", 0.1, 0.3)] {
+        let (a, b) = (F64::from_bits(a.to_bits()), F64::from_bits(b.to_bits()));
+        group.bench_with_input(
+            BenchmarkId::new("backend", input),
+            &(a, b),
+            |bench, &(a, b)| bench.iter(|| backend(black_box(a), black_box(b))),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("fast", input),
+            &(a, b),
+            |bench, &(a, b)| bench.iter(|| fast(black_box(a), black_box(b))),
+        );
+    }
+    group.finish();
+}
+
+fn bench32(c: &mut Criterion, name: &str, fast: Binop32, backend: Binop32) {
+    let mut group = c.benchmark_group(format!("f32 {}", name));
+    for (input, a, b) in [("integers", 1234.0f32, 64.0f32), ("inexact", 0.1, 0.3)] {
+        let (a, b) = (F32::from_bits(a.to_bits()), F32::from_bits(b.to_bits()));
+        group.bench_with_input(
+            BenchmarkId::new("backend", input),
+            &(a, b),
+            |bench, &(a, b)| bench.iter(|| backend(black_box(a), black_box(b))),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("fast", input),
+            &(a, b),
+            |bench, &(a, b)| bench.iter(|| fast(black_box(a), black_box(b))),
+        );
+    }
+    group.finish();
+}
+
+fn arithmetic(c: &mut Criterion) {
+    bench64(c, "add", Fast::f_64_add, Backend::f_64_add);
+    bench64(c, "sub", Fast::f_64_sub, Backend::f_64_sub);
+    bench64(c, "mul", Fast::f_64_mul, Backend::f_64_mul);
+    bench64(c, "div", Fast::f_64_div, Backend::f_64_div);
+    bench32(c, "add", Fast::f_32_add, Backend::f_32_add);
+    bench32(c, "mul", Fast::f_32_mul, Backend::f_32_mul);
+}
+
+criterion_group!(benches, arithmetic);
+criterion_main!(benches);
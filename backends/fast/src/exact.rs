@@ -0,0 +1,273 @@
+//! Exact float arithmetic using only integer instructions.
+//!
+//! Every finite float is `mantissa * 2^exponent` for an integer mantissa, so sums, products and quotients
+//! of the mantissas give the exact result. If that result can be represented in the format without
+//! rounding, it is the correctly rounded result and is returned. To stay cheaper than a full
+//! implementation, only cases that fit into 64 bit integers are handled:
+//! - addition and subtraction of normal numbers with few significant bits that are close to each other,
+//!   like small integers
+//! - multiplication of normal numbers by powers of two or with few significant bits
+//! - division of normal numbers by powers of two or by ones whose mantissa divides the one of the dividend
+//! - zero operands
+//!
+//! For everything else, including subnormal operands and results, the functions return `None`
+//! and the caller has to use a full implementation.
+//! The operands and results are the bits of the float, zero-extended to 64 bits for `f32`.
+
+/// A binary float format
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Format {
+    /// number of significant bits, including the implicit one
+    pub precision: u32,
+    /// number of exponent bits
+    pub exponent_bits: u32,
+}
+
+impl Format {
+    pub const F32: Format = Format {
+        precision: 24,
+        exponent_bits: 8,
+    };
+    pub const F64: Format = Format {
+        precision: 53,
+        exponent_bits: 11,
+    };
+
+    fn bias(self) -> i32 {
+        (1 << (self.exponent_bits - 1)) - 1
+    }
+
+    /// The biased exponent of infinity and NaN
+    fn max_exponent(self) -> i32 {
+        (1 << self.exponent_bits) - 1
+    }
+
+    fn sign(self, bits: u64) -> u64 {
+        bits & (1 << (self.precision + self.exponent_bits - 1))
+    }
+
+    /// The bits without the sign bit
+    fn abs(self, bits: u64) -> u64 {
+        bits ^ self.sign(bits)
+    }
+
+    fn infinity(self) -> u64 {
+        (self.max_exponent() as u64) << (self.precision - 1)
+    }
+
+    fn biased_exponent(self, bits: u64) -> i32 {
+        (self.abs(bits) >> (self.precision - 1)) as i32
+    }
+
+    /// The stored bits of the mantissa, without the implicit bit
+    fn fraction(self, bits: u64) -> u64 {
+        bits & ((1 << (self.precision - 1)) - 1)
+    }
+
+    /// The mantissa of a normal number, including the implicit bit
+    fn mantissa(self, bits: u64) -> u64 {
+        self.fraction(bits) | 1 << (self.precision - 1)
+    }
+
+    fn is_normal(self, exponent: i32) -> bool {
+        exponent > 0 && exponent < self.max_exponent()
+    }
+
+    /// Returns the bits of the normal number `mantissa * 2^(exponent - bias - precision + 1)`
+    /// if it can be represented exactly. `mantissa` must not be zero.
+    fn pack(self, sign: u64, mut mantissa: u64, mut exponent: i32) -> Option<u64> {
+        let top = 63 - mantissa.leading_zeros();
+        let implicit = self.precision - 1;
+        if top > implicit {
+            let shift = top - implicit;
+            if mantissa & ((1 << shift) - 1) != 0 {
+                return None;
+            }
+            mantissa >>= shift;
+        } else {
+            mantissa <<= implicit - top;
+        }
+        exponent += top as i32 - implicit as i32;
+        if !self.is_normal(exponent) {
+            return None;
+        }
+        Some(sign | (exponent as u64) << implicit | (mantissa & ((1 << implicit) - 1)))
+    }
+}
+
+/// Computes `a + b` if the result is exact
+#[inline]
+pub fn add(a: u64, b: u64, f: Format) -> Option<u64> {
+    let (abs_a, abs_b) = (f.abs(a), f.abs(b));
+    if abs_a == 0 || abs_b == 0 {
+        return if abs_a == 0 && abs_b == 0 {
+            // `-0 + -0` is the only negative sum of zeros
+            Some(a & b)
+        } else if abs_a == 0 && abs_b <= f.infinity() {
+            Some(b)
+        } else if abs_b == 0 && abs_a <= f.infinity() {
+            Some(a)
+        } else {
+            None
+        };
+    }
+
+    let (exponent_a, exponent_b) = (f.biased_exponent(a), f.biased_exponent(b));
+    if !f.is_normal(exponent_a) || !f.is_normal(exponent_b) {
+        return None;
+    }
+    // align the odd parts of the mantissas to the smaller exponent, if they still fit into 64 bits
+    let (mantissa_a, mantissa_b) = (f.mantissa(a), f.mantissa(b));
+    let (zeros_a, zeros_b) = (mantissa_a.trailing_zeros(), mantissa_b.trailing_zeros());
+    let (exponent_a, exponent_b) = (exponent_a + zeros_a as i32, exponent_b + zeros_b as i32);
+    let exponent = exponent_a.min(exponent_b);
+    let (shift_a, shift_b) = (
+        (exponent_a - exponent) as u32,
+        (exponent_b - exponent) as u32,
+    );
+    let (mantissa_a, mantissa_b) = (mantissa_a >> zeros_a, mantissa_b >> zeros_b);
+    if mantissa_a.leading_zeros() <= shift_a || mantissa_b.leading_zeros() <= shift_b {
+        return None;
+    }
+    let (mantissa_a, mantissa_b) = (mantissa_a << shift_a, mantissa_b << shift_b);
+    let (sign, mantissa) = if f.sign(a) == f.sign(b) {
+        (f.sign(a), mantissa_a + mantissa_b)
+    } else if mantissa_a >= mantissa_b {
+        (f.sign(a), mantissa_a - mantissa_b)
+    } else {
+        (f.sign(b), mantissa_b - mantissa_a)
+    };
+    if mantissa == 0 {
+        // `x - x` is positive zero
+        return Some(0);
+    }
+    f.pack(sign, mantissa, exponent)
+}
+
+/// Computes `a * b` if the result is exact
+#[inline]
+pub fn mul(a: u64, b: u64, f: Format) -> Option<u64> {
+    let sign = f.sign(a) ^ f.sign(b);
+    let (abs_a, abs_b) = (f.abs(a), f.abs(b));
+    if abs_a == 0 || abs_b == 0 {
+        // zero times infinity is NaN
+        return (abs_a < f.infinity() && abs_b < f.infinity()).then_some(sign);
+    }
+
+    let (exponent_a, exponent_b) = (f.biased_exponent(a), f.biased_exponent(b));
+    if !f.is_normal(exponent_a) || !f.is_normal(exponent_b) {
+        return None;
+    }
+    if f.fraction(a) == 0 || f.fraction(b) == 0 {
+        // multiplying by a power of two only changes the exponent
+        let exponent = exponent_a + exponent_b - f.bias();
+        return f.is_normal(exponent).then(|| {
+            sign | (exponent as u64) << (f.precision - 1) | f.fraction(a) | f.fraction(b)
+        });
+    }
+    // remove the trailing zeros, so the product is more likely to fit into 64 bits
+    let (mantissa_a, mantissa_b) = (f.mantissa(a), f.mantissa(b));
+    let (zeros_a, zeros_b) = (mantissa_a.trailing_zeros(), mantissa_b.trailing_zeros());
+    let (mantissa_a, mantissa_b) = (mantissa_a >> zeros_a, mantissa_b >> zeros_b);
+    if mantissa_a.leading_zeros() + mantissa_b.leading_zeros() < 64 {
+        return None;
+    }
+    f.pack(
+        sign,
+        mantissa_a * mantissa_b,
+        exponent_a + exponent_b - f.bias() - f.precision as i32 + 1 + (zeros_a + zeros_b) as i32,
+    )
+}
+
+/// Computes `a / b` if the result is exact
+#[inline]
+pub fn div(a: u64, b: u64, f: Format) -> Option<u64> {
+    let sign = f.sign(a) ^ f.sign(b);
+    let (abs_a, abs_b) = (f.abs(a), f.abs(b));
+    if abs_a == 0 {
+        // zero divided by zero is NaN
+        return (abs_b != 0 && abs_b <= f.infinity()).then_some(sign);
+    }
+
+    let (exponent_a, exponent_b) = (f.biased_exponent(a), f.biased_exponent(b));
+    if !f.is_normal(exponent_a) || !f.is_normal(exponent_b) {
+        return None;
+    }
+    if f.fraction(b) == 0 {
+        // dividing by a power of two only changes the exponent
+        let exponent = exponent_a - exponent_b + f.bias();
+        return f
+            .is_normal(exponent)
+            .then(|| sign | (exponent as u64) << (f.precision - 1) | f.fraction(a));
+    }
+    // the quotient is only a binary fraction if the odd part of the divisor divides the dividend
+    let mantissa_b = f.mantissa(b);
+    let zeros = mantissa_b.trailing_zeros();
+    let mantissa_b = mantissa_b >> zeros;
+    let mantissa_a = f.mantissa(a);
+    if !mantissa_a.is_multiple_of(mantissa_b) {
+        return None;
+    }
+    f.pack(
+        sign,
+        mantissa_a / mantissa_b,
+        exponent_a - exponent_b + f.bias() + f.precision as i32 - 1 - zeros as i32,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add64(a: f64, b: f64) -> Option<f64> {
+        add(a.to_bits(), b.to_bits(), Format::F64).map(f64::from_bits)
+    }
+
+    fn mul64(a: f64, b: f64) -> Option<f64> {
+        mul(a.to_bits(), b.to_bits(), Format::F64).map(f64::from_bits)
+    }
+
+    fn div64(a: f64, b: f64) -> Option<f64> {
+        div(a.to_bits(), b.to_bits(), Format::F64).map(f64::from_bits)
+    }
+
+    #[test]
+    fn packs() {
+        let f = Format::F64;
+        assert_eq!(f.pack(f.sign(u64::MAX), 3, 1075), Some((-3.0f64).to_bits()));
+        assert_eq!(f.pack(0, 1 << 60, 1075 - 60), Some(1.0f64.to_bits()));
+        assert_eq!(f.pack(0, (1 << 53) + 1, 1075), None);
+        // subnormal and infinite results
+        assert_eq!(f.pack(0, 1, 1), None);
+        assert_eq!(f.pack(0, 1, 2100), None);
+    }
+
+    #[test]
+    fn only_exact_results() {
+        assert_eq!(add64(1.0, 2.0), Some(3.0));
+        assert_eq!(add64(1.0, -1.0).map(f64::to_bits), Some(0));
+        assert_eq!(
+            add64(-0.0, -0.0).map(f64::to_bits),
+            Some((-0.0f64).to_bits())
+        );
+        assert_eq!(add64(0.0, f64::INFINITY), Some(f64::INFINITY));
+        assert_eq!(add64(0.1, 0.2), None);
+        assert_eq!(add64(1e300, 1e-300), None);
+        assert_eq!(add64(f64::MAX, f64::MAX), None);
+        assert_eq!(
+            mul64(-0.0, 2.0).map(f64::to_bits),
+            Some((-0.0f64).to_bits())
+        );
+        assert_eq!(mul64(0.0, f64::INFINITY), None);
+        assert_eq!(mul64(1234567.0, 0.25), Some(308641.75));
+        assert_eq!(mul64(f64::MAX, 2.0), None);
+        assert_eq!(div64(6.0, 4.0), Some(1.5));
+        assert_eq!(div64(1.0, 3.0), None);
+        assert_eq!(div64(1.0, 0.0), None);
+        assert_eq!(div64(0.0, 0.0), None);
+        assert_eq!(
+            div64(-0.0, f64::INFINITY).map(f64::to_bits),
+            Some((-0.0f64).to_bits())
+        );
+    }
+}
@@ -0,0 +1,130 @@
+//! Software float backend that computes the results of exact arithmetic with integer instructions.
+//!
+//! Many programs only use floats to hold integers or values with few significant bits, so the result of
+//! most additions, subtractions, multiplications and divisions needs no rounding at all. For these,
+//! [`exact`] computes the result directly from the mantissas and exponents of the operands, which is
+//! much cheaper than the full implementation. Everything else, including NaN and infinite operands,
+//! overflow and all inexact results, falls back to the backend chosen with the `bs` (default),
+//! `apfloat` or `softfp` feature, so the results are bit-identical to the ones of that backend.
+
+#[cfg(any(
+    all(feature = "bs", feature = "apfloat"),
+    all(feature = "bs", feature = "softfp"),
+    all(feature = "apfloat", feature = "softfp"),
+))]
+compile_error!("only one of the `bs`, `apfloat` and `softfp` features can be enabled");
+#[cfg(not(any(feature = "bs", feature = "apfloat", feature = "softfp")))]
+compile_error!("one of the `bs`, `apfloat` and `softfp` features must be enabled");
+
+pub mod exact;
+
+#[cfg(feature = "apfloat")]
+pub use wasm_soft_float_apfloat::Apfloat as Backend;
+#[cfg(feature = "bs")]
+pub use wasm_soft_float_bs::BerkeleySoftFloat as Backend;
+#[cfg(feature = "softfp")]
+pub use wasm_soft_float_softfp::SoftFp as Backend;
+
+// reexport the utils and export the backend
+pub use wasm_soft_float_utils::*;
+wasm_soft_float_utils::export_backend!(Fast);
+
+use exact::Format;
+use wasm_soft_float_utils::{
+    backend::SoftFloatBackend,
+    float::{F32, F64},
+};
+
+/// The backend with the fast paths, which calls [`Backend`] for everything else
+pub struct Fast;
+
+/// Implements the given arithmetic operations, trying `exact::$exact` before calling the backend.
+/// Subtraction is computed as the addition of the second operand with its sign bit flipped.
+macro_rules! fast {
+    ($($name: ident: $float: ident, $exact: ident $(, flip $sign: literal)?;)*) => {
+        $(
+            fn $name(a: $float, b: $float) -> $float {
+                match exact::$exact(a.to_bits() as u64, (b.to_bits() $(^ $sign)?) as u64, Format::$float) {
+                    Some(result) => $float::from_bits(result as _),
+                    None => Backend::$name(a, b),
+                }
+            }
+        )*
+    };
+}
+
+/// Implements the given operations by calling the backend
+macro_rules! forward {
+    ($($name: ident($($arg: ident: $t: ty),*) -> $ret: ty;)*) => {
+        $(
+            fn $name($($arg: $t),*) -> $ret {
+                Backend::$name($($arg),*)
+            }
+        )*
+    };
+}
+
+// the sign, equality and reinterpret operations keep the defaults of the trait like all backends
+impl SoftFloatBackend for Fast {
+    fast!(
+        f_32_add: F32, add;
+        f_32_sub: F32, add, flip 0x8000_0000;
+        f_32_mul: F32, mul;
+        f_32_div: F32, div;
+        f_64_add: F64, add;
+        f_64_sub: F64, add, flip 0x8000_0000_0000_0000;
+        f_64_mul: F64, mul;
+        f_64_div: F64, div;
+    );
+
+    forward!(
+        f_32_lt(a: F32, b: F32) -> bool;
+        f_32_gt(a: F32, b: F32) -> bool;
+        f_32_le(a: F32, b: F32) -> bool;
+        f_32_ge(a: F32, b: F32) -> bool;
+        f_64_lt(a: F64, b: F64) -> bool;
+        f_64_gt(a: F64, b: F64) -> bool;
+        f_64_le(a: F64, b: F64) -> bool;
+        f_64_ge(a: F64, b: F64) -> bool;
+        f_32_ceil(v: F32) -> F32;
+        f_32_floor(v: F32) -> F32;
+        f_32_trunc(v: F32) -> F32;
+        f_32_nearest(v: F32) -> F32;
+        f_32_sqrt(v: F32) -> F32;
+        f_32_min(a: F32, b: F32) -> F32;
+        f_32_max(a: F32, b: F32) -> F32;
+        f_64_ceil(v: F64) -> F64;
+        f_64_floor(v: F64) -> F64;
+        f_64_trunc(v: F64) -> F64;
+        f_64_nearest(v: F64) -> F64;
+        f_64_sqrt(v: F64) -> F64;
+        f_64_min(a: F64, b: F64) -> F64;
+        f_64_max(a: F64, b: F64) -> F64;
+        i_32_trunc_sf_32(v: F32) -> i32;
+        i_32_trunc_uf_32(v: F32) -> u32;
+        i_32_trunc_sf_64(v: F64) -> i32;
+        i_32_trunc_uf_64(v: F64) -> u32;
+        i_64_trunc_sf_32(v: F32) -> i64;
+        i_64_trunc_uf_32(v: F32) -> u64;
+        i_64_trunc_sf_64(v: F64) -> i64;
+        i_64_trunc_uf_64(v: F64) -> u64;
+        f_32_convert_si_32(v: i32) -> F32;
+        f_32_convert_ui_32(v: u32) -> F32;
+        f_32_convert_si_64(v: i64) -> F32;
+        f_32_convert_ui_64(v: u64) -> F32;
+        f_32_demote_f_64(v: F64) -> F32;
+        f_64_convert_si_32(v: i32) -> F64;
+        f_64_convert_ui_32(v: u32) -> F64;
+        f_64_convert_si_64(v: i64) -> F64;
+        f_64_convert_ui_64(v: u64) -> F64;
+        f_64_promote_f_32(v: F32) -> F64;
+        i_32_trunc_s_sat_f_32(v: F32) -> i32;
+        i_32_trunc_u_sat_f_32(v: F32) -> u32;
+        i_32_trunc_s_sat_f_64(v: F64) -> i32;
+        i_32_trunc_u_sat_f_64(v: F64) -> u32;
+        i_64_trunc_s_sat_f_32(v: F32) -> i64;
+        i_64_trunc_u_sat_f_32(v: F32) -> u64;
+        i_64_trunc_s_sat_f_64(v: F64) -> i64;
+        i_64_trunc_u_sat_f_64(v: F64) -> u64;
+    );
+}
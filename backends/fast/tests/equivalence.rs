@@ -0,0 +1,179 @@
+//! Checks that the fast paths give bit-identical results to the backend they fall back to.
+
+use quickcheck::quickcheck;
+use wasm_soft_float_fast::{
+    backend::SoftFloatBackend,
+    float::{F32, F64},
+    Backend, Fast,
+};
+
+type Binop32 = fn(F32, F32) -> F32;
+type Binop64 = fn(F64, F64) -> F64;
+
+/// The fast and backend implementations of every operation with a fast path
+const OPS32: [(&str, Binop32, Binop32); 4] = [
+    ("add", Fast::f_32_add, Backend::f_32_add),
+    ("sub", Fast::f_32_sub, Backend::f_32_sub),
+    ("mul", Fast::f_32_mul, Backend::f_32_mul),
+    ("div", Fast::f_32_div, Backend::f_32_div),
+];
+
+const OPS64: [(&str, Binop64, Binop64); 4] = [
+    ("add", Fast::f_64_add, Backend::f_64_add),
+    ("sub", Fast::f_64_sub, Backend::f_64_sub),
+    ("mul", Fast::f_64_mul, Backend::f_64_mul),
+    ("div", Fast::f_64_div, Backend::f_64_div),
+];
+
+fn check32(a: u32, b: u32) {
+    let (a, b) = (F32::from_bits(a), F32::from_bits(b));
+    for (name, fast, backend) in OPS32 {
+        assert_eq!(
+            fast(a, b).to_bits(),
+            backend(a, b).to_bits(),
+            "f32 {} of {:#x} and {:#x}",
+            name,
+            a.to_bits(),
+            b.to_bits()
+        );
+    }
+}
+
+fn check64(a: u64, b: u64) {
+    let (a, b) = (F64::from_bits(a), F64::from_bits(b));
+    for (name, fast, backend) in OPS64 {
+        assert_eq!(
+            fast(a, b).to_bits(),
+            backend(a, b).to_bits(),
+            "f64 {} of {:#x} and {:#x}",
+            name,
+            a.to_bits(),
+            b.to_bits()
+        );
+    }
+}
+
+/// Values that hit the edge cases of the fast paths
+fn special32() -> Vec<u32> {
+    let mut values = vec![
+        0,
+        1,
+        0x007f_ffff,
+        0x0080_0000,
+        0x7f7f_ffff,
+        0x7f80_0000,
+        0x7fc0_0000,
+        0x7f80_0001,
+    ];
+    values.extend((0..=254).map(|exponent| exponent << 23));
+    values.extend((0..=255).map(|v| (v as f32).to_bits()));
+    values.extend((1..=16).map(|v| (1.0 / v as f32).to_bits()));
+    let negated: Vec<_> = values.iter().map(|v| v ^ 0x8000_0000).collect();
+    values.extend(negated);
+    values
+}
+
+#[test]
+fn small_integers() {
+    for a in -256..=256 {
+        for b in -256..=256 {
+            check32((a as f32).to_bits(), (b as f32).to_bits());
+            check64((a as f64).to_bits(), (b as f64).to_bits());
+        }
+    }
+}
+
+fn promote(v: u32) -> u64 {
+    Backend::f_64_promote_f_32(F32::from_bits(v)).to_bits()
+}
+
+#[test]
+fn special_values() {
+    let values = special32();
+    for &a in &values {
+        for &b in &values {
+            check32(a, b);
+            check64(promote(a), promote(b));
+        }
+    }
+}
+
+/// Checks every `f32` against itself, its negation and a few values taking each fast path.
+/// This takes a while, run it with `cargo test --release -- --ignored`.
+#[test]
+#[ignore]
+fn exhaustive32() {
+    let values: Vec<u32> = [
+        0.0f32,
+        -0.0,
+        1.0,
+        -1.0,
+        2.0,
+        0.5,
+        3.0,
+        f32::INFINITY,
+        f32::NAN,
+    ]
+    .iter()
+    .map(|v| v.to_bits())
+    .chain([1])
+    .collect();
+    let threads = std::thread::available_parallelism().map_or(4, |n| n.get()) as u64;
+    let chunk = (1u64 << 32) / threads;
+    std::thread::scope(|scope| {
+        for thread in 0..threads {
+            let values = &values;
+            scope.spawn(move || {
+                let end = if thread == threads - 1 {
+                    1 << 32
+                } else {
+                    (thread + 1) * chunk
+                };
+                for a in thread * chunk..end {
+                    let a = a as u32;
+                    check32(a, a);
+                    check32(a, a ^ 0x8000_0000);
+                    for &b in values {
+                        check32(a, b);
+                        check32(b, a);
+                    }
+                }
+            });
+        }
+    });
+}
+
+quickcheck! {
+    fn random32(a: u32, b: u32) -> () {
+        check32(a, b)
+    }
+
+    fn random64(a: u64, b: u64) -> () {
+        check64(a, b)
+    }
+
+    fn integers32(a: i16, b: i16) -> () {
+        check32((a as f32).to_bits(), (b as f32).to_bits())
+    }
+
+    fn integers64(a: i32, b: i32) -> () {
+        check64((a as f64).to_bits(), (b as f64).to_bits())
+    }
+
+    fn same_exponent32(a: u32, b: u32) -> () {
+        // replace the exponent of `b` with the one of `a`
+        check32(a, (b & 0x807f_ffff) | (a & 0x7f80_0000))
+    }
+
+    fn same_exponent64(a: u64, b: u64) -> () {
+        check64(a, (b & 0x800f_ffff_ffff_ffff) | (a & 0x7ff0_0000_0000_0000))
+    }
+
+    fn power_of_two32(a: u32, exponent: u8) -> () {
+        check32(a, (exponent as u32) << 23)
+    }
+
+    fn power_of_two64(a: u64, exponent: u16) -> () {
+        check64(a, ((exponent % 2048) as u64) << 52)
+    }
+}
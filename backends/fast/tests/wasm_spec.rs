@@ -0,0 +1,3 @@
+use wasm_soft_float_fast::Fast;
+
+wasm_soft_float_utils::impl_tests!(Fast);
@@ -0,0 +1,222 @@
+//! Exponential, logarithm and power functions.
+
+use core::cmp::Ordering;
+
+use crate::ext::{horner, Class, Ext, Format};
+
+// constants rounded to 128 bits
+const LN2: Ext = Ext::raw(-1, 0xb17217f7_d1cf79ab_c9e3b398_03f2f6af);
+const INV_LN2: Ext = Ext::raw(0, 0xb8aa3b29_5c17f0bb_be87fed0_691d3e89);
+const INV_LN10: Ext = Ext::raw(-2, 0xde5bd8a9_37287195_355baaaf_ad33dc32);
+const LOG10_2: Ext = Ext::raw(-2, 0x9a209a84_fbcff798_8f8959ac_0b7c9178);
+/// Mantissa of `sqrt(2)`
+const SQRT2: u128 = 0xb504f333_f9de6484_597d89b3_754abe9f;
+
+/// `1 / k!`
+pub(crate) const INV_FACT: [Ext; 23] = [
+    Ext::raw(0, 0x80000000_00000000_00000000_00000000),
+    Ext::raw(0, 0x80000000_00000000_00000000_00000000),
+    Ext::raw(-1, 0x80000000_00000000_00000000_00000000),
+    Ext::raw(-3, 0xaaaaaaaa_aaaaaaaa_aaaaaaaa_aaaaaaab),
+    Ext::raw(-5, 0xaaaaaaaa_aaaaaaaa_aaaaaaaa_aaaaaaab),
+    Ext::raw(-7, 0x88888888_88888888_88888888_88888889),
+    Ext::raw(-10, 0xb60b60b6_0b60b60b_60b60b60_b60b60b6),
+    Ext::raw(-13, 0xd00d00d0_0d00d00d_00d00d00_d00d00d0),
+    Ext::raw(-16, 0xd00d00d0_0d00d00d_00d00d00_d00d00d0),
+    Ext::raw(-19, 0xb8ef1d2a_b6399c7d_560e4472_800b8ef2),
+    Ext::raw(-22, 0x93f27dbb_c4fae397_780b69f5_333c725b),
+    Ext::raw(-26, 0xd7322b3f_aa271c7f_3a3f25c1_bee38f10),
+    Ext::raw(-29, 0x8f76c77f_c6c4bdaa_26d4c3d6_7f425f60),
+    Ext::raw(-33, 0xb092309d_43684be5_1c198e91_d7b4269e),
+    Ext::raw(-37, 0xc9cba546_03e4e905_d6f8a2ef_d1f27546),
+    Ext::raw(-41, 0xd73f9f39_9dc0f88e_c32b5877_4657f48f),
+    Ext::raw(-45, 0xd73f9f39_9dc0f88e_c32b5877_4657f48f),
+    Ext::raw(-49, 0xca963b81_856a5359_3028cbbb_8d7ff53c),
+    Ext::raw(-53, 0xb413c31d_cbecbbdd_80244351_61554bc3),
+    Ext::raw(-57, 0x97a4da34_0a0ab926_50f61dbd_cb3a5abf),
+    Ext::raw(-62, 0xf2a15d20_1011283d_4e5695fc_785d5dff),
+    Ext::raw(-66, 0xb8dc77b6_e7ab8c5f_78a37e77_372290c2),
+    Ext::raw(-70, 0x8671cb6d_bfc294a2_86485bf9_9c763abc),
+];
+
+/// `1 / (2j + 1)`
+const INV_ODD: [Ext; 15] = [
+    Ext::raw(0, 0x80000000_00000000_00000000_00000000),
+    Ext::raw(-2, 0xaaaaaaaa_aaaaaaaa_aaaaaaaa_aaaaaaab),
+    Ext::raw(-3, 0xcccccccc_cccccccc_cccccccc_cccccccd),
+    Ext::raw(-3, 0x92492492_49249249_24924924_92492492),
+    Ext::raw(-4, 0xe38e38e3_8e38e38e_38e38e38_e38e38e4),
+    Ext::raw(-4, 0xba2e8ba2_e8ba2e8b_a2e8ba2e_8ba2e8ba),
+    Ext::raw(-4, 0x9d89d89d_89d89d89_d89d89d8_9d89d89e),
+    Ext::raw(-4, 0x88888888_88888888_88888888_88888889),
+    Ext::raw(-5, 0xf0f0f0f0_f0f0f0f0_f0f0f0f0_f0f0f0f1),
+    Ext::raw(-5, 0xd79435e5_0d79435e_50d79435_e50d7943),
+    Ext::raw(-5, 0xc30c30c3_0c30c30c_30c30c30_c30c30c3),
+    Ext::raw(-5, 0xb21642c8_590b2164_2c8590b2_1642c859),
+    Ext::raw(-5, 0xa3d70a3d_70a3d70a_3d70a3d7_0a3d70a4),
+    Ext::raw(-5, 0x97b425ed_097b425e_d097b425_ed097b42),
+    Ext::raw(-5, 0x8d3dcb08_d3dcb08d_3dcb08d3_dcb08d3e),
+];
+
+/// Computes `2^t` for `|t| < 2^11`
+fn exp2(t: Ext) -> Ext {
+    let (n, f) = t.split_int();
+    // |f * ln(2)| <= 0.35, so the taylor series is accurate to 2^-74 after 18 terms
+    horner(&INV_FACT[..18], f.mul(LN2)).scale(n as i32)
+}
+
+/// Computes `2^t` rounded to `format`
+fn round_exp2(t: Ext, format: Format) -> u64 {
+    if t.exp >= 11 {
+        // far outside of the range of all formats
+        return if t.neg {
+            format.zero(false)
+        } else {
+            format.infinity(false)
+        };
+    }
+    exp2(t).round(format)
+}
+
+pub(crate) fn exp(x: u64, format: Format) -> u64 {
+    match format.classify(x) {
+        Class::Nan => format.quiet(x),
+        Class::Infinite { neg: true } => format.zero(false),
+        Class::Infinite { neg: false } => x,
+        Class::Zero { .. } => format.one(),
+        Class::Finite(x) => round_exp2(x.mul(INV_LN2), format),
+    }
+}
+
+/// Splits a positive value into `e` and `ln(m)`, with `x = 2^e * m` and `m` in `[sqrt(1/2), sqrt(2)]`
+fn ln_parts(x: Ext) -> (i32, Ext) {
+    let (e, m) = if x.mant > SQRT2 {
+        (x.exp + 1, Ext::raw(-1, x.mant))
+    } else {
+        (x.exp, Ext::raw(0, x.mant))
+    };
+    // ln(m) = 2 atanh(s) = 2 (s + s^3 / 3 + s^5 / 5 + ...) with |s| <= 0.172,
+    // which is accurate to 2^-76 after 15 terms
+    let f = m.sub(Ext::ONE);
+    let s = f.div(m.add(Ext::ONE));
+    let ln_m = s.mul(horner(&INV_ODD, s.mul(s))).scale(1);
+    (e, ln_m)
+}
+
+/// Computes a logarithm of `x` from [`ln_parts`], handling all special cases
+fn log(x: u64, format: Format, from_parts: impl Fn(i32, Ext) -> Ext) -> u64 {
+    match format.classify(x) {
+        Class::Nan => format.quiet(x),
+        Class::Zero { .. } => format.infinity(true),
+        Class::Infinite { neg: false } => x,
+        Class::Infinite { neg: true } => format.nan(),
+        Class::Finite(x) if x.neg => format.nan(),
+        Class::Finite(x) => {
+            let (e, ln_m) = ln_parts(x);
+            from_parts(e, ln_m).round(format)
+        }
+    }
+}
+
+pub(crate) fn ln(x: u64, format: Format) -> u64 {
+    log(x, format, |e, ln_m| {
+        Ext::from_int(e as i64).mul(LN2).add(ln_m)
+    })
+}
+
+pub(crate) fn log2(x: u64, format: Format) -> u64 {
+    log(x, format, |e, ln_m| {
+        Ext::from_int(e as i64).add(ln_m.mul(INV_LN2))
+    })
+}
+
+pub(crate) fn log10(x: u64, format: Format) -> u64 {
+    log(x, format, |e, ln_m| {
+        Ext::from_int(e as i64).mul(LOG10_2).add(ln_m.mul(INV_LN10))
+    })
+}
+
+#[derive(PartialEq, Eq)]
+enum Parity {
+    NotInteger,
+    Even,
+    Odd,
+}
+
+fn parity(bits: u64, format: Format) -> Parity {
+    let y = match format.classify(bits) {
+        Class::Finite(y) => y,
+        Class::Zero { .. } | Class::Infinite { .. } => return Parity::Even,
+        Class::Nan => return Parity::NotInteger,
+    };
+    if y.exp < 0 {
+        return Parity::NotInteger;
+    }
+    // the mantissa has at most 53 significant bits, so every larger value is even
+    if y.exp > 64 {
+        return Parity::Even;
+    }
+    let frac_bits = 127 - y.exp as u32;
+    if y.mant & ((1 << frac_bits) - 1) != 0 {
+        Parity::NotInteger
+    } else if (y.mant >> frac_bits) & 1 == 1 {
+        Parity::Odd
+    } else {
+        Parity::Even
+    }
+}
+
+pub(crate) fn pow(x_bits: u64, y_bits: u64, format: Format) -> u64 {
+    let one = format.one();
+    match (format.classify(x_bits), format.classify(y_bits)) {
+        (_, Class::Zero { .. }) => one,
+        _ if x_bits == one => one,
+        (Class::Nan, _) => format.quiet(x_bits),
+        (_, Class::Nan) => format.quiet(y_bits),
+        (x, Class::Infinite { neg }) => {
+            let magnitude = match x {
+                Class::Zero { .. } => Ordering::Less,
+                Class::Infinite { .. } => Ordering::Greater,
+                Class::Finite(x) => (x.exp, x.mant).cmp(&(0, 1 << 127)),
+                Class::Nan => unreachable!(),
+            };
+            match (magnitude, neg) {
+                (Ordering::Equal, _) => one,
+                (Ordering::Less, false) | (Ordering::Greater, true) => format.zero(false),
+                _ => format.infinity(false),
+            }
+        }
+        (Class::Zero { neg }, Class::Finite(y)) => {
+            let neg = neg && parity(y_bits, format) == Parity::Odd;
+            if y.neg {
+                format.infinity(neg)
+            } else {
+                format.zero(neg)
+            }
+        }
+        (Class::Infinite { neg }, Class::Finite(y)) => {
+            let neg = neg && parity(y_bits, format) == Parity::Odd;
+            if y.neg {
+                format.zero(neg)
+            } else {
+                format.infinity(neg)
+            }
+        }
+        (Class::Finite(x), Class::Finite(y)) => {
+            let neg = match (x.neg, parity(y_bits, format)) {
+                (false, _) => false,
+                (true, Parity::NotInteger) => return format.nan(),
+                (true, parity) => parity == Parity::Odd,
+            };
+            // |x|^y = 2^(y * (e + log2(m)))
+            let (e, ln_m) = ln_parts(x.abs());
+            let log2_x = Ext::from_int(e as i64).add(ln_m.mul(INV_LN2));
+            let result = round_exp2(y.mul(log2_x), format);
+            if neg {
+                result | format.sign_mask()
+            } else {
+                result
+            }
+        }
+    }
+}
@@ -0,0 +1,408 @@
+//! Extended precision float used for all intermediate results.
+//!
+//! The operations only use integer arithmetic, so they do not contain any float instructions
+//! and behave the same on every platform.
+
+/// A float with a 128 bit mantissa and an unbounded exponent.
+///
+/// The value is `(-1)^neg * mant * 2^(exp - 127)`, where `mant` has its highest bit set
+/// unless the value is zero. In other words, `exp` is the exponent of the highest bit.
+/// Results are truncated, so every operation has a relative error below `2^-126`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Ext {
+    pub(crate) neg: bool,
+    pub(crate) exp: i32,
+    pub(crate) mant: u128,
+}
+
+impl Ext {
+    pub(crate) const ZERO: Ext = Ext::raw(0, 0);
+    pub(crate) const ONE: Ext = Ext::raw(0, 1 << 127);
+
+    /// Creates a positive value from an already normalized mantissa
+    pub(crate) const fn raw(exp: i32, mant: u128) -> Ext {
+        Ext {
+            neg: false,
+            exp,
+            mant,
+        }
+    }
+
+    /// Creates the value `(-1)^neg * m * 2^scale`
+    pub(crate) fn new(neg: bool, m: u128, scale: i32) -> Ext {
+        if m == 0 {
+            return Ext::ZERO;
+        }
+        let lz = m.leading_zeros();
+        Ext {
+            neg,
+            exp: scale + 127 - lz as i32,
+            mant: m << lz,
+        }
+    }
+
+    /// Creates the value `(-1)^neg * (hi * 2^128 + lo) * 2^scale`
+    pub(crate) fn new_wide(neg: bool, hi: u128, lo: u128, scale: i32) -> Ext {
+        if hi == 0 {
+            return Ext::new(neg, lo, scale);
+        }
+        let lz = hi.leading_zeros();
+        let mant = if lz == 0 {
+            hi
+        } else {
+            hi << lz | lo >> (128 - lz)
+        };
+        Ext {
+            neg,
+            exp: scale + 255 - lz as i32,
+            mant,
+        }
+    }
+
+    pub(crate) fn from_int(v: i64) -> Ext {
+        Ext::new(v < 0, v.unsigned_abs() as u128, 0)
+    }
+
+    pub(crate) fn is_zero(self) -> bool {
+        self.mant == 0
+    }
+
+    pub(crate) fn neg(self) -> Ext {
+        Ext {
+            neg: !self.neg,
+            ..self
+        }
+    }
+
+    pub(crate) fn abs(self) -> Ext {
+        Ext { neg: false, ..self }
+    }
+
+    /// Multiplies by `2^n`
+    pub(crate) fn scale(self, n: i32) -> Ext {
+        if self.is_zero() {
+            return self;
+        }
+        Ext {
+            exp: self.exp + n,
+            ..self
+        }
+    }
+
+    pub(crate) fn add(self, other: Ext) -> Ext {
+        if other.is_zero() {
+            return self;
+        }
+        if self.is_zero() {
+            return other;
+        }
+        let (a, b) = if (self.exp, self.mant) >= (other.exp, other.mant) {
+            (self, other)
+        } else {
+            (other, self)
+        };
+
+        // align both mantissas in 256 bits, leaving one bit for the carry
+        let (a_hi, a_lo) = (a.mant >> 1, a.mant << 127);
+        let (b_hi, b_lo) = shr_wide(b.mant >> 1, b.mant << 127, (a.exp - b.exp) as u32);
+        let (hi, lo) = if a.neg == b.neg {
+            let (lo, carry) = a_lo.overflowing_add(b_lo);
+            (a_hi + b_hi + carry as u128, lo)
+        } else {
+            let (lo, borrow) = a_lo.overflowing_sub(b_lo);
+            (a_hi - b_hi - borrow as u128, lo)
+        };
+        if hi == 0 && lo == 0 {
+            return Ext::ZERO;
+        }
+        Ext::new_wide(a.neg, hi, lo, a.exp - 254)
+    }
+
+    pub(crate) fn sub(self, other: Ext) -> Ext {
+        self.add(other.neg())
+    }
+
+    pub(crate) fn mul(self, other: Ext) -> Ext {
+        if self.is_zero() || other.is_zero() {
+            return Ext::ZERO;
+        }
+        let (hi, lo) = mul_wide(self.mant, other.mant);
+        Ext::new_wide(self.neg != other.neg, hi, lo, self.exp + other.exp - 254)
+    }
+
+    pub(crate) fn div(self, other: Ext) -> Ext {
+        self.mul(other.recip())
+    }
+
+    /// Computes `1 / self` for a non-zero value
+    pub(crate) fn recip(self) -> Ext {
+        debug_assert!(!self.is_zero());
+        // 64 bit approximation of `2^192 / mant`, which is slightly too small
+        let top = (self.mant >> 64) + 1;
+        let approx = Ext::new(self.neg, u128::MAX / top, -65 - self.exp);
+        // one newton iteration doubles the precision
+        let two = Ext::raw(1, 1 << 127);
+        approx.mul(two.sub(self.mul(approx)))
+    }
+
+    /// Splits the value into the nearest integer and the remaining fraction in `[-0.5, 0.5]`.
+    /// The absolute value must be below `2^62`.
+    pub(crate) fn split_int(self) -> (i64, Ext) {
+        if self.exp < -1 {
+            return (0, self);
+        }
+        debug_assert!(self.exp < 62);
+        let rounded = ((self.mant >> (126 - self.exp)) + 1) >> 1;
+        let int = if self.neg {
+            -(rounded as i64)
+        } else {
+            rounded as i64
+        };
+        (int, self.sub(Ext::from_int(int)))
+    }
+
+    /// Rounds to the nearest float with the given format, ties to even.
+    /// Values that are too large become infinity, values that are too small become subnormal or zero.
+    pub(crate) fn round(self, format: Format) -> u64 {
+        let sign = if self.neg { format.sign_mask() } else { 0 };
+        if self.is_zero() {
+            return sign;
+        }
+        let biased = self.exp + format.bias();
+        if biased >= format.max_exp() {
+            return sign | format.exp_mask();
+        }
+
+        // number of mantissa bits that have to be dropped
+        let shift = if biased > 0 {
+            127 - format.frac_bits
+        } else {
+            128 - format.frac_bits + (-biased) as u32
+        };
+        let (m, rem, half) = match shift {
+            0..=127 => (
+                self.mant >> shift,
+                self.mant & ((1 << shift) - 1),
+                1 << (shift - 1),
+            ),
+            128 => (0, self.mant, 1 << 127),
+            _ => return sign,
+        };
+        let m = (m + (rem > half || (rem == half && m & 1 == 1)) as u128) as u64;
+
+        if biased > 0 {
+            // rounding up can carry into the exponent, which also handles overflow to infinity
+            sign + ((biased as u64) << format.frac_bits) + (m - (1 << format.frac_bits))
+        } else {
+            // rounding up can result in the smallest normal number
+            sign | m
+        }
+    }
+}
+
+/// Shifts a 256 bit number to the right
+fn shr_wide(hi: u128, lo: u128, shift: u32) -> (u128, u128) {
+    match shift {
+        0 => (hi, lo),
+        1..=127 => (hi >> shift, lo >> shift | hi << (128 - shift)),
+        128..=255 => (0, hi >> (shift - 128)),
+        _ => (0, 0),
+    }
+}
+
+/// Computes the full 256 bit product
+fn mul_wide(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+    let (a_hi, a_lo) = (a >> 64, a & MASK);
+    let (b_hi, b_lo) = (b >> 64, b & MASK);
+
+    let lo = a_lo * b_lo;
+    let mid1 = a_hi * b_lo;
+    let mid2 = a_lo * b_hi;
+    let hi = a_hi * b_hi;
+
+    let (mid, mid_carry) = mid1.overflowing_add(mid2);
+    let (lo, lo_carry) = lo.overflowing_add(mid << 64);
+    let hi = hi + (mid >> 64) + ((mid_carry as u128) << 64) + lo_carry as u128;
+    (hi, lo)
+}
+
+/// Bit layout of a binary float format
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Format {
+    pub(crate) frac_bits: u32,
+    pub(crate) exp_bits: u32,
+}
+
+pub(crate) const F32: Format = Format {
+    frac_bits: 23,
+    exp_bits: 8,
+};
+pub(crate) const F64: Format = Format {
+    frac_bits: 52,
+    exp_bits: 11,
+};
+
+/// A decoded float
+pub(crate) enum Class {
+    Nan,
+    Infinite { neg: bool },
+    Zero { neg: bool },
+    Finite(Ext),
+}
+
+impl Format {
+    pub(crate) fn sign_mask(self) -> u64 {
+        1 << (self.frac_bits + self.exp_bits)
+    }
+
+    pub(crate) fn exp_mask(self) -> u64 {
+        ((1 << self.exp_bits) - 1) << self.frac_bits
+    }
+
+    fn frac_mask(self) -> u64 {
+        (1 << self.frac_bits) - 1
+    }
+
+    fn bias(self) -> i32 {
+        (1 << (self.exp_bits - 1)) - 1
+    }
+
+    fn max_exp(self) -> i32 {
+        (1 << self.exp_bits) - 1
+    }
+
+    pub(crate) fn infinity(self, neg: bool) -> u64 {
+        self.exp_mask() | if neg { self.sign_mask() } else { 0 }
+    }
+
+    pub(crate) fn zero(self, neg: bool) -> u64 {
+        if neg {
+            self.sign_mask()
+        } else {
+            0
+        }
+    }
+
+    pub(crate) fn one(self) -> u64 {
+        (self.bias() as u64) << self.frac_bits
+    }
+
+    /// The canonical NaN
+    pub(crate) fn nan(self) -> u64 {
+        self.exp_mask() | 1 << (self.frac_bits - 1)
+    }
+
+    /// Turns a signaling NaN into a quiet one, keeping the payload
+    pub(crate) fn quiet(self, bits: u64) -> u64 {
+        bits | 1 << (self.frac_bits - 1)
+    }
+
+    pub(crate) fn classify(self, bits: u64) -> Class {
+        let neg = bits & self.sign_mask() != 0;
+        let exp = ((bits & self.exp_mask()) >> self.frac_bits) as i32;
+        let frac = bits & self.frac_mask();
+        if exp == self.max_exp() {
+            if frac == 0 {
+                Class::Infinite { neg }
+            } else {
+                Class::Nan
+            }
+        } else if exp == 0 {
+            if frac == 0 {
+                Class::Zero { neg }
+            } else {
+                Class::Finite(Ext::new(
+                    neg,
+                    frac as u128,
+                    1 - self.bias() - self.frac_bits as i32,
+                ))
+            }
+        } else {
+            let m = frac | 1 << self.frac_bits;
+            Class::Finite(Ext::new(
+                neg,
+                m as u128,
+                exp - self.bias() - self.frac_bits as i32,
+            ))
+        }
+    }
+}
+
+/// Evaluates the polynomial with the given coefficients (lowest degree first) at `x`
+pub(crate) fn horner(coeffs: &[Ext], x: Ext) -> Ext {
+    coeffs
+        .iter()
+        .rev()
+        .fold(Ext::ZERO, |acc, &c| acc.mul(x).add(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ext(v: f64) -> Ext {
+        match F64.classify(v.to_bits()) {
+            Class::Finite(v) => v,
+            Class::Zero { .. } => Ext::ZERO,
+            _ => panic!("not finite"),
+        }
+    }
+
+    fn f64(v: Ext) -> f64 {
+        f64::from_bits(v.round(F64))
+    }
+
+    #[test]
+    fn roundtrip() {
+        for v in [1.0, -1.5, 3.0e-310, f64::MAX, f64::MIN_POSITIVE, 123456.789] {
+            assert_eq!(f64(ext(v)), v);
+        }
+        assert_eq!(f64(Ext::ZERO), 0.0);
+    }
+
+    #[test]
+    fn exact_operations() {
+        assert_eq!(f64(ext(1.5).add(ext(2.25))), 3.75);
+        assert_eq!(f64(ext(1.5).sub(ext(2.25))), -0.75);
+        assert_eq!(f64(ext(1.0).sub(ext(1.0))), 0.0);
+        assert_eq!(f64(ext(-3.0).mul(ext(0.5))), -1.5);
+        assert_eq!(
+            f64(ext(1.0).sub(ext(1.0 - f64::EPSILON / 2.0))),
+            f64::EPSILON / 2.0
+        );
+        assert_eq!(f64(ext(7.0).div(ext(2.0))), 3.5);
+        assert_eq!(f64(ext(1.0).div(ext(3.0))), 1.0 / 3.0);
+    }
+
+    #[test]
+    fn rounding() {
+        // ties to even
+        let half_ulp = Ext::ONE.scale(-53);
+        assert_eq!(f64(Ext::ONE.add(half_ulp)), 1.0);
+        assert_eq!(
+            f64(Ext::ONE.add(half_ulp).add(half_ulp.scale(-60))),
+            1.0 + f64::EPSILON
+        );
+        // overflow and underflow
+        assert_eq!(f64(Ext::ONE.scale(1024)), f64::INFINITY);
+        assert_eq!(f64(Ext::ONE.scale(-1075)), 0.0);
+        assert_eq!(
+            f64(Ext::ONE.scale(-1075).add(Ext::ONE.scale(-1100))),
+            5e-324
+        );
+        assert_eq!(
+            f64(ext(f64::MIN_POSITIVE).sub(ext(5e-324))),
+            2.225073858507201e-308
+        );
+        assert_eq!(f32::from_bits(ext(0.1).round(F32) as u32), 0.1f32);
+    }
+
+    #[test]
+    fn split_int() {
+        let (int, frac) = ext(-2.75).split_int();
+        assert_eq!((int, f64(frac)), (-3, 0.25));
+        let (int, frac) = ext(0.25).split_int();
+        assert_eq!((int, f64(frac)), (0, 0.25));
+    }
+}
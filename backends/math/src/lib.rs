@@ -0,0 +1,61 @@
+//! Deterministic transcendental functions for use together with a soft float backend.
+//!
+//! Compiling `f64::sin` and friends to wasm pulls in a libm implementation, which consists of long
+//! chains of float instructions that are all replaced by soft float calls. Its results depend on the libm version.
+//! The functions in this crate are computed with integer arithmetic only, using an extended precision format
+//! with a 128 bit mantissa, and are exported as `__wasm_soft_float_*` functions, e.g. `__wasm_soft_float_f_64_sin`.
+//! The transpiler can redirect calls to the libm functions to them (see `--redirect-libm`).
+//!
+//! | function | `f64` | `f32` | libm |
+//! |----------|-------|-------|------|
+//! | `sin(x)` | `f_64_sin` | `f_32_sin` | `sin`, `sinf` |
+//! | `cos(x)` | `f_64_cos` | `f_32_cos` | `cos`, `cosf` |
+//! | `tan(x)` | `f_64_tan` | `f_32_tan` | `tan`, `tanf` |
+//! | `e^x` | `f_64_exp` | `f_32_exp` | `exp`, `expf` |
+//! | `ln(x)` | `f_64_ln` | `f_32_ln` | `log`, `logf` |
+//! | `log2(x)` | `f_64_log_2` | `f_32_log_2` | `log2`, `log2f` |
+//! | `log10(x)` | `f_64_log_10` | `f_32_log_10` | `log10`, `log10f` |
+//! | `x^y` | `f_64_pow` | `f_32_pow` | `pow`, `powf` |
+//!
+//! # Accuracy
+//!
+//! All intermediate results have a relative error below `2^-100`, which is only rounded once to the result format
+//! (round to nearest, ties to even). So the error is below 0.5001 ulp, and results are correctly rounded unless
+//! the exact result is extremely close to the middle between two floats. Exact results like `exp(0)`, `log2(8)` or
+//! `pow(3, 2)` are always returned exactly. The arguments of `sin`, `cos` and `tan` are reduced using enough bits
+//! of `pi`, so this also holds for huge arguments.
+//!
+//! Special values follow the C standard (Annex F), e.g. `pow(x, 0) = 1` even for NaN,
+//! `ln(0) = -inf` and `ln(-1) = NaN`. NaN arguments are passed through, but made quiet.
+
+mod exp;
+mod ext;
+mod trig;
+
+use ext::{F32, F64};
+
+macro_rules! export_functions {
+    ($($f32: ident, $f64: ident => $func: path [$($arg: ident),*];)*) => {
+        $(
+            #[cfg_attr(feature = "export", no_mangle)]
+            pub extern "C" fn $f32($($arg: u32),*) -> u32 {
+                $func($($arg as u64,)* F32) as u32
+            }
+            #[cfg_attr(feature = "export", no_mangle)]
+            pub extern "C" fn $f64($($arg: u64),*) -> u64 {
+                $func($($arg,)* F64)
+            }
+        )*
+    };
+}
+
+export_functions!(
+    __wasm_soft_float_f_32_sin, __wasm_soft_float_f_64_sin => trig::sin[v];
+    __wasm_soft_float_f_32_cos, __wasm_soft_float_f_64_cos => trig::cos[v];
+    __wasm_soft_float_f_32_tan, __wasm_soft_float_f_64_tan => trig::tan[v];
+    __wasm_soft_float_f_32_exp, __wasm_soft_float_f_64_exp => exp::exp[v];
+    __wasm_soft_float_f_32_ln, __wasm_soft_float_f_64_ln => exp::ln[v];
+    __wasm_soft_float_f_32_log_2, __wasm_soft_float_f_64_log_2 => exp::log2[v];
+    __wasm_soft_float_f_32_log_10, __wasm_soft_float_f_64_log_10 => exp::log10[v];
+    __wasm_soft_float_f_32_pow, __wasm_soft_float_f_64_pow => exp::pow[x, y];
+);
@@ -0,0 +1,146 @@
+//! Trigonometric functions.
+
+use crate::{
+    exp::INV_FACT,
+    ext::{horner, Class, Ext, Format},
+};
+
+const PI_2: Ext = Ext::raw(0, 0xc90fdaa2_2168c234_c4c6628b_80dc1cd1);
+
+/// The first 1280 fraction bits of `2 / pi`, enough to reduce every finite `f64`
+const TWO_OVER_PI: [u64; 20] = [
+    0xa2f9836e4e441529,
+    0xfc2757d1f534ddc0,
+    0xdb6295993c439041,
+    0xfe5163abdebbc561,
+    0xb7246e3a424dd2e0,
+    0x06492eea09d1921c,
+    0xfe1deb1cb129a73e,
+    0xe88235f52ebb4484,
+    0xe99c7026b45f7e41,
+    0x3991d639835339f4,
+    0x9c845f8bbdf9283b,
+    0x1ff897ffde05980f,
+    0xef2f118b5a0a6d1f,
+    0x6d367ecf27cb09b7,
+    0x4f463f669e5fea2d,
+    0x7527bac7ebe5f17b,
+    0x3d0739f78a5292ea,
+    0x6bfb5fb11f8d5d08,
+    0x56033046fc7b6bab,
+    0xf0cfbc209af4361d,
+];
+
+/// Returns 192 bits of `2 / pi`, starting at the given fraction bit (counting from 0)
+fn two_over_pi_bits(start: usize) -> (u64, u128) {
+    let (word, offset) = (start / 64, start % 64);
+    let bits = |i: usize| {
+        if offset == 0 {
+            TWO_OVER_PI[word + i]
+        } else {
+            TWO_OVER_PI[word + i] << offset | TWO_OVER_PI[word + i + 1] >> (64 - offset)
+        }
+    };
+    (bits(0), (bits(1) as u128) << 64 | bits(2) as u128)
+}
+
+/// Reduces a positive value to `r` in `[-pi/4, pi/4]` with `x = n * pi/2 + r`, returning `n mod 4` and `r`.
+///
+/// This multiplies `x` exactly with the bits of `2 / pi` that can influence the result,
+/// so even huge arguments are reduced with an absolute error below `2^-136`.
+fn reduce(x: Ext) -> (u32, Ext) {
+    // x = m * 2^e with an integer m < 2^53
+    let m = (x.mant >> 75) as u64;
+    let e = x.exp - 52;
+    // bits of 2/pi that result in multiples of 4 do not influence the result
+    let start = (e - 2).max(0) as usize;
+    let (w_hi, w_lo) = two_over_pi_bits(start);
+
+    // p = m * w, which is below 2^245
+    let low = m as u128 * (w_lo as u64) as u128;
+    let mid = m as u128 * (w_lo >> 64);
+    let (p_lo, carry) = low.overflowing_add(mid << 64);
+    let p_hi = m as u128 * w_hi as u128 + (mid >> 64) + carry as u128;
+
+    // x * 2/pi = p * 2^-frac_bits
+    let frac_bits = (start as i32 + 192 - e) as u32;
+    if frac_bits >= 256 {
+        // x * 2/pi < 0.5
+        return (
+            0,
+            Ext::new_wide(false, p_hi, p_lo, -(frac_bits as i32)).mul(PI_2),
+        );
+    }
+    let shift = frac_bits - 128;
+    let int = (p_hi >> shift) as u32;
+    let f_hi = p_hi & ((1 << shift) - 1);
+    let (n, neg, f_hi, f_lo) = if (f_hi >> (shift - 1)) & 1 == 0 {
+        (int, false, f_hi, p_lo)
+    } else {
+        // round up, so the fraction becomes negative
+        let f_lo = p_lo.wrapping_neg();
+        let f_hi = (!f_hi).wrapping_add((p_lo == 0) as u128) & ((1 << shift) - 1);
+        (int.wrapping_add(1), true, f_hi, f_lo)
+    };
+    let r = Ext::new_wide(neg, f_hi, f_lo, -(frac_bits as i32)).mul(PI_2);
+    (n % 4, r)
+}
+
+/// Computes `sin(r)` and `cos(r)` for `|r| <= pi/4` using their taylor series,
+/// which are accurate to 2^-72 after 11 and 12 terms
+fn sin_cos(r: Ext) -> (Ext, Ext) {
+    let z = r.mul(r).neg();
+    let sin: [Ext; 11] = core::array::from_fn(|k| INV_FACT[2 * k + 1]);
+    let cos: [Ext; 12] = core::array::from_fn(|k| INV_FACT[2 * k]);
+    (r.mul(horner(&sin, z)), horner(&cos, z))
+}
+
+/// Evaluates a trigonometric function, handling all special cases.
+/// `f` gets the quadrant and the sine and cosine of the reduced argument.
+fn trig(x: u64, format: Format, odd: bool, f: impl Fn(u32, Ext, Ext) -> Ext) -> u64 {
+    match format.classify(x) {
+        Class::Nan => format.quiet(x),
+        Class::Infinite { .. } => format.nan(),
+        // odd functions keep the sign of zero
+        Class::Zero { .. } if odd => x,
+        Class::Zero { .. } => format.one(),
+        Class::Finite(x) => {
+            let (n, r) = reduce(x.abs());
+            let (sin, cos) = sin_cos(r);
+            let result = f(n, sin, cos);
+            if odd && x.neg {
+                result.neg().round(format)
+            } else {
+                result.round(format)
+            }
+        }
+    }
+}
+
+pub(crate) fn sin(x: u64, format: Format) -> u64 {
+    trig(x, format, true, |n, sin, cos| match n {
+        0 => sin,
+        1 => cos,
+        2 => sin.neg(),
+        _ => cos.neg(),
+    })
+}
+
+pub(crate) fn cos(x: u64, format: Format) -> u64 {
+    trig(x, format, false, |n, sin, cos| match n {
+        0 => cos,
+        1 => sin.neg(),
+        2 => cos.neg(),
+        _ => sin,
+    })
+}
+
+pub(crate) fn tan(x: u64, format: Format) -> u64 {
+    trig(x, format, true, |n, sin, cos| {
+        if n % 2 == 0 {
+            sin.div(cos)
+        } else {
+            cos.div(sin).neg()
+        }
+    })
+}
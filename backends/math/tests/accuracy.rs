@@ -0,0 +1,213 @@
+//! Compares the transcendental functions with the ones of the platform's libm,
+//! which are accurate to less than 1 ulp as well.
+
+use quickcheck::{quickcheck, TestResult};
+use wasm_soft_float_math::*;
+
+/// Maps floats to integers, so that neighbouring floats are neighbouring integers
+fn ordered64(v: f64) -> i64 {
+    let bits = v.to_bits() as i64;
+    if bits < 0 {
+        i64::MIN - bits
+    } else {
+        bits
+    }
+}
+
+fn ordered32(v: f32) -> i64 {
+    let bits = v.to_bits() as i32;
+    if bits < 0 {
+        (i32::MIN - bits) as i64
+    } else {
+        bits as i64
+    }
+}
+
+fn close64(actual: u64, expected: f64) -> bool {
+    let actual = f64::from_bits(actual);
+    if actual.is_nan() || expected.is_nan() {
+        return actual.is_nan() && expected.is_nan();
+    }
+    (ordered64(actual) - ordered64(expected)).abs() <= 1
+}
+
+fn close32(actual: u32, expected: f64) -> bool {
+    let actual = f32::from_bits(actual);
+    let expected = expected as f32;
+    if actual.is_nan() || expected.is_nan() {
+        return actual.is_nan() && expected.is_nan();
+    }
+    (ordered32(actual) - ordered32(expected)).abs() <= 1
+}
+
+macro_rules! unary_tests {
+    ($($name: ident: $f32: ident, $f64: ident, $std: expr;)*) => {
+        $(
+            mod $name {
+                use super::*;
+
+                quickcheck! {
+                    fn any_f64(bits: u64) -> bool {
+                        let v = f64::from_bits(bits);
+                        close64($f64(bits), $std(v))
+                    }
+
+                    fn small_f64(v: f64) -> bool {
+                        close64($f64(v.to_bits()), $std(v))
+                    }
+
+                    fn any_f32(bits: u32) -> bool {
+                        let v = f32::from_bits(bits);
+                        close32($f32(bits), $std(v as f64))
+                    }
+                }
+            }
+        )*
+    };
+}
+
+unary_tests!(
+    sin: __wasm_soft_float_f_32_sin, __wasm_soft_float_f_64_sin, f64::sin;
+    cos: __wasm_soft_float_f_32_cos, __wasm_soft_float_f_64_cos, f64::cos;
+    tan: __wasm_soft_float_f_32_tan, __wasm_soft_float_f_64_tan, f64::tan;
+    exp: __wasm_soft_float_f_32_exp, __wasm_soft_float_f_64_exp, f64::exp;
+    ln: __wasm_soft_float_f_32_ln, __wasm_soft_float_f_64_ln, f64::ln;
+    log2: __wasm_soft_float_f_32_log_2, __wasm_soft_float_f_64_log_2, f64::log2;
+    log10: __wasm_soft_float_f_32_log_10, __wasm_soft_float_f_64_log_10, f64::log10;
+);
+
+quickcheck! {
+    fn pow_f64(x: f64, y: f64) -> bool {
+        close64(__wasm_soft_float_f_64_pow(x.to_bits(), y.to_bits()), x.powf(y))
+    }
+
+    fn pow_f64_any(x: u64, y: u64) -> bool {
+        let expected = f64::from_bits(x).powf(f64::from_bits(y));
+        close64(__wasm_soft_float_f_64_pow(x, y), expected)
+    }
+
+    fn pow_f64_integer_exponent(x: f64, y: i16) -> bool {
+        close64(__wasm_soft_float_f_64_pow(x.to_bits(), (y as f64).to_bits()), x.powi(y as i32))
+    }
+
+    fn pow_f32(x: f32, y: f32) -> bool {
+        let expected = (x as f64).powf(y as f64);
+        close32(__wasm_soft_float_f_32_pow(x.to_bits(), y.to_bits()), expected)
+    }
+
+    fn sin_near_multiples_of_pi(k: i32, offset: i8) -> TestResult {
+        if k == 0 {
+            return TestResult::discard();
+        }
+        let v = f64::from_bits((k as f64 * std::f64::consts::PI).to_bits().wrapping_add(offset as u64));
+        TestResult::from_bool(close64(__wasm_soft_float_f_64_sin(v.to_bits()), v.sin()))
+    }
+}
+
+#[test]
+fn special_values() {
+    let f64 = |v: u64| f64::from_bits(v);
+    let inf = f64::INFINITY.to_bits();
+    let nan = f64::NAN.to_bits();
+
+    assert_eq!(
+        __wasm_soft_float_f_64_sin((-0.0f64).to_bits()),
+        (-0.0f64).to_bits()
+    );
+    assert!(f64(__wasm_soft_float_f_64_sin(inf)).is_nan());
+    assert!(f64(__wasm_soft_float_f_64_cos(nan)).is_nan());
+    assert_eq!(
+        f64(__wasm_soft_float_f_64_exp((-f64::INFINITY).to_bits())),
+        0.0
+    );
+    assert_eq!(
+        f64(__wasm_soft_float_f_64_exp(1000f64.to_bits())),
+        f64::INFINITY
+    );
+    assert_eq!(f64(__wasm_soft_float_f_64_exp((-1000f64).to_bits())), 0.0);
+    assert_eq!(f64(__wasm_soft_float_f_64_ln(0)), f64::NEG_INFINITY);
+    assert!(f64(__wasm_soft_float_f_64_ln((-1f64).to_bits())).is_nan());
+    assert_eq!(f64(__wasm_soft_float_f_64_pow(nan, 0)), 1.0);
+    assert_eq!(f64(__wasm_soft_float_f_64_pow(1f64.to_bits(), nan)), 1.0);
+    assert_eq!(f64(__wasm_soft_float_f_64_pow((-1f64).to_bits(), inf)), 1.0);
+    assert!(f64(__wasm_soft_float_f_64_pow(
+        (-8f64).to_bits(),
+        (1.0f64 / 3.0).to_bits()
+    ))
+    .is_nan());
+    assert_eq!(
+        __wasm_soft_float_f_64_pow((-0.0f64).to_bits(), (-3f64).to_bits()),
+        (-f64::INFINITY).to_bits()
+    );
+    assert_eq!(
+        __wasm_soft_float_f_64_pow((-f64::INFINITY).to_bits(), 3f64.to_bits()),
+        (-f64::INFINITY).to_bits()
+    );
+}
+
+#[test]
+fn exact_results() {
+    let f64 = |v: u64| f64::from_bits(v);
+    let f32 = |v: u32| f32::from_bits(v);
+
+    assert_eq!(f64(__wasm_soft_float_f_64_exp(0)), 1.0);
+    assert_eq!(f64(__wasm_soft_float_f_64_cos(0)), 1.0);
+    assert_eq!(f64(__wasm_soft_float_f_64_ln(1f64.to_bits())), 0.0);
+    assert_eq!(f64(__wasm_soft_float_f_64_log_2(1024f64.to_bits())), 10.0);
+    assert_eq!(f64(__wasm_soft_float_f_64_log_2(0.125f64.to_bits())), -3.0);
+    assert_eq!(f64(__wasm_soft_float_f_64_log_10(1e22f64.to_bits())), 22.0);
+    assert_eq!(
+        f64(__wasm_soft_float_f_64_pow(3f64.to_bits(), 2f64.to_bits())),
+        9.0
+    );
+    assert_eq!(
+        f64(__wasm_soft_float_f_64_pow(
+            (-2f64).to_bits(),
+            3f64.to_bits()
+        )),
+        -8.0
+    );
+    assert_eq!(
+        f64(__wasm_soft_float_f_64_pow(
+            2f64.to_bits(),
+            (-1074f64).to_bits()
+        )),
+        5e-324
+    );
+    assert_eq!(
+        f64(__wasm_soft_float_f_64_pow(
+            16f64.to_bits(),
+            0.5f64.to_bits()
+        )),
+        4.0
+    );
+    assert_eq!(
+        f32(__wasm_soft_float_f_32_pow(10f32.to_bits(), 7f32.to_bits())),
+        1e7
+    );
+    assert_eq!(f32(__wasm_soft_float_f_32_log_2(0.5f32.to_bits())), -1.0);
+}
+
+#[test]
+fn huge_arguments() {
+    // 6381956970095103 * 2^797 is the double closest to a multiple of pi/2,
+    // see "Argument Reduction for Huge Arguments: Good to the Last Bit" by K. C. Ng
+    let v = 0x7506_ac5b_262c_a1ff;
+    assert_eq!(
+        __wasm_soft_float_f_64_cos(v),
+        (-4.687165924254628e-19f64).to_bits()
+    );
+    assert_eq!(
+        __wasm_soft_float_f_64_tan(v),
+        (-2.133485385753704e18f64).to_bits()
+    );
+    assert_eq!(__wasm_soft_float_f_64_sin(v), 1f64.to_bits());
+    assert!(close64(
+        __wasm_soft_float_f_64_sin(f64::MAX.to_bits()),
+        f64::MAX.sin()
+    ));
+    assert!(close64(
+        __wasm_soft_float_f_64_tan(1e300f64.to_bits()),
+        1e300f64.tan()
+    ));
+}
@@ -0,0 +1,204 @@
+use wasm_soft_float_utils::backend::SoftFloatBackend;
+use wasm_soft_float_utils::float::{F32, F64};
+use wasm_soft_float_utils::trunc;
+
+// special functions needed for the softfp crate, which links to them even if the operations are not exported
+#[no_mangle]
+fn softfp_get_rounding_mode() -> softfp::RoundingMode {
+    softfp::RoundingMode::TiesToEven
+}
+#[no_mangle]
+fn softfp_set_exception_flags(_flags: softfp::ExceptionFlags) {}
+
+/// The backend based on the `softfp` crate
+pub struct SoftFp;
+
+impl SoftFloatBackend for SoftFp {
+    // `softfp` cannot round to integers
+    fn f_32_ceil(v: F32) -> F32 {
+        v.ceil()
+    }
+    fn f_32_floor(v: F32) -> F32 {
+        v.floor()
+    }
+    fn f_32_trunc(v: F32) -> F32 {
+        v.trunc()
+    }
+    fn f_32_nearest(v: F32) -> F32 {
+        v.nearest()
+    }
+    fn f_32_sqrt(v: F32) -> F32 {
+        bits_32(soft_32(v).square_root())
+    }
+    fn f_64_ceil(v: F64) -> F64 {
+        v.ceil()
+    }
+    fn f_64_floor(v: F64) -> F64 {
+        v.floor()
+    }
+    fn f_64_trunc(v: F64) -> F64 {
+        v.trunc()
+    }
+    fn f_64_nearest(v: F64) -> F64 {
+        v.nearest()
+    }
+    fn f_64_sqrt(v: F64) -> F64 {
+        bits_64(soft_64(v).square_root())
+    }
+    fn i_32_trunc_sf_32(v: F32) -> i32 {
+        trunc::f32_to_int(v.to_bits())
+    }
+    fn i_32_trunc_uf_32(v: F32) -> u32 {
+        trunc::f32_to_int(v.to_bits())
+    }
+    fn i_32_trunc_sf_64(v: F64) -> i32 {
+        trunc::f64_to_int(v.to_bits())
+    }
+    fn i_32_trunc_uf_64(v: F64) -> u32 {
+        trunc::f64_to_int(v.to_bits())
+    }
+    fn i_64_trunc_sf_32(v: F32) -> i64 {
+        trunc::f32_to_int(v.to_bits())
+    }
+    fn i_64_trunc_uf_32(v: F32) -> u64 {
+        trunc::f32_to_int(v.to_bits())
+    }
+    fn i_64_trunc_sf_64(v: F64) -> i64 {
+        trunc::f64_to_int(v.to_bits())
+    }
+    fn i_64_trunc_uf_64(v: F64) -> u64 {
+        trunc::f64_to_int(v.to_bits())
+    }
+    fn f_32_convert_si_32(v: i32) -> F32 {
+        bits_32(softfp::F32::convert_from_sint(v as u32))
+    }
+    fn f_32_convert_ui_32(v: u32) -> F32 {
+        bits_32(softfp::F32::convert_from_uint(v))
+    }
+    fn f_32_convert_si_64(v: i64) -> F32 {
+        bits_32(softfp::F32::convert_from_sint(v as u64))
+    }
+    fn f_32_convert_ui_64(v: u64) -> F32 {
+        bits_32(softfp::F32::convert_from_uint(v))
+    }
+    fn f_32_demote_f_64(v: F64) -> F32 {
+        bits_32(soft_64(v).convert_format())
+    }
+    fn f_64_convert_si_32(v: i32) -> F64 {
+        bits_64(softfp::F64::convert_from_sint(v as u32))
+    }
+    fn f_64_convert_ui_32(v: u32) -> F64 {
+        bits_64(softfp::F64::convert_from_uint(v))
+    }
+    fn f_64_convert_si_64(v: i64) -> F64 {
+        bits_64(softfp::F64::convert_from_sint(v as u64))
+    }
+    fn f_64_convert_ui_64(v: u64) -> F64 {
+        bits_64(softfp::F64::convert_from_uint(v))
+    }
+    fn f_64_promote_f_32(v: F32) -> F64 {
+        bits_64(soft_32(v).convert_format())
+    }
+    fn i_32_trunc_s_sat_f_32(v: F32) -> i32 {
+        trunc::f32_to_int_sat(v.to_bits())
+    }
+    fn i_32_trunc_u_sat_f_32(v: F32) -> u32 {
+        trunc::f32_to_int_sat(v.to_bits())
+    }
+    fn i_32_trunc_s_sat_f_64(v: F64) -> i32 {
+        trunc::f64_to_int_sat(v.to_bits())
+    }
+    fn i_32_trunc_u_sat_f_64(v: F64) -> u32 {
+        trunc::f64_to_int_sat(v.to_bits())
+    }
+    fn i_64_trunc_s_sat_f_32(v: F32) -> i64 {
+        trunc::f32_to_int_sat(v.to_bits())
+    }
+    fn i_64_trunc_u_sat_f_32(v: F32) -> u64 {
+        trunc::f32_to_int_sat(v.to_bits())
+    }
+    fn i_64_trunc_s_sat_f_64(v: F64) -> i64 {
+        trunc::f64_to_int_sat(v.to_bits())
+    }
+    fn i_64_trunc_u_sat_f_64(v: F64) -> u64 {
+        trunc::f64_to_int_sat(v.to_bits())
+    }
+    fn f_32_lt(a: F32, b: F32) -> bool {
+        soft_32(a) < soft_32(b)
+    }
+    fn f_32_gt(a: F32, b: F32) -> bool {
+        soft_32(a) > soft_32(b)
+    }
+    fn f_32_le(a: F32, b: F32) -> bool {
+        soft_32(a) <= soft_32(b)
+    }
+    fn f_32_ge(a: F32, b: F32) -> bool {
+        soft_32(a) >= soft_32(b)
+    }
+    fn f_64_lt(a: F64, b: F64) -> bool {
+        soft_64(a) < soft_64(b)
+    }
+    fn f_64_gt(a: F64, b: F64) -> bool {
+        soft_64(a) > soft_64(b)
+    }
+    fn f_64_le(a: F64, b: F64) -> bool {
+        soft_64(a) <= soft_64(b)
+    }
+    fn f_64_ge(a: F64, b: F64) -> bool {
+        soft_64(a) >= soft_64(b)
+    }
+    fn f_32_add(a: F32, b: F32) -> F32 {
+        bits_32(soft_32(a) + soft_32(b))
+    }
+    fn f_32_sub(a: F32, b: F32) -> F32 {
+        bits_32(soft_32(a) - soft_32(b))
+    }
+    fn f_32_mul(a: F32, b: F32) -> F32 {
+        bits_32(soft_32(a) * soft_32(b))
+    }
+    fn f_32_div(a: F32, b: F32) -> F32 {
+        bits_32(soft_32(a) / soft_32(b))
+    }
+    fn f_32_min(a: F32, b: F32) -> F32 {
+        // `softfp` implements IEEE 754 `minNum` and `maxNum`,
+        // which ignore NaN operands and do not order the zeroes
+        a.min(b)
+    }
+    fn f_32_max(a: F32, b: F32) -> F32 {
+        a.max(b)
+    }
+    fn f_64_add(a: F64, b: F64) -> F64 {
+        bits_64(soft_64(a) + soft_64(b))
+    }
+    fn f_64_sub(a: F64, b: F64) -> F64 {
+        bits_64(soft_64(a) - soft_64(b))
+    }
+    fn f_64_mul(a: F64, b: F64) -> F64 {
+        bits_64(soft_64(a) * soft_64(b))
+    }
+    fn f_64_div(a: F64, b: F64) -> F64 {
+        bits_64(soft_64(a) / soft_64(b))
+    }
+    fn f_64_min(a: F64, b: F64) -> F64 {
+        a.min(b)
+    }
+    fn f_64_max(a: F64, b: F64) -> F64 {
+        a.max(b)
+    }
+}
+
+fn soft_32(v: F32) -> softfp::F32 {
+    softfp::F32::new(v.to_bits())
+}
+
+fn soft_64(v: F64) -> softfp::F64 {
+    softfp::F64::new(v.to_bits())
+}
+
+fn bits_32(v: softfp::F32) -> F32 {
+    F32::from_bits(v.0)
+}
+
+fn bits_64(v: softfp::F64) -> F64 {
+    F64::from_bits(v.0)
+}
@@ -0,0 +1,10 @@
+use wasm_soft_float_softfp::SoftFp;
+use wasm_soft_float_utils::{exhaustive, implementations};
+
+/// Checks every input of the operations with 32 bit operands against the native ones.
+/// This takes a long time, run it with `cargo test --release --test exhaustive -- --ignored`.
+#[test]
+#[ignore]
+fn exhaustive() {
+    exhaustive::check(&implementations!(SoftFp));
+}
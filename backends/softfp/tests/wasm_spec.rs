@@ -1,3 +1,3 @@
-use wasm_soft_float_softfp::*;
+use wasm_soft_float_softfp::SoftFp;
 
-wasm_soft_float_utils::impl_tests!();
+wasm_soft_float_utils::impl_tests!(SoftFp);
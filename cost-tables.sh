@@ -0,0 +1,11 @@
+#!/usr/bin/env sh
+
+# Compiles the backends and measures the instructions executed by their operations.
+# The cost tables are stored in costs-<backend>.json
+
+set -e
+
+for backend in bs apfloat softfp; do
+    cargo build --release --lib --target wasm32-unknown-unknown -p wasm-soft-float-$backend --locked
+    cargo run --bin wasm-float-transpiler -- costs ./target/wasm32-unknown-unknown/release/wasm_soft_float_$backend.wasm -o costs-$backend.json
+done
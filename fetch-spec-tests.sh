@@ -0,0 +1,16 @@
+#!/usr/bin/env sh
+
+# Downloads the float related modules of the WebAssembly spec test suite
+# into wasm-float-transpiler/tests/spec, where they are run by `cargo test`.
+# Set TESTSUITE_REV to use another revision of https://github.com/WebAssembly/testsuite
+
+set -e
+
+rev=${TESTSUITE_REV:-main}
+dir=wasm-float-transpiler/tests/spec
+mkdir -p $dir
+
+for test in const conversions f32 f32_bitwise f32_cmp f64 f64_bitwise f64_cmp \
+    float_exprs float_literals float_memory float_misc; do
+    curl -fsSL https://raw.githubusercontent.com/WebAssembly/testsuite/$rev/$test.wast -o $dir/$test.wast
+done
@@ -0,0 +1,11 @@
+#!/usr/bin/env sh
+
+# Regenerates the golden test vectors of the backends in backends/<backend>/tests/golden.bin
+# Only run this after an intended change of their results.
+# Pass backend names to only regenerate some of them, e.g. `./golden-vectors.sh bs`.
+
+set -e
+
+for backend in ${*:-bs apfloat softfp}; do
+    UPDATE_GOLDEN_VECTORS=1 cargo test --release -p wasm-soft-float-$backend --test golden
+done
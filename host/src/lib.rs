@@ -0,0 +1,82 @@
+//! Native implementation of the soft float import ABI for the [`wasmi`] interpreter.
+//!
+//! Modules transpiled with `--host-imports` do not contain the soft float backend,
+//! but import every operation they use from the `wasm_soft_float` module instead.
+//! [`define_soft_float`] provides these imports by calling the exact same backend functions that
+//! would otherwise be compiled into the module, so the results are bit-identical.
+//!
+//! The backend is chosen using the `bs` (default), `apfloat` or `softfp` feature.
+
+#[cfg(any(
+    all(feature = "bs", feature = "apfloat"),
+    all(feature = "bs", feature = "softfp"),
+    all(feature = "apfloat", feature = "softfp"),
+))]
+compile_error!("only one of the `bs`, `apfloat` and `softfp` features can be enabled");
+#[cfg(not(any(feature = "bs", feature = "apfloat", feature = "softfp")))]
+compile_error!("one of the `bs`, `apfloat` and `softfp` features must be enabled");
+
+#[cfg(feature = "apfloat")]
+pub use wasm_soft_float_apfloat::Apfloat as Backend;
+#[cfg(feature = "bs")]
+pub use wasm_soft_float_bs::BerkeleySoftFloat as Backend;
+#[cfg(feature = "softfp")]
+pub use wasm_soft_float_softfp::SoftFp as Backend;
+
+use wasm_soft_float_utils::{
+    float::{F32, F64},
+    for_each_operation,
+    ops::{Bits, Operation},
+};
+use wasmi::{
+    core::{Trap, TrapCode},
+    errors::LinkerError,
+    Linker,
+};
+
+pub use wasm_soft_float_utils::DEFAULT_IMPORT_MODULE;
+
+/// Defines all operations of the selected backend in `linker` as imports of `module`,
+/// named like the operation (e.g. `f_32_add`).
+pub fn define_soft_float<T>(linker: &mut Linker<T>, module: &str) -> Result<(), LinkerError> {
+    macro_rules! define {
+        (@op $name: ident ($a: ident) -> $result: ident) => {{
+            let op = Operation::by_name(stringify!($name)).unwrap();
+            // traps like the native instructions instead of calling the backend with an invalid operand,
+            // which would panic through the interpreter
+            linker.func_wrap(module, op.name, move |v: $a| -> Result<$result, Trap> {
+                use wasm_soft_float_utils::backend::{SoftFloatBackend, WasmAbi};
+                if op.traps(&[v.into_bits()]) {
+                    return Err(trap(op, v.into_bits()));
+                }
+                Ok(<Backend as SoftFloatBackend>::$name(WasmAbi::from_abi(v)).into_abi())
+            })?;
+        }};
+        (@op $name: ident ($a: ident, $b: ident) -> $result: ident) => {
+            linker.func_wrap(module, stringify!($name), |a: $a, b: $b| -> $result {
+                use wasm_soft_float_utils::backend::{SoftFloatBackend, WasmAbi};
+                <Backend as SoftFloatBackend>::$name(WasmAbi::from_abi(a), WasmAbi::from_abi(b))
+                    .into_abi()
+            })?;
+        };
+        ($($name: ident: $instr: ident $text: literal [$($opcode: literal),+] ($($param: ident),*) -> $result: ident, $category: ident;)*) => {
+            $(define!(@op $name ($($param),*) -> $result);)*
+        };
+    }
+
+    for_each_operation!(define);
+    Ok(())
+}
+
+/// The trap of the native instruction for an operand of a truncation that is out of range
+fn trap(op: &Operation, bits: u64) -> Trap {
+    let nan = match op.operand() {
+        "f32" => F32::from_bits(bits as u32).is_nan(),
+        _ => F64::from_bits(bits).is_nan(),
+    };
+    Trap::from(if nan {
+        TrapCode::BadConversionToInteger
+    } else {
+        TrapCode::IntegerOverflow
+    })
+}
@@ -0,0 +1,131 @@
+use quickcheck::quickcheck;
+use wasm_soft_float_host::{define_soft_float, Backend, DEFAULT_IMPORT_MODULE};
+use wasm_soft_float_utils::{
+    backend::SoftFloatBackend,
+    float,
+    ops::{ValType, OPERATIONS},
+};
+use wasmi::{core::F64, Engine, Instance, Linker, Module, Store};
+
+/// A module that calls some imported soft float operations, like a transpiled module would
+const MODULE: &str = r#"
+(module
+    (import "wasm_soft_float" "f_32_add" (func $f_32_add (param i32 i32) (result i32)))
+    (import "wasm_soft_float" "f_64_div" (func $f_64_div (param i64 i64) (result i64)))
+    (import "wasm_soft_float" "f_64_promote_f_32" (func $f_64_promote_f_32 (param i32) (result i64)))
+    (import "wasm_soft_float" "i_32_trunc_sf_64" (func $i_32_trunc_sf_64 (param i64) (result i32)))
+    (func (export "add") (param i32 i32) (result i32)
+        local.get 0
+        local.get 1
+        call $f_32_add)
+    (func (export "div") (param i64 i64) (result i64)
+        local.get 0
+        local.get 1
+        call $f_64_div)
+    (func (export "promote") (param i32) (result i64)
+        local.get 0
+        call $f_64_promote_f_32)
+    (func (export "truncate") (param i64) (result i32)
+        local.get 0
+        call $i_32_trunc_sf_64)
+    ;; the same operation with the native instruction
+    (func (export "native_truncate") (param f64) (result i32)
+        local.get 0
+        i32.trunc_f64_s))
+"#;
+
+fn instantiate() -> (Store<()>, Instance) {
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let mut linker = Linker::new(&engine);
+    define_soft_float(&mut linker, DEFAULT_IMPORT_MODULE).unwrap();
+
+    let wasm = wat::parse_str(MODULE).unwrap();
+    let module = Module::new(&engine, &wasm[..]).unwrap();
+    let instance = linker
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    (store, instance)
+}
+
+/// Whether the imported truncation returns the same as the native instruction or traps with the same message
+fn truncates_like_native(v: f64) -> bool {
+    let (mut store, instance) = instantiate();
+    let truncate = instance.get_typed_func::<u64, i32>(&store, "truncate").unwrap();
+    let native = instance.get_typed_func::<F64, i32>(&store, "native_truncate").unwrap();
+    let result = truncate.call(&mut store, v.to_bits()).map_err(|e| e.to_string());
+    result == native.call(&mut store, F64::from(v)).map_err(|e| e.to_string())
+}
+
+#[test]
+fn truncation_traps_like_native() {
+    for v in [
+        -2147483649.0,
+        -2147483648.9,
+        -2147483648.0,
+        -0.5,
+        2147483647.9,
+        2147483648.0,
+        f64::INFINITY,
+        f64::NEG_INFINITY,
+        f64::NAN,
+    ] {
+        assert!(truncates_like_native(v), "{}", v);
+    }
+}
+
+#[test]
+fn defines_all_operations() {
+    let engine = Engine::default();
+    let mut linker = Linker::new(&engine);
+    define_soft_float(&mut linker, DEFAULT_IMPORT_MODULE).unwrap();
+
+    let name = |ty: &ValType| match ty {
+        ValType::I32 => "i32",
+        ValType::I64 => "i64",
+    };
+    for op in OPERATIONS {
+        let params: Vec<_> = op.params.iter().map(name).collect();
+        let wat = format!(
+            r#"(module (import "{}" "{}" (func (param {}) (result {}))))"#,
+            DEFAULT_IMPORT_MODULE,
+            op.name,
+            params.join(" "),
+            name(&op.result)
+        );
+        let module = Module::new(&engine, &wat::parse_str(wat).unwrap()[..]).unwrap();
+        let defined = linker
+            .instantiate(&mut Store::new(&engine, ()), &module)
+            .is_ok();
+        assert!(defined, "{}", op.name);
+    }
+}
+
+quickcheck! {
+    fn truncation_matches_native(v: u64) -> bool {
+        truncates_like_native(f64::from_bits(v))
+    }
+
+    fn add_matches_backend(a: u32, b: u32) -> bool {
+        let (mut store, instance) = instantiate();
+        let add = instance.get_typed_func::<(u32, u32), u32>(&store, "add").unwrap();
+        let expected = Backend::f_32_add(float::F32::from_bits(a), float::F32::from_bits(b));
+        add.call(&mut store, (a, b)).unwrap() == expected.to_bits()
+    }
+
+    fn div_matches_backend(a: u64, b: u64) -> bool {
+        let (mut store, instance) = instantiate();
+        let div = instance.get_typed_func::<(u64, u64), u64>(&store, "div").unwrap();
+        let expected = Backend::f_64_div(float::F64::from_bits(a), float::F64::from_bits(b));
+        div.call(&mut store, (a, b)).unwrap() == expected.to_bits()
+    }
+
+    fn promote_matches_backend(v: u32) -> bool {
+        let (mut store, instance) = instantiate();
+        let promote = instance.get_typed_func::<u32, u64>(&store, "promote").unwrap();
+        let expected = Backend::f_64_promote_f_32(float::F32::from_bits(v));
+        promote.call(&mut store, v).unwrap() == expected.to_bits()
+    }
+}
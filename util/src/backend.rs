@@ -0,0 +1,235 @@
+//! Trait based soft float backends.
+//!
+//! Instead of writing all `__wasm_soft_float_*` functions by hand, a backend can implement
+//! [`SoftFloatBackend`] and generate them using [`export_backend!`](crate::export_backend):
+//! ```ignore
+//! struct MyBackend;
+//!
+//! impl SoftFloatBackend for MyBackend {
+//!     fn f_32_add(a: F32, b: F32) -> F32 {
+//!         // ...
+//!     }
+//!     // ...
+//! }
+//!
+//! wasm_soft_float_utils::export_backend!(MyBackend);
+//! ```
+//! Forgetting to implement an operation is a compile error.
+
+use core::ops::Neg;
+
+use crate::float::{F32, F64};
+
+/// A complete implementation of all soft float operations.
+///
+/// The methods are named like the operations in [`for_each_operation!`](crate::for_each_operation).
+/// Trapping operations (the non-saturating float to int conversions) should trap exactly where the spec says,
+/// e.g. by calling [`trunc::check_f32`](crate::trunc::check_f32) first, or convert with
+/// [`trunc::f32_to_int`](crate::trunc::f32_to_int).
+pub trait SoftFloatBackend {
+    fn f_32_eq(a: F32, b: F32) -> bool {
+        a == b
+    }
+    fn f_32_ne(a: F32, b: F32) -> bool {
+        a != b
+    }
+    fn f_32_lt(a: F32, b: F32) -> bool;
+    fn f_32_gt(a: F32, b: F32) -> bool;
+    fn f_32_le(a: F32, b: F32) -> bool;
+    fn f_32_ge(a: F32, b: F32) -> bool;
+    fn f_64_eq(a: F64, b: F64) -> bool {
+        a == b
+    }
+    fn f_64_ne(a: F64, b: F64) -> bool {
+        a != b
+    }
+    fn f_64_lt(a: F64, b: F64) -> bool;
+    fn f_64_gt(a: F64, b: F64) -> bool;
+    fn f_64_le(a: F64, b: F64) -> bool;
+    fn f_64_ge(a: F64, b: F64) -> bool;
+
+    fn f_32_abs(v: F32) -> F32 {
+        v.abs()
+    }
+    fn f_32_neg(v: F32) -> F32 {
+        v.neg()
+    }
+    fn f_32_ceil(v: F32) -> F32;
+    fn f_32_floor(v: F32) -> F32;
+    fn f_32_trunc(v: F32) -> F32;
+    fn f_32_nearest(v: F32) -> F32;
+    fn f_32_sqrt(v: F32) -> F32;
+    fn f_32_add(a: F32, b: F32) -> F32;
+    fn f_32_sub(a: F32, b: F32) -> F32;
+    fn f_32_mul(a: F32, b: F32) -> F32;
+    fn f_32_div(a: F32, b: F32) -> F32;
+    fn f_32_min(a: F32, b: F32) -> F32;
+    fn f_32_max(a: F32, b: F32) -> F32;
+    fn f_32_copysign(a: F32, b: F32) -> F32 {
+        a.copy_sign(b)
+    }
+
+    fn f_64_abs(v: F64) -> F64 {
+        v.abs()
+    }
+    fn f_64_neg(v: F64) -> F64 {
+        v.neg()
+    }
+    fn f_64_ceil(v: F64) -> F64;
+    fn f_64_floor(v: F64) -> F64;
+    fn f_64_trunc(v: F64) -> F64;
+    fn f_64_nearest(v: F64) -> F64;
+    fn f_64_sqrt(v: F64) -> F64;
+    fn f_64_add(a: F64, b: F64) -> F64;
+    fn f_64_sub(a: F64, b: F64) -> F64;
+    fn f_64_mul(a: F64, b: F64) -> F64;
+    fn f_64_div(a: F64, b: F64) -> F64;
+    fn f_64_min(a: F64, b: F64) -> F64;
+    fn f_64_max(a: F64, b: F64) -> F64;
+    fn f_64_copysign(a: F64, b: F64) -> F64 {
+        a.copy_sign(b)
+    }
+
+    fn i_32_trunc_sf_32(v: F32) -> i32;
+    fn i_32_trunc_uf_32(v: F32) -> u32;
+    fn i_32_trunc_sf_64(v: F64) -> i32;
+    fn i_32_trunc_uf_64(v: F64) -> u32;
+    fn i_64_trunc_sf_32(v: F32) -> i64;
+    fn i_64_trunc_uf_32(v: F32) -> u64;
+    fn i_64_trunc_sf_64(v: F64) -> i64;
+    fn i_64_trunc_uf_64(v: F64) -> u64;
+    fn f_32_convert_si_32(v: i32) -> F32;
+    fn f_32_convert_ui_32(v: u32) -> F32;
+    fn f_32_convert_si_64(v: i64) -> F32;
+    fn f_32_convert_ui_64(v: u64) -> F32;
+    fn f_32_demote_f_64(v: F64) -> F32;
+    fn f_64_convert_si_32(v: i32) -> F64;
+    fn f_64_convert_ui_32(v: u32) -> F64;
+    fn f_64_convert_si_64(v: i64) -> F64;
+    fn f_64_convert_ui_64(v: u64) -> F64;
+    fn f_64_promote_f_32(v: F32) -> F64;
+    fn i_32_reinterpret_f_32(v: F32) -> u32 {
+        v.to_bits()
+    }
+    fn i_64_reinterpret_f_64(v: F64) -> u64 {
+        v.to_bits()
+    }
+    fn f_32_reinterpret_i_32(v: u32) -> F32 {
+        F32::from_bits(v)
+    }
+    fn f_64_reinterpret_i_64(v: u64) -> F64 {
+        F64::from_bits(v)
+    }
+
+    fn i_32_trunc_s_sat_f_32(v: F32) -> i32;
+    fn i_32_trunc_u_sat_f_32(v: F32) -> u32;
+    fn i_32_trunc_s_sat_f_64(v: F64) -> i32;
+    fn i_32_trunc_u_sat_f_64(v: F64) -> u32;
+    fn i_64_trunc_s_sat_f_32(v: F32) -> i64;
+    fn i_64_trunc_u_sat_f_32(v: F32) -> u64;
+    fn i_64_trunc_s_sat_f_64(v: F64) -> i64;
+    fn i_64_trunc_u_sat_f_64(v: F64) -> u64;
+}
+
+/// Conversion between the types used by [`SoftFloatBackend`] and the wasm type `T`
+/// they are passed as.
+pub trait WasmAbi<T> {
+    fn from_abi(v: T) -> Self;
+    fn into_abi(self) -> T;
+}
+
+macro_rules! impl_wasm_abi {
+    ($abi: ty: $($t: ty),*) => {
+        $(
+            impl WasmAbi<$abi> for $t {
+                #[inline(always)]
+                fn from_abi(v: $abi) -> Self {
+                    v as $t
+                }
+                #[inline(always)]
+                fn into_abi(self) -> $abi {
+                    self as $abi
+                }
+            }
+        )*
+    };
+}
+
+impl_wasm_abi!(i32: i32, u32);
+impl_wasm_abi!(i64: i64, u64);
+
+impl WasmAbi<i32> for F32 {
+    #[inline(always)]
+    fn from_abi(v: i32) -> Self {
+        F32::from_bits(v as u32)
+    }
+    #[inline(always)]
+    fn into_abi(self) -> i32 {
+        self.to_bits() as i32
+    }
+}
+
+impl WasmAbi<i64> for F64 {
+    #[inline(always)]
+    fn from_abi(v: i64) -> Self {
+        F64::from_bits(v as u64)
+    }
+    #[inline(always)]
+    fn into_abi(self) -> i64 {
+        self.to_bits() as i64
+    }
+}
+
+impl WasmAbi<i32> for bool {
+    #[inline(always)]
+    fn from_abi(v: i32) -> Self {
+        v != 0
+    }
+    #[inline(always)]
+    fn into_abi(self) -> i32 {
+        self as i32
+    }
+}
+
+/// Exports all `__wasm_soft_float_*` functions, implemented by the given [`SoftFloatBackend`].
+///
+/// Call this once in the backend crate.
+#[macro_export]
+macro_rules! export_backend {
+    ($backend: ty) => {
+        const _: () = {
+            $crate::for_each_operation!($crate::__export_backend_operations, $backend);
+        };
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __export_backend_operations {
+    ($backend: ty; $($name: ident: $instr: ident $text: literal [$($opcode: literal),+] ($($param: ident),*) -> $result: ident, $category: ident;)*) => {
+        $(
+            $crate::__export_backend_operation!($backend, $name ($($param),*) -> $result);
+        )*
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __export_backend_operation {
+    // `"C-unwind"`, so that the traps of the conversions can be caught on the host
+    ($backend: ty, $name: ident ($a: ident) -> $result: ident) => {
+        #[export_name = concat!("__wasm_soft_float_", stringify!($name))]
+        extern "C-unwind" fn $name(a: $a) -> $result {
+            use $crate::backend::{SoftFloatBackend, WasmAbi};
+            <$backend as SoftFloatBackend>::$name(WasmAbi::from_abi(a)).into_abi()
+        }
+    };
+    ($backend: ty, $name: ident ($a: ident, $b: ident) -> $result: ident) => {
+        #[export_name = concat!("__wasm_soft_float_", stringify!($name))]
+        extern "C-unwind" fn $name(a: $a, b: $b) -> $result {
+            use $crate::backend::{SoftFloatBackend, WasmAbi};
+            <$backend as SoftFloatBackend>::$name(WasmAbi::from_abi(a), WasmAbi::from_abi(b))
+                .into_abi()
+        }
+    };
+}
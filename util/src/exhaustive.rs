@@ -0,0 +1,249 @@
+//! Exhaustive verification of the operations with 32 bit operands.
+//!
+//! There are only 2^32 inputs to an `f32` unary operation, so instead of sampling them, [`check`] runs every
+//! single bit pattern through the backend and compares the result with the native operation, which implements
+//! the wasm semantics for all of them. Binary operations are checked with every first operand and `+0` as the
+//! second one, which covers e.g. the comparison of subnormals with zero. The trapping conversions have to trap
+//! for exactly the inputs for which the native conversion is out of range.
+//!
+//! A simple operation takes about half a minute on a single core, and the trapping conversions take hours since
+//! every one of their roughly 2^31 traps unwinds a panic. So backends run this in an ignored test:
+//! ```ignore
+//! use wasm_soft_float_bs::BerkeleySoftFloat;
+//!
+//! /// This takes a long time, run it with `cargo test --release -- --ignored exhaustive`.
+//! #[test]
+//! #[ignore]
+//! fn exhaustive() {
+//!     wasm_soft_float_utils::exhaustive::check(&wasm_soft_float_utils::implementations!(BerkeleySoftFloat));
+//! }
+//! ```
+
+use std::{env, fmt::Write as _, time::Instant};
+
+use rayon::prelude::*;
+
+use crate::{
+    ops::{Category, Implementation, Operation, ValType},
+    test::catch_trap,
+};
+
+/// Setting this environment variable to a comma separated list of operation names, e.g. `f_32_sqrt,f_32_eq`,
+/// restricts [`check`] to these operations
+pub const OPERATIONS_VAR: &str = "EXHAUSTIVE_OPERATIONS";
+
+/// Computes the expected result of an operation, `None` if the operation traps.
+/// Unary operations ignore their second argument.
+type Reference = fn(u32, u32) -> Option<u64>;
+
+/// Checks every input of all `implementations` with 32 bit operands and panics if any result is wrong.
+/// Operations with 64 bit operands are skipped.
+pub fn check(implementations: &[Implementation]) {
+    let selected = env::var(OPERATIONS_VAR).ok();
+    let selected = selected
+        .as_deref()
+        .map(|names| names.split(',').map(str::trim).collect::<Vec<_>>());
+
+    let mut report = String::new();
+    for imp in implementations {
+        if !is_32_bit(imp.op)
+            || selected
+                .as_ref()
+                .is_some_and(|names| !names.contains(&imp.op.name))
+        {
+            continue;
+        }
+        let start = Instant::now();
+        let result = verify(imp);
+        println!(
+            "{}: {} ({:.1?})",
+            imp.op.name,
+            if result.is_ok() { "ok" } else { "failed" },
+            start.elapsed()
+        );
+        if let Err(e) = result {
+            writeln!(report, "{}", e).unwrap();
+        }
+    }
+    assert!(report.is_empty(), "wrong results:\n{}", report);
+}
+
+/// Runs every input through `imp`. The error describes the smallest wrong input and counts the wrong ones.
+/// The inputs for which the operation traps have to trap, which [`catch_trap`] checks.
+pub fn verify(imp: &Implementation) -> Result<(), String> {
+    let op = imp.op;
+    assert!(is_32_bit(op), "{} has 64 bit operands", op.name);
+    let reference =
+        reference(op).unwrap_or_else(|| panic!("there is no reference for {}", op.name));
+    // the second operand of binary operations is +0
+    let args = |v: u32| [v as u64, 0];
+    let call = |v: u32| catch_trap(|| imp.call(&args(v)[..op.params.len()]));
+    let wrong = |v: u32| match (call(v), reference(v, 0)) {
+        (Some(actual), Some(expected)) => !same(op, actual, expected),
+        (actual, expected) => actual.is_some() != expected.is_some(),
+    };
+
+    let (count, first) = (0..=u32::MAX)
+        .into_par_iter()
+        .filter(|&v| wrong(v))
+        .map(|v| (1u64, v))
+        .reduce(|| (0, u32::MAX), |a, b| (a.0 + b.0, a.1.min(b.1)));
+    if count == 0 {
+        return Ok(());
+    }
+    Err(format!(
+        "{}({}) = {}, expected {} ({} of 2^32 inputs differ)",
+        op.name,
+        args(first)[..op.params.len()]
+            .iter()
+            .map(|arg| format!("{:#x}", arg))
+            .collect::<Vec<_>>()
+            .join(", "),
+        result(call(first)),
+        result(reference(first, 0)),
+        count
+    ))
+}
+
+/// Formats the result of an operation, `None` if it traps
+fn result(result: Option<u64>) -> String {
+    result.map_or_else(|| "a trap".to_string(), |v| format!("{:#x}", v))
+}
+
+/// Whether all operands of `op` are 32 bit values
+pub fn is_32_bit(op: &Operation) -> bool {
+    op.params.iter().all(|&ty| ty == ValType::I32)
+}
+
+/// Whether `actual` is a correct result if `expected` is.
+/// The spec does not specify the payload of NaN results, except for the operations that only change the sign.
+fn same(op: &Operation, actual: u64, expected: u64) -> bool {
+    if actual == expected {
+        return true;
+    }
+    match op.instruction.split_once('.').unwrap().0 {
+        _ if matches!(
+            op.category,
+            Category::Sign | Category::Reinterpret | Category::Comparison
+        ) =>
+        {
+            false
+        }
+        "f32" => f32::from_bits(actual as u32).is_nan() && f32::from_bits(expected as u32).is_nan(),
+        "f64" => f64::from_bits(actual).is_nan() && f64::from_bits(expected).is_nan(),
+        _ => false,
+    }
+}
+
+/// The native implementation of `op`, if it has 32 bit operands
+fn reference(op: &Operation) -> Option<Reference> {
+    let reference: Reference = match op.name {
+        "f_32_eq" => |a, b| int(f32(a) == f32(b)),
+        "f_32_ne" => |a, b| int(f32(a) != f32(b)),
+        "f_32_lt" => |a, b| int(f32(a) < f32(b)),
+        "f_32_gt" => |a, b| int(f32(a) > f32(b)),
+        "f_32_le" => |a, b| int(f32(a) <= f32(b)),
+        "f_32_ge" => |a, b| int(f32(a) >= f32(b)),
+        "f_32_abs" => |v, _| float(f32(v).abs()),
+        "f_32_neg" => |v, _| float(-f32(v)),
+        "f_32_ceil" => |v, _| float(f32(v).ceil()),
+        "f_32_floor" => |v, _| float(f32(v).floor()),
+        "f_32_trunc" => |v, _| float(f32(v).trunc()),
+        "f_32_nearest" => |v, _| float(f32(v).round_ties_even()),
+        "f_32_sqrt" => |v, _| float(f32(v).sqrt()),
+        "f_32_add" => |a, b| float(f32(a) + f32(b)),
+        "f_32_sub" => |a, b| float(f32(a) - f32(b)),
+        "f_32_mul" => |a, b| float(f32(a) * f32(b)),
+        "f_32_div" => |a, b| float(f32(a) / f32(b)),
+        // unlike Rust's `min` and `max`, these return NaN if either operand is NaN and order -0 before +0
+        "f_32_min" => |a, b| match (f32(a), f32(b)) {
+            (x, y) if x.is_nan() || y.is_nan() => float(f32::NAN),
+            (x, y) if x == y => Some((a | b) as u64),
+            (x, y) => float(x.min(y)),
+        },
+        "f_32_max" => |a, b| match (f32(a), f32(b)) {
+            (x, y) if x.is_nan() || y.is_nan() => float(f32::NAN),
+            (x, y) if x == y => Some((a & b) as u64),
+            (x, y) => float(x.max(y)),
+        },
+        "f_32_copysign" => |a, b| float(f32(a).copysign(f32(b))),
+        // these trap for NaN and values that are out of range after truncation
+        "i_32_trunc_sf_32" => {
+            |v, _| truncate(v, -2147483649.0, 2147483648.0, |v| v as i32 as u32 as u64)
+        }
+        "i_32_trunc_uf_32" => |v, _| truncate(v, -1.0, 4294967296.0, |v| v as u32 as u64),
+        "i_64_trunc_sf_32" => |v, _| {
+            truncate(v, -9223372036854777856.0, 9223372036854775808.0, |v| {
+                v as i64 as u64
+            })
+        },
+        "i_64_trunc_uf_32" => |v, _| truncate(v, -1.0, 18446744073709551616.0, |v| v as u64),
+        "f_32_convert_si_32" => |v, _| float(v as i32 as f32),
+        "f_32_convert_ui_32" => |v, _| float(v as f32),
+        "f_64_convert_si_32" => |v, _| Some((v as i32 as f64).to_bits()),
+        "f_64_convert_ui_32" => |v, _| Some((v as f64).to_bits()),
+        "f_64_promote_f_32" => |v, _| Some((f32(v) as f64).to_bits()),
+        "i_32_reinterpret_f_32" | "f_32_reinterpret_i_32" => |v, _| Some(v as u64),
+        // Rust's casts saturate exactly like these
+        "i_32_trunc_s_sat_f_32" => |v, _| Some(f32(v) as i32 as u32 as u64),
+        "i_32_trunc_u_sat_f_32" => |v, _| Some(f32(v) as u32 as u64),
+        "i_64_trunc_s_sat_f_32" => |v, _| Some(f32(v) as i64 as u64),
+        "i_64_trunc_u_sat_f_32" => |v, _| Some(f32(v) as u64),
+        _ => return None,
+    };
+    Some(reference)
+}
+
+fn f32(bits: u32) -> f32 {
+    f32::from_bits(bits)
+}
+
+fn float(v: f32) -> Option<u64> {
+    Some(v.to_bits() as u64)
+}
+
+fn int(v: bool) -> Option<u64> {
+    Some(v as u64)
+}
+
+/// Converts `v` if it is strictly between `min` and `max`, `None` otherwise (and for NaN)
+fn truncate(v: u32, min: f64, max: f64, convert: fn(f32) -> u64) -> Option<u64> {
+    let v = f32(v);
+    (min < v as f64 && (v as f64) < max).then(|| convert(v))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ops::OPERATIONS;
+
+    #[test]
+    fn every_32_bit_operation_has_a_reference() {
+        for op in OPERATIONS.iter().filter(|op| is_32_bit(op)) {
+            assert!(reference(op).is_some(), "{}", op.name);
+        }
+    }
+
+    #[test]
+    fn references_follow_the_spec() {
+        let reference = |name, a: f32, b: f32| {
+            reference(Operation::by_name(name).unwrap()).unwrap()(a.to_bits(), b.to_bits())
+        };
+        assert_eq!(reference("f_32_min", 0.0, -0.0), float(-0.0));
+        assert_eq!(reference("f_32_max", -0.0, 0.0), float(0.0));
+        assert!(f32::from_bits(reference("f_32_min", 1.0, f32::NAN).unwrap() as u32).is_nan());
+        assert_eq!(reference("f_32_eq", f32::from_bits(1), 0.0), int(false));
+        assert_eq!(
+            reference("i_32_trunc_sf_32", -2147483648.0, 0.0),
+            Some(0x8000_0000)
+        );
+        assert_eq!(reference("i_32_trunc_sf_32", 2147483648.0, 0.0), None);
+        assert_eq!(reference("i_32_trunc_uf_32", -0.75, 0.0), Some(0));
+        assert_eq!(reference("i_32_trunc_uf_32", -1.0, 0.0), None);
+        assert_eq!(
+            reference("i_64_trunc_sf_32", -9223372036854775808.0, 0.0),
+            Some(1 << 63)
+        );
+        assert_eq!(reference("i_64_trunc_uf_32", f32::NAN, 0.0), None);
+    }
+}
@@ -8,6 +8,16 @@
 
 use core::ops::Neg;
 
+/// The direction of [`F32::round`] and [`F64::round`]
+#[derive(Clone, Copy)]
+enum Rounding {
+    Up,
+    Down,
+    TowardZero,
+    /// To the nearest integer, ties to even
+    Nearest,
+}
+
 macro_rules! impl_float {
     ($name: ident, $bits: ident, $exp_bits: tt) => {
         #[derive(Debug, Clone, Copy)]
@@ -21,10 +31,12 @@ macro_rules! impl_float {
             const SIGN_MASK: $bits = 1 << (Self::BIT_SIZE - 1);
             const FRAC_MASK: $bits = $bits::MAX >> ($exp_bits + 1);
             const EXP_MASK: $bits = !(Self::SIGN_MASK | Self::FRAC_MASK);
+            const QUIET_BIT: $bits = 1 << (Self::MANTISSA_BITS - 1);
+            const BIAS: i32 = (1 << (Self::EXP_BITS - 1)) - 1;
             /// The canonical NaN value.
             /// Please keep in mind that there are other NaN values, so-called arithmetic NaNs.
             /// See https://webassembly.github.io/spec/core/syntax/values.html#syntax-float
-            pub const NAN: Self = Self::from_bits(Self::EXP_MASK | 1 << (Self::MANTISSA_BITS - 1));
+            pub const NAN: Self = Self::from_bits(Self::EXP_MASK | Self::QUIET_BIT);
             pub const NEG_NAN: Self = Self::from_bits(Self::NAN.0 | Self::SIGN_MASK);
             pub const INFINITY: Self = Self::from_bits(Self::EXP_MASK);
             pub const NEG_INFINITY: Self = Self::from_bits(Self::EXP_MASK | Self::SIGN_MASK);
@@ -53,6 +65,17 @@ macro_rules! impl_float {
                 (self.0 & Self::EXP_MASK) == Self::EXP_MASK && (self.0 & Self::FRAC_MASK) != 0
             }
 
+            /// Returns the canonical NaN if `self` is some kind of NaN and `self` otherwise.
+            ///
+            /// Most operations may return any NaN, so this makes their results independent of the implementation.
+            pub const fn canonicalize(self) -> Self {
+                if self.is_nan() {
+                    Self::NAN
+                } else {
+                    self
+                }
+            }
+
             /// Returns true if `self` is positive or negative infinity.
             pub const fn is_infinite(self) -> bool {
                 (self.0 & (Self::EXP_MASK | Self::FRAC_MASK)) == Self::EXP_MASK
@@ -60,7 +83,7 @@ macro_rules! impl_float {
 
             /// Returns true if `self` is positive or negative zero.
             pub const fn is_zero(self) -> bool {
-                self.0 & Self::EXP_MASK == 0
+                self.0 & !Self::SIGN_MASK == 0
             }
 
             /// Computes the absolute value of `self`.
@@ -72,6 +95,229 @@ macro_rules! impl_float {
                 Self(self.0 & !Self::SIGN_MASK)
             }
 
+            /// Returns the smaller of `self` and `other`.
+            ///
+            /// Unlike IEEE 754 `minNum`, this returns NaN if either value is NaN and orders `-0` before `+0`.
+            ///
+            /// https://webassembly.github.io/spec/core/exec/numerics.html#op-fmin
+            pub const fn min(self, other: Self) -> Self {
+                if self.is_nan() || other.is_nan() {
+                    Self::NAN
+                } else if self.is_zero() && other.is_zero() {
+                    Self(self.0 | other.0)
+                } else if other.lt(self) {
+                    other
+                } else {
+                    self
+                }
+            }
+
+            /// Returns the larger of `self` and `other`.
+            ///
+            /// Unlike IEEE 754 `maxNum`, this returns NaN if either value is NaN and orders `-0` before `+0`.
+            ///
+            /// https://webassembly.github.io/spec/core/exec/numerics.html#op-fmax
+            pub const fn max(self, other: Self) -> Self {
+                if self.is_nan() || other.is_nan() {
+                    Self::NAN
+                } else if self.is_zero() && other.is_zero() {
+                    Self(self.0 & other.0)
+                } else if self.lt(other) {
+                    other
+                } else {
+                    self
+                }
+            }
+
+            /// Rounds `self` up to an integer.
+            ///
+            /// https://webassembly.github.io/spec/core/exec/numerics.html#op-fceil
+            pub const fn ceil(self) -> Self {
+                self.round(Rounding::Up)
+            }
+
+            /// Rounds `self` down to an integer.
+            ///
+            /// https://webassembly.github.io/spec/core/exec/numerics.html#op-ffloor
+            pub const fn floor(self) -> Self {
+                self.round(Rounding::Down)
+            }
+
+            /// Rounds `self` toward zero to an integer.
+            ///
+            /// https://webassembly.github.io/spec/core/exec/numerics.html#op-ftrunc
+            pub const fn trunc(self) -> Self {
+                self.round(Rounding::TowardZero)
+            }
+
+            /// Rounds `self` to the nearest integer, ties to even.
+            ///
+            /// https://webassembly.github.io/spec/core/exec/numerics.html#op-fnearest
+            pub const fn nearest(self) -> Self {
+                self.round(Rounding::Nearest)
+            }
+
+            /// Rounds `self` to an integer in the given direction, keeping its sign. NaNs are quieted.
+            ///
+            /// The result is exact, so this only clears or increments the bits below the binary point.
+            const fn round(self, rounding: Rounding) -> Self {
+                if self.is_nan() {
+                    return self.quiet();
+                }
+                let exponent = self.exponent();
+                if exponent >= Self::MANTISSA_BITS as i32 {
+                    // already an integer or infinite
+                    return self;
+                }
+                let negative = !self.is_sign_positive();
+                if exponent < 0 {
+                    // |self| < 1 rounds to zero or one
+                    let one = match rounding {
+                        _ if self.is_zero() => false,
+                        Rounding::Up => !negative,
+                        Rounding::Down => negative,
+                        Rounding::TowardZero => false,
+                        // 0.5 is a tie and rounds to the even zero
+                        Rounding::Nearest => exponent == -1 && self.0 & Self::FRAC_MASK != 0,
+                    };
+                    return Self((self.0 & Self::SIGN_MASK) | if one { Self::ONE.0 } else { 0 });
+                }
+                // the bits below the binary point, and the lowest bit above it
+                let fraction = Self::FRAC_MASK >> exponent as u32;
+                let one = fraction + 1;
+                let rest = self.0 & fraction;
+                if rest == 0 {
+                    return self;
+                }
+                let half = one >> 1;
+                let up = match rounding {
+                    Rounding::Up => !negative,
+                    Rounding::Down => negative,
+                    Rounding::TowardZero => false,
+                    // for `exponent == 0`, `one` is the lowest bit of the biased exponent, which is odd
+                    // just like the implicit bit it stands for
+                    Rounding::Nearest => rest > half || (rest == half && self.0 & one != 0),
+                };
+                let truncated = self.0 & !fraction;
+                // a carry out of the fraction increments the exponent, which gives the next power of two
+                Self(if up { truncated + one } else { truncated })
+            }
+
+            /// Computes the square root of `self`, correctly rounded to nearest, ties to even.
+            /// The square root of a number below zero is NaN, NaNs are quieted.
+            ///
+            /// This computes the integer square root of the scaled significand,
+            /// so it does not need float instructions.
+            ///
+            /// https://webassembly.github.io/spec/core/exec/numerics.html#op-fsqrt
+            pub const fn sqrt(self) -> Self {
+                if self.is_nan() {
+                    return self.quiet();
+                }
+                if self.is_zero() || self.0 == Self::INFINITY.0 {
+                    return self;
+                }
+                if !self.is_sign_positive() {
+                    return Self::NAN;
+                }
+                // self = significand * 2^exponent with a normalized integer significand
+                let mut significand = (self.0 & Self::FRAC_MASK) as u128;
+                let mut exponent = if self.is_subnormal() {
+                    1 - Self::BIAS
+                } else {
+                    significand |= 1 << Self::MANTISSA_BITS;
+                    self.exponent()
+                } - Self::MANTISSA_BITS as i32;
+                while significand >> Self::MANTISSA_BITS == 0 {
+                    significand <<= 1;
+                    exponent -= 1;
+                }
+                // halve an even exponent, the significand is below 2^(MANTISSA_BITS + 2) then
+                if exponent & 1 != 0 {
+                    significand <<= 1;
+                    exponent -= 1;
+                }
+                // an even shift that leaves at least two bits below the result for rounding
+                let shift = (Self::MANTISSA_BITS as u32 + 5) & !1;
+                let scaled = significand << shift;
+                let root = scaled.isqrt();
+                let exact = root * root == scaled;
+
+                let extra = 128 - root.leading_zeros() - (Self::MANTISSA_BITS as u32 + 1);
+                let mut result = root >> extra;
+                let rest = root & ((1 << extra) - 1);
+                let half = 1 << (extra - 1);
+                if rest > half || (rest == half && (!exact || result & 1 != 0)) {
+                    result += 1;
+                }
+                let mut exponent = (exponent - shift as i32) / 2 + extra as i32;
+                if result >> (Self::MANTISSA_BITS + 1) != 0 {
+                    result >>= 1;
+                    exponent += 1;
+                }
+                // the square root of every positive number is normal
+                let biased = (exponent + Self::MANTISSA_BITS as i32 + Self::BIAS) as $bits;
+                Self((biased << Self::MANTISSA_BITS) | (result as $bits & Self::FRAC_MASK))
+            }
+
+            /// Returns true if truncating `self` to an integer with `bits` bits (signed if `signed` is true)
+            /// traps, i.e. if `self` is NaN or its truncation is out of the range of the integer type.
+            ///
+            /// This only looks at the bits, so it does not need float instructions.
+            ///
+            /// https://webassembly.github.io/spec/core/exec/numerics.html#op-trunc-s
+            pub const fn trunc_traps(self, bits: u32, signed: bool) -> bool {
+                // |self| < 2^limit fits into the integer type, NaN and infinities have a larger exponent
+                let limit = if signed { bits - 1 } else { bits } as i32;
+                let exponent = self.exponent();
+                if self.is_sign_positive() {
+                    exponent >= limit
+                } else if !signed {
+                    // everything above -1 truncates to 0
+                    exponent >= 0
+                } else {
+                    // -2^limit fits as well, and so does everything above -2^limit - 1
+                    let fraction = self.0 & Self::FRAC_MASK;
+                    let integer_bits = Self::MANTISSA_BITS.saturating_sub(limit as usize);
+                    exponent > limit || (exponent == limit && fraction >> integer_bits != 0)
+                }
+            }
+
+            /// The absolute value of `self`, truncated to an integer.
+            /// `self` must not trap according to [`trunc_traps`](Self::trunc_traps) for 64 bit integers.
+            pub(crate) const fn trunc_magnitude(self) -> u64 {
+                let exponent = self.exponent();
+                if exponent < 0 {
+                    return 0;
+                }
+                let significand = ((self.0 & Self::FRAC_MASK) | 1 << Self::MANTISSA_BITS) as u64;
+                let exponent = exponent as usize;
+                if exponent >= Self::MANTISSA_BITS {
+                    significand << (exponent - Self::MANTISSA_BITS)
+                } else {
+                    significand >> (Self::MANTISSA_BITS - exponent)
+                }
+            }
+
+            /// The unbiased exponent of `self`. Subnormals are treated like zero and have the smallest one.
+            const fn exponent(self) -> i32 {
+                ((self.0 & Self::EXP_MASK) >> Self::MANTISSA_BITS) as i32 - Self::BIAS
+            }
+
+            /// `self` with the quiet bit set, which turns a signaling NaN into a quiet one
+            const fn quiet(self) -> Self {
+                Self(self.0 | Self::QUIET_BIT)
+            }
+
+            /// Whether `self` is smaller than `other`, which must both not be NaN or zeroes of different signs
+            const fn lt(self, other: Self) -> bool {
+                match (self.is_sign_positive(), other.is_sign_positive()) {
+                    (true, true) => self.0 < other.0,
+                    (false, false) => self.0 > other.0,
+                    (positive, _) => !positive,
+                }
+            }
+
             /// Reinterprets the given bits as this type.
             ///
             /// This is a no-op.
@@ -170,4 +416,128 @@ mod tests {
         // most significant bit not set and sign bit set
         assert!(F32::from_bits(0xff801001).is_nan());
     }
+
+    #[test]
+    fn test_canonicalize() {
+        for nan in [0x7fc00000, 0xffc00000, 0x7f800001, 0xffbfffff] {
+            assert_eq!(F32::from_bits(nan).canonicalize().0, F32::NAN.0);
+        }
+        assert_eq!(
+            F64::from_bits(0xfff8_0000_0000_0001).canonicalize().0,
+            F64::NAN.0
+        );
+        for v in [
+            F32::INFINITY,
+            F32::NEG_ZERO,
+            F32::NEG_ONE,
+            F32::from_bits(1),
+        ] {
+            assert_eq!(v.canonicalize().0, v.0);
+        }
+    }
+
+    #[test]
+    fn test_eq() {
+        assert!(F32::ZERO == F32::NEG_ZERO && F64::ZERO == F64::NEG_ZERO);
+        assert!(F32::NAN != F32::NAN);
+        // subnormals are not zero
+        assert!(F32::from_bits(1) != F32::ZERO);
+        assert!(F32::from_bits(0x8000_0001) != F32::NEG_ZERO);
+        assert!(F32::from_bits(1) != F32::from_bits(2));
+        assert!(F64::from_bits(0x000f_ffff_ffff_ffff) != F64::ZERO);
+    }
+
+    quickcheck! {
+        fn min_max_work(a: f32, b: f32) -> bool {
+            let (sa, sb) = (F32(a.to_bits()), F32(b.to_bits()));
+            let (min, max) = (sa.min(sb), sa.max(sb));
+            if a.is_nan() || b.is_nan() {
+                min.is_nan() && max.is_nan()
+            } else {
+                // the zeroes are tested separately
+                (a == 0.0 && b == 0.0) || (min.0 == a.min(b).to_bits() && max.0 == a.max(b).to_bits())
+            }
+        }
+    }
+
+    quickcheck! {
+        fn rounding_works(f: f64, bits: u32) -> bool {
+            let same32 = |a: F32, b: f32| a.0 == b.to_bits() || (a.is_nan() && b.is_nan());
+            let same64 = |a: F64, b: f64| a.0 == b.to_bits() || (a.is_nan() && b.is_nan());
+            let (sf, g) = (F64(f.to_bits()), f32::from_bits(bits));
+            let sg = F32(bits);
+            same64(sf.ceil(), f.ceil())
+                && same64(sf.floor(), f.floor())
+                && same64(sf.trunc(), f.trunc())
+                && same64(sf.nearest(), f.round_ties_even())
+                && same32(sg.ceil(), g.ceil())
+                && same32(sg.floor(), g.floor())
+                && same32(sg.trunc(), g.trunc())
+                && same32(sg.nearest(), g.round_ties_even())
+        }
+
+        fn sqrt_works(f: f64, bits: u32) -> bool {
+            let (root32, root64) = (F32(bits).sqrt(), F64(f.to_bits()).sqrt());
+            let (expected32, expected64) = (f32::from_bits(bits).sqrt(), f.sqrt());
+            (root32.0 == expected32.to_bits() || (root32.is_nan() && expected32.is_nan()))
+                && (root64.0 == expected64.to_bits() || (root64.is_nan() && expected64.is_nan()))
+        }
+    }
+
+    #[test]
+    fn test_rounding() {
+        for (v, ceil, floor, trunc, nearest) in [
+            (0.5, 1.0, 0.0, 0.0, 0.0),
+            (-0.5, -0.0, -1.0, -0.0, -0.0),
+            (0.75, 1.0, 0.0, 0.0, 1.0),
+            (1.5, 2.0, 1.0, 1.0, 2.0),
+            (2.5, 3.0, 2.0, 2.0, 2.0),
+            (-3.5, -3.0, -4.0, -3.0, -4.0),
+            (8388607.5, 8388608.0, 8388607.0, 8388607.0, 8388608.0),
+            (-0.0, -0.0, -0.0, -0.0, -0.0),
+            (f32::from_bits(1), 1.0, 0.0, 0.0, 0.0),
+            (f32::INFINITY, f32::INFINITY, f32::INFINITY, f32::INFINITY, f32::INFINITY),
+        ] {
+            let v = F32(v.to_bits());
+            assert_eq!(v.ceil().0, ceil.to_bits(), "ceil({:?})", v);
+            assert_eq!(v.floor().0, floor.to_bits(), "floor({:?})", v);
+            assert_eq!(v.trunc().0, trunc.to_bits(), "trunc({:?})", v);
+            assert_eq!(v.nearest().0, nearest.to_bits(), "nearest({:?})", v);
+        }
+        // signaling NaNs are quieted
+        assert_eq!(F32::from_bits(0xff80_0001).floor().0, 0xffc0_0001);
+        assert_eq!(
+            F64::from_bits(0x7ff0_0000_0000_0001).nearest().0,
+            0x7ff8_0000_0000_0001
+        );
+    }
+
+    #[test]
+    fn test_sqrt() {
+        for (v, root) in [
+            (4.0, 2.0),
+            (2.0, core::f64::consts::SQRT_2),
+            (-0.0, -0.0),
+            (f64::INFINITY, f64::INFINITY),
+            (f64::from_bits(1), 2f64.powi(-537)),
+            (f64::MAX, 1.3407807929942596e154),
+        ] {
+            assert_eq!(F64(f64::to_bits(v)).sqrt().0, f64::to_bits(root), "sqrt({})", v);
+        }
+        assert_eq!(F32::from_bits(1).sqrt().0, f32::from_bits(1).sqrt().to_bits());
+        assert_eq!(F32::NEG_ONE.sqrt().0, F32::NAN.0);
+        assert!(F64::NEG_INFINITY.sqrt().is_nan());
+        assert_eq!(F32::from_bits(0x7f80_0001).sqrt().0, 0x7fc0_0001);
+    }
+
+    #[test]
+    fn test_min_max() {
+        assert_eq!(F32::ZERO.min(F32::NEG_ZERO).0, F32::NEG_ZERO.0);
+        assert_eq!(F32::NEG_ZERO.min(F32::ZERO).0, F32::NEG_ZERO.0);
+        assert_eq!(F32::ZERO.max(F32::NEG_ZERO).0, F32::ZERO.0);
+        assert_eq!(F64::NEG_ZERO.max(F64::ZERO).0, F64::ZERO.0);
+        assert_eq!(F32::NEG_ONE.min(F32::ONE).0, F32::NEG_ONE.0);
+        assert_eq!(F32::NEG_INFINITY.max(F32::NEG_ONE).0, F32::NEG_ONE.0);
+        assert!(F32::ONE.min(F32::NAN).is_nan() && F64::NAN.max(F64::ONE).is_nan());
+    }
 }
@@ -0,0 +1,450 @@
+//! Golden test vectors that lock in the exact results of a backend.
+//!
+//! The backends pin their dependencies to avoid changes in determinism, but only these vectors detect one.
+//! [`generate`] evaluates every operation of a backend on edge cases and on a seeded stream of random inputs,
+//! and [`replay`] checks that the backend still produces the same bits for all of them.
+//! A backend test only has to call [`check`] with its [`Implementation`]s:
+//! ```ignore
+//! use wasm_soft_float_bs::BerkeleySoftFloat;
+//!
+//! #[test]
+//! fn golden_vectors() {
+//!     wasm_soft_float_utils::golden::check(
+//!         concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden.bin"),
+//!         &wasm_soft_float_utils::implementations!(BerkeleySoftFloat),
+//!     );
+//! }
+//! ```
+//!
+//! The file is a sequence of little endian values. After the [`MAGIC`] and the number of operations,
+//! every operation consists of
+//! - its name, prefixed by its length as a `u8`
+//! - the number of edge cases as a `u32`, followed by the parameters and the result of every edge case,
+//!   each in the width of its wasm type
+//! - the seed (`u64`) and the number (`u32`) of the random cases, which are generated again when replaying
+//! - one `u64` digest of the results of every [`BLOCK`] random cases
+//!
+//! The edge cases are stored with their inputs, so their failures can be reported exactly.
+//! The random cases only store digests, which keeps the file small even for millions of inputs.
+
+use std::{collections::BTreeMap, env, fmt::Write as _, fs, path::Path};
+
+use crate::ops::{Implementation, Operation, ValType};
+
+/// Identifies golden vector files and the version of their format and input generation
+pub const MAGIC: &[u8; 8] = b"wsfgold1";
+
+/// The number of random cases per operation written by [`check`]
+pub const DEFAULT_RANDOM_CASES: u32 = 1 << 16;
+
+/// The number of random cases summarized by a single digest
+pub const BLOCK: u32 = 1 << 12;
+
+/// Setting this environment variable makes [`check`] write the golden vectors instead of replaying them
+pub const UPDATE_VAR: &str = "UPDATE_GOLDEN_VECTORS";
+
+/// Replays the golden vectors in `path` and panics if any result changed.
+/// If the [`UPDATE_VAR`] environment variable is set, the golden vectors are written instead.
+pub fn check(path: impl AsRef<Path>, implementations: &[Implementation]) {
+    let path = path.as_ref();
+    if env::var_os(UPDATE_VAR).is_some() {
+        fs::write(path, generate(implementations, DEFAULT_RANDOM_CASES))
+            .unwrap_or_else(|e| panic!("cannot write {}: {}", path.display(), e));
+        return;
+    }
+    let data = fs::read(path).unwrap_or_else(|e| {
+        panic!(
+            "cannot read {}: {}\nrun the test with {}=1 to generate it",
+            path.display(),
+            e,
+            UPDATE_VAR
+        )
+    });
+    if let Err(report) = replay(&data, implementations) {
+        panic!(
+            "the results differ from the golden vectors in {}:\n{}",
+            path.display(),
+            report
+        );
+    }
+}
+
+/// Generates the golden vectors of all `implementations`, with `random_cases` random cases each
+pub fn generate(implementations: &[Implementation], random_cases: u32) -> Vec<u8> {
+    let mut implementations = implementations.iter().collect::<Vec<_>>();
+    implementations.sort_by_key(|imp| imp.op.name);
+
+    let mut out = MAGIC.to_vec();
+    out.extend((implementations.len() as u32).to_le_bytes());
+    for imp in implementations {
+        let op = imp.op;
+        out.push(op.name.len() as u8);
+        out.extend(op.name.as_bytes());
+
+        let edge_cases = edge_cases(op);
+        out.extend((edge_cases.len() as u32).to_le_bytes());
+        for args in edge_cases {
+            for (&arg, &ty) in args.iter().zip(op.params) {
+                write_value(&mut out, ty, arg);
+            }
+            write_value(&mut out, op.result, imp.call(&args));
+        }
+
+        let seed = fnv1a(op.name.as_bytes());
+        out.extend(seed.to_le_bytes());
+        out.extend(random_cases.to_le_bytes());
+        for digest in random_digests(imp, seed, random_cases) {
+            out.extend(digest.to_le_bytes());
+        }
+    }
+    out
+}
+
+/// Checks `implementations` against the golden vectors in `data`.
+/// The error lists every difference.
+pub fn replay(data: &[u8], implementations: &[Implementation]) -> Result<(), String> {
+    let mut reader = Reader(data);
+    if reader.bytes(MAGIC.len()) != Some(&MAGIC[..]) {
+        return Err("not a golden vector file of this version".to_string());
+    }
+    let mut remaining = implementations
+        .iter()
+        .map(|imp| (imp.op.name, imp))
+        .collect::<BTreeMap<_, _>>();
+    let mut report = String::new();
+
+    let operations = reader.u32()?;
+    for _ in 0..operations {
+        let len = reader.bytes(1).ok_or(TRUNCATED)?[0] as usize;
+        let name = reader.bytes(len).ok_or(TRUNCATED)?;
+        let name = std::str::from_utf8(name).map_err(|_| "invalid operation name")?;
+        let op = Operation::by_name(name).ok_or_else(|| format!("unknown operation `{}`", name))?;
+        let imp = remaining.remove(name);
+        if imp.is_none() {
+            writeln!(report, "`{}` is not implemented anymore", name).unwrap();
+        }
+
+        let mut differences = 0;
+        for _ in 0..reader.u32()? {
+            let args = op
+                .params
+                .iter()
+                .map(|&ty| reader.value(ty))
+                .collect::<Result<Vec<_>, _>>()?;
+            let expected = reader.value(op.result)?;
+            let actual = match imp {
+                Some(imp) => imp.call(&args),
+                None => continue,
+            };
+            if actual != expected {
+                differences += 1;
+                if differences <= 10 {
+                    let args = args.iter().map(|a| format!("{:#x}", a)).collect::<Vec<_>>();
+                    writeln!(
+                        report,
+                        "{}({}) = {:#x}, expected {:#x}",
+                        name,
+                        args.join(", "),
+                        actual,
+                        expected
+                    )
+                    .unwrap();
+                }
+            }
+        }
+        if differences > 10 {
+            writeln!(
+                report,
+                "... and {} more edge cases of {}",
+                differences - 10,
+                name
+            )
+            .unwrap();
+        }
+
+        let seed = reader.u64()?;
+        let random_cases = reader.u32()?;
+        let blocks = random_cases.div_ceil(BLOCK);
+        let expected = (0..blocks)
+            .map(|_| reader.u64())
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(imp) = imp {
+            let actual = random_digests(imp, seed, random_cases);
+            for (block, _) in (0..)
+                .zip(expected.iter().zip(&actual))
+                .filter(|(_, (e, a))| e != a)
+            {
+                let end = random_cases.min((block + 1) * BLOCK);
+                writeln!(
+                    report,
+                    "{}: random cases {}..{} of seed {:#x} differ",
+                    name,
+                    block * BLOCK,
+                    end,
+                    seed
+                )
+                .unwrap();
+            }
+        }
+    }
+    if !reader.0.is_empty() {
+        return Err("trailing data after the golden vectors".to_string());
+    }
+    for name in remaining.keys() {
+        writeln!(report, "`{}` has no golden vectors", name).unwrap();
+    }
+
+    if report.is_empty() {
+        Ok(())
+    } else {
+        Err(report)
+    }
+}
+
+const TRUNCATED: &str = "the golden vectors are truncated";
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.0.len() < len {
+            return None;
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Some(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let bytes = self.bytes(4).ok_or(TRUNCATED)?;
+        Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        let bytes = self.bytes(8).ok_or(TRUNCATED)?;
+        Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn value(&mut self, ty: ValType) -> Result<u64, String> {
+        match ty {
+            ValType::I32 => self.u32().map(u64::from),
+            ValType::I64 => self.u64(),
+        }
+    }
+}
+
+fn write_value(out: &mut Vec<u8>, ty: ValType, value: u64) {
+    match ty {
+        ValType::I32 => out.extend((value as u32).to_le_bytes()),
+        ValType::I64 => out.extend(value.to_le_bytes()),
+    }
+}
+
+/// The digests of the results of every [`BLOCK`] random cases
+fn random_digests(imp: &Implementation, seed: u64, cases: u32) -> Vec<u64> {
+    let mut rng = SplitMix64(seed);
+    let mut digests = Vec::new();
+    for i in 0..cases {
+        if i % BLOCK == 0 {
+            digests.push(0xcbf2_9ce4_8422_2325u64);
+        }
+        let args = random_args(imp.op, &mut rng);
+        let digest = digests.last_mut().unwrap();
+        *digest = (digest.rotate_left(5) ^ imp.call(&args)).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    }
+    digests
+}
+
+fn random_args(op: &Operation, rng: &mut SplitMix64) -> Vec<u64> {
+    loop {
+        let args = op
+            .params
+            .iter()
+            .map(|&ty| rng.next() & ty.mask())
+            .collect::<Vec<_>>();
+        if !op.traps(&args) {
+            return args;
+        }
+    }
+}
+
+/// The inputs every operation is tested with: all combinations of the edge cases of its operand type
+fn edge_cases(op: &Operation) -> Vec<Vec<u64>> {
+    let values = match op.operand() {
+        // the conversion boundaries are only interesting for unary operations
+        "f32" if op.params.len() == 1 => float_edges(8, 23, true),
+        "f64" if op.params.len() == 1 => float_edges(11, 52, true),
+        "f32" => float_edges(8, 23, false),
+        "f64" => float_edges(11, 52, false),
+        "i32" => int_edges(32),
+        _ => int_edges(64),
+    };
+    let mut cases = vec![vec![]];
+    for _ in op.params {
+        cases = cases
+            .into_iter()
+            .flat_map(|case| {
+                values.iter().map(move |&v| {
+                    let mut case = case.clone();
+                    case.push(v);
+                    case
+                })
+            })
+            .collect();
+    }
+    cases.retain(|args| !op.traps(args));
+    cases
+}
+
+fn float_edges(exponent_bits: u32, fraction_bits: u32, boundaries: bool) -> Vec<u64> {
+    let bits = |v: f64| {
+        if exponent_bits == 8 {
+            (v as f32).to_bits() as u64
+        } else {
+            v.to_bits()
+        }
+    };
+    let exponent_mask = ((1u64 << exponent_bits) - 1) << fraction_bits;
+    let quiet = 1 << (fraction_bits - 1);
+    let mut values = vec![
+        0,
+        1,
+        (1 << fraction_bits) - 1,
+        1 << fraction_bits,
+        bits(0.5),
+        bits(1.0),
+        bits(1.5),
+        bits(2.5),
+        exponent_mask - 1,
+        exponent_mask,
+        exponent_mask | quiet,
+        exponent_mask | 1,
+        exponent_mask | quiet | 1,
+    ];
+    if boundaries {
+        for exponent in [24, 31, 32, 53, 63, 64] {
+            let boundary = bits(2f64.powi(exponent));
+            values.extend([boundary - 1, boundary]);
+        }
+    }
+    let sign = 1 << (exponent_bits + fraction_bits);
+    let negative = values.iter().map(|v| v | sign).collect::<Vec<_>>();
+    values.extend(negative);
+    values.sort_unstable();
+    values.dedup();
+    values
+}
+
+fn int_edges(bits: u32) -> Vec<u64> {
+    let mask = if bits == 32 {
+        u32::MAX as u64
+    } else {
+        u64::MAX
+    };
+    let mut values = [
+        0,
+        1,
+        u64::MAX,
+        (1 << 24) + 1,
+        (1 << 53) + 1,
+        i32::MAX as u64,
+        i32::MIN as u64,
+        u32::MAX as u64,
+        i64::MAX as u64,
+        i64::MIN as u64,
+    ]
+    .iter()
+    .map(|v| v & mask)
+    .collect::<Vec<_>>();
+    values.sort_unstable();
+    values.dedup();
+    values
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x100_0000_01b3)
+    })
+}
+
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::implementations;
+
+    extern "C" fn __wasm_soft_float_f_32_add(a: u32, b: u32) -> u32 {
+        (f32::from_bits(a) + f32::from_bits(b)).to_bits()
+    }
+
+    extern "C" fn __wasm_soft_float_i_64_trunc_uf_32(v: u32) -> u64 {
+        assert!(!Operation::by_name("i_64_trunc_uf_32")
+            .unwrap()
+            .traps(&[v as u64]));
+        f32::from_bits(v) as u64
+    }
+
+    extern "C" fn __wasm_soft_float_f_64_convert_si_32(v: i32) -> u64 {
+        (v as f64).to_bits()
+    }
+
+    #[test]
+    fn replays_generated_vectors() {
+        let implementations = implementations![
+            unary: [
+                __wasm_soft_float_i_64_trunc_uf_32,
+                __wasm_soft_float_f_64_convert_si_32,
+            ],
+            binary: [__wasm_soft_float_f_32_add],
+        ];
+        let data = generate(&implementations, 10_000);
+        assert_eq!(replay(&data, &implementations), Ok(()));
+        assert_eq!(generate(&implementations, 10_000), data);
+    }
+
+    /// An `f_32_add` that flushes the smallest subnormal to zero and is wrong for some random inputs
+    fn wrong_add() -> [Implementation; 1] {
+        extern "C" fn __wasm_soft_float_f_32_add(a: u32, b: u32) -> u32 {
+            match super::tests::__wasm_soft_float_f_32_add(a, b) {
+                1 => 0,
+                r if a & 0xfff == 0xabc => r ^ 1,
+                r => r,
+            }
+        }
+        implementations![unary: [], binary: [__wasm_soft_float_f_32_add]]
+    }
+
+    #[test]
+    fn reports_differences() {
+        let data = generate(
+            &implementations![unary: [], binary: [__wasm_soft_float_f_32_add]],
+            10_000,
+        );
+
+        let report = replay(&data, &wrong_add()).unwrap_err();
+        assert!(report.starts_with("f_32_add(0x0, 0x1) = 0x0, expected 0x1\n"));
+        assert!(report.contains("\nf_32_add: random cases 0..4096 of seed "));
+
+        let report = replay(
+            &data,
+            &implementations![unary: [__wasm_soft_float_f_64_convert_si_32], binary: []],
+        );
+        assert_eq!(
+            report.unwrap_err(),
+            "`f_32_add` is not implemented anymore\n`f_64_convert_si_32` has no golden vectors\n"
+        );
+
+        assert!(replay(&data[..data.len() - 1], &[]).is_err());
+    }
+}
@@ -0,0 +1,474 @@
+//! Registry of all operations that are replaced by the transpiler and implemented by the backends.
+//!
+//! [`for_each_operation!`](crate::for_each_operation) is the single source of truth for the operations.
+//! Adding an operation there makes it known to the transpiler, the generated backends and the tests.
+
+use crate::float::{F32, F64};
+
+/// Calls the macro `$callback` with the list of all soft float operations.
+/// Any further arguments are passed to `$callback` first, followed by a `;`.
+///
+/// Every entry has the form
+/// `name: Instruction "wasm.instruction" [opcode bytes] (param types) -> result type, Category;`,
+/// e.g. `f_32_add: F32Add "f32.add" [0x92] (i32, i32) -> i32, Arithmetic;`.
+/// - `name` is the name of the operation. The backends implement it as `__wasm_soft_float_<name>`,
+///   host imports use it as is. These names are part of the ABI and must never change.
+/// - `Instruction` is the name of the instruction in `walrus`' `UnaryOp` / `BinaryOp`.
+///   The number of parameters determines which one of the two it is.
+/// - The types are the ones of the soft float function, so floats are passed as their bits.
+/// - `Category` is a variant of [`Category`].
+#[macro_export]
+macro_rules! for_each_operation {
+    ($callback: path $(, $($args: tt)*)?) => {
+        $callback! {
+            $($($args)*;)?
+            f_32_eq: F32Eq "f32.eq" [0x5b] (i32, i32) -> i32, Comparison;
+            f_32_ne: F32Ne "f32.ne" [0x5c] (i32, i32) -> i32, Comparison;
+            f_32_lt: F32Lt "f32.lt" [0x5d] (i32, i32) -> i32, Comparison;
+            f_32_gt: F32Gt "f32.gt" [0x5e] (i32, i32) -> i32, Comparison;
+            f_32_le: F32Le "f32.le" [0x5f] (i32, i32) -> i32, Comparison;
+            f_32_ge: F32Ge "f32.ge" [0x60] (i32, i32) -> i32, Comparison;
+            f_64_eq: F64Eq "f64.eq" [0x61] (i64, i64) -> i32, Comparison;
+            f_64_ne: F64Ne "f64.ne" [0x62] (i64, i64) -> i32, Comparison;
+            f_64_lt: F64Lt "f64.lt" [0x63] (i64, i64) -> i32, Comparison;
+            f_64_gt: F64Gt "f64.gt" [0x64] (i64, i64) -> i32, Comparison;
+            f_64_le: F64Le "f64.le" [0x65] (i64, i64) -> i32, Comparison;
+            f_64_ge: F64Ge "f64.ge" [0x66] (i64, i64) -> i32, Comparison;
+
+            f_32_abs: F32Abs "f32.abs" [0x8b] (i32) -> i32, Sign;
+            f_32_neg: F32Neg "f32.neg" [0x8c] (i32) -> i32, Sign;
+            f_32_ceil: F32Ceil "f32.ceil" [0x8d] (i32) -> i32, Rounding;
+            f_32_floor: F32Floor "f32.floor" [0x8e] (i32) -> i32, Rounding;
+            f_32_trunc: F32Trunc "f32.trunc" [0x8f] (i32) -> i32, Rounding;
+            f_32_nearest: F32Nearest "f32.nearest" [0x90] (i32) -> i32, Rounding;
+            f_32_sqrt: F32Sqrt "f32.sqrt" [0x91] (i32) -> i32, Arithmetic;
+            f_32_add: F32Add "f32.add" [0x92] (i32, i32) -> i32, Arithmetic;
+            f_32_sub: F32Sub "f32.sub" [0x93] (i32, i32) -> i32, Arithmetic;
+            f_32_mul: F32Mul "f32.mul" [0x94] (i32, i32) -> i32, Arithmetic;
+            f_32_div: F32Div "f32.div" [0x95] (i32, i32) -> i32, Arithmetic;
+            f_32_min: F32Min "f32.min" [0x96] (i32, i32) -> i32, Arithmetic;
+            f_32_max: F32Max "f32.max" [0x97] (i32, i32) -> i32, Arithmetic;
+            f_32_copysign: F32Copysign "f32.copysign" [0x98] (i32, i32) -> i32, Sign;
+
+            f_64_abs: F64Abs "f64.abs" [0x99] (i64) -> i64, Sign;
+            f_64_neg: F64Neg "f64.neg" [0x9a] (i64) -> i64, Sign;
+            f_64_ceil: F64Ceil "f64.ceil" [0x9b] (i64) -> i64, Rounding;
+            f_64_floor: F64Floor "f64.floor" [0x9c] (i64) -> i64, Rounding;
+            f_64_trunc: F64Trunc "f64.trunc" [0x9d] (i64) -> i64, Rounding;
+            f_64_nearest: F64Nearest "f64.nearest" [0x9e] (i64) -> i64, Rounding;
+            f_64_sqrt: F64Sqrt "f64.sqrt" [0x9f] (i64) -> i64, Arithmetic;
+            f_64_add: F64Add "f64.add" [0xa0] (i64, i64) -> i64, Arithmetic;
+            f_64_sub: F64Sub "f64.sub" [0xa1] (i64, i64) -> i64, Arithmetic;
+            f_64_mul: F64Mul "f64.mul" [0xa2] (i64, i64) -> i64, Arithmetic;
+            f_64_div: F64Div "f64.div" [0xa3] (i64, i64) -> i64, Arithmetic;
+            f_64_min: F64Min "f64.min" [0xa4] (i64, i64) -> i64, Arithmetic;
+            f_64_max: F64Max "f64.max" [0xa5] (i64, i64) -> i64, Arithmetic;
+            f_64_copysign: F64Copysign "f64.copysign" [0xa6] (i64, i64) -> i64, Sign;
+
+            i_32_trunc_sf_32: I32TruncSF32 "i32.trunc_f32_s" [0xa8] (i32) -> i32, Conversion;
+            i_32_trunc_uf_32: I32TruncUF32 "i32.trunc_f32_u" [0xa9] (i32) -> i32, Conversion;
+            i_32_trunc_sf_64: I32TruncSF64 "i32.trunc_f64_s" [0xaa] (i64) -> i32, Conversion;
+            i_32_trunc_uf_64: I32TruncUF64 "i32.trunc_f64_u" [0xab] (i64) -> i32, Conversion;
+            i_64_trunc_sf_32: I64TruncSF32 "i64.trunc_f32_s" [0xae] (i32) -> i64, Conversion;
+            i_64_trunc_uf_32: I64TruncUF32 "i64.trunc_f32_u" [0xaf] (i32) -> i64, Conversion;
+            i_64_trunc_sf_64: I64TruncSF64 "i64.trunc_f64_s" [0xb0] (i64) -> i64, Conversion;
+            i_64_trunc_uf_64: I64TruncUF64 "i64.trunc_f64_u" [0xb1] (i64) -> i64, Conversion;
+            f_32_convert_si_32: F32ConvertSI32 "f32.convert_i32_s" [0xb2] (i32) -> i32, Conversion;
+            f_32_convert_ui_32: F32ConvertUI32 "f32.convert_i32_u" [0xb3] (i32) -> i32, Conversion;
+            f_32_convert_si_64: F32ConvertSI64 "f32.convert_i64_s" [0xb4] (i64) -> i32, Conversion;
+            f_32_convert_ui_64: F32ConvertUI64 "f32.convert_i64_u" [0xb5] (i64) -> i32, Conversion;
+            f_32_demote_f_64: F32DemoteF64 "f32.demote_f64" [0xb6] (i64) -> i32, Conversion;
+            f_64_convert_si_32: F64ConvertSI32 "f64.convert_i32_s" [0xb7] (i32) -> i64, Conversion;
+            f_64_convert_ui_32: F64ConvertUI32 "f64.convert_i32_u" [0xb8] (i32) -> i64, Conversion;
+            f_64_convert_si_64: F64ConvertSI64 "f64.convert_i64_s" [0xb9] (i64) -> i64, Conversion;
+            f_64_convert_ui_64: F64ConvertUI64 "f64.convert_i64_u" [0xba] (i64) -> i64, Conversion;
+            f_64_promote_f_32: F64PromoteF32 "f64.promote_f32" [0xbb] (i32) -> i64, Conversion;
+            i_32_reinterpret_f_32: I32ReinterpretF32 "i32.reinterpret_f32" [0xbc] (i32) -> i32, Reinterpret;
+            i_64_reinterpret_f_64: I64ReinterpretF64 "i64.reinterpret_f64" [0xbd] (i64) -> i64, Reinterpret;
+            f_32_reinterpret_i_32: F32ReinterpretI32 "f32.reinterpret_i32" [0xbe] (i32) -> i32, Reinterpret;
+            f_64_reinterpret_i_64: F64ReinterpretI64 "f64.reinterpret_i64" [0xbf] (i64) -> i64, Reinterpret;
+
+            i_32_trunc_s_sat_f_32: I32TruncSSatF32 "i32.trunc_sat_f32_s" [0xfc, 0x00] (i32) -> i32, Conversion;
+            i_32_trunc_u_sat_f_32: I32TruncUSatF32 "i32.trunc_sat_f32_u" [0xfc, 0x01] (i32) -> i32, Conversion;
+            i_32_trunc_s_sat_f_64: I32TruncSSatF64 "i32.trunc_sat_f64_s" [0xfc, 0x02] (i64) -> i32, Conversion;
+            i_32_trunc_u_sat_f_64: I32TruncUSatF64 "i32.trunc_sat_f64_u" [0xfc, 0x03] (i64) -> i32, Conversion;
+            i_64_trunc_s_sat_f_32: I64TruncSSatF32 "i64.trunc_sat_f32_s" [0xfc, 0x04] (i32) -> i64, Conversion;
+            i_64_trunc_u_sat_f_32: I64TruncUSatF32 "i64.trunc_sat_f32_u" [0xfc, 0x05] (i32) -> i64, Conversion;
+            i_64_trunc_s_sat_f_64: I64TruncSSatF64 "i64.trunc_sat_f64_s" [0xfc, 0x06] (i64) -> i64, Conversion;
+            i_64_trunc_u_sat_f_64: I64TruncUSatF64 "i64.trunc_sat_f64_u" [0xfc, 0x07] (i64) -> i64, Conversion;
+        }
+    };
+}
+
+/// A soft float operation, see [`for_each_operation!`](crate::for_each_operation)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Operation {
+    /// Name of the operation, e.g. `f_32_add`
+    pub name: &'static str,
+    /// Name of the replaced instruction in the text format, e.g. `f32.add`
+    pub instruction: &'static str,
+    /// Binary encoding of the replaced instruction
+    pub opcode: &'static [u8],
+    /// Parameter types of the soft float function
+    pub params: &'static [ValType],
+    /// Result type of the soft float function
+    pub result: ValType,
+    /// Rough classification of the operation
+    pub category: Category,
+}
+
+impl Operation {
+    /// Looks up the operation with the given name
+    pub fn by_name(name: &str) -> Option<&'static Operation> {
+        OPERATIONS.iter().find(|op| op.name == name)
+    }
+
+    /// Name of the function implementing this operation in the backends,
+    /// e.g. `__wasm_soft_float_f_32_add`
+    pub fn symbol(&self) -> String {
+        format!("{}{}", crate::SOFTFLOAT_PREFIX, self.name)
+    }
+
+    /// The type of the operands of the replaced instruction, e.g. `i64` for `f32.convert_i64_s`
+    pub fn operand(&self) -> &'static str {
+        let (ty, rest) = self.instruction.split_once('.').unwrap();
+        rest.split('_')
+            .find(|part| matches!(*part, "f32" | "f64" | "i32" | "i64"))
+            .unwrap_or(ty)
+    }
+
+    /// Whether the operation traps for `args`, i.e. it is a non-saturating truncation of a value that is out of range
+    pub fn traps(&self, args: &[u64]) -> bool {
+        let (ty, rest) = self.instruction.split_once('.').unwrap();
+        if !rest.starts_with("trunc_f") {
+            return false;
+        }
+        let (bits, signed) = (if ty == "i32" { 32 } else { 64 }, rest.ends_with("_s"));
+        match self.operand() {
+            "f32" => F32::from_bits(args[0] as u32).trunc_traps(bits, signed),
+            _ => F64::from_bits(args[0]).trunc_traps(bits, signed),
+        }
+    }
+
+    /// Whether the result is a float computed from floats, which wasm allows to be any NaN, e.g. `f32.add`.
+    /// The sign and reinterpret operations keep the bits of NaNs and integers are never NaN.
+    pub fn returns_any_nan(&self) -> bool {
+        matches!(
+            self.category,
+            Category::Arithmetic | Category::Rounding | Category::Conversion
+        ) && self.instruction.starts_with('f')
+            && self.operand().starts_with('f')
+    }
+}
+
+/// The wasm types used by soft float functions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValType {
+    I32,
+    I64,
+}
+
+impl ValType {
+    /// The bits a value of this type uses in a `u64`
+    pub const fn mask(self) -> u64 {
+        match self {
+            ValType::I32 => u32::MAX as u64,
+            ValType::I64 => u64::MAX,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    /// Operations that only look at or change the sign bit
+    Sign,
+    Comparison,
+    /// Arithmetic operations that need to round their result
+    Arithmetic,
+    /// Rounding to an integral float
+    Rounding,
+    /// Conversions between floats and integers or between different float sizes
+    Conversion,
+    /// Conversions that keep the bits as they are
+    Reinterpret,
+}
+
+impl Category {
+    pub const ALL: [Category; 6] = [
+        Category::Sign,
+        Category::Comparison,
+        Category::Arithmetic,
+        Category::Rounding,
+        Category::Conversion,
+        Category::Reinterpret,
+    ];
+
+    /// Lowercase name of the category, e.g. `arithmetic`
+    pub const fn name(self) -> &'static str {
+        match self {
+            Category::Sign => "sign",
+            Category::Comparison => "comparison",
+            Category::Arithmetic => "arithmetic",
+            Category::Rounding => "rounding",
+            Category::Conversion => "conversion",
+            Category::Reinterpret => "reinterpret",
+        }
+    }
+
+    /// Looks up the category with the given [`name`](Category::name)
+    pub fn by_name(name: &str) -> Option<Category> {
+        Self::ALL.into_iter().find(|c| c.name() == name)
+    }
+}
+
+macro_rules! operations {
+    ($($name: ident: $instr: ident $text: literal [$($opcode: literal),+] ($($param: ident),*) -> $result: ident, $category: ident;)*) => {
+        /// All soft float operations
+        pub const OPERATIONS: &[Operation] = &[
+            $(
+                Operation {
+                    name: stringify!($name),
+                    instruction: $text,
+                    opcode: &[$($opcode),+],
+                    params: &[$(val_type!($param)),*],
+                    result: val_type!($result),
+                    category: Category::$category,
+                },
+            )*
+        ];
+    };
+}
+
+macro_rules! val_type {
+    (i32) => {
+        ValType::I32
+    };
+    (i64) => {
+        ValType::I64
+    };
+}
+
+for_each_operation!(operations);
+
+/// The types the `__wasm_soft_float_*` functions use for their parameters and results
+pub trait Bits: Copy {
+    fn from_bits(bits: u64) -> Self;
+    /// The bits zero extended to 64 bits
+    fn into_bits(self) -> u64;
+}
+
+macro_rules! impl_bits {
+    ($($t: ty: $unsigned: ty),*) => {
+        $(
+            impl Bits for $t {
+                fn from_bits(bits: u64) -> Self {
+                    bits as $t
+                }
+                fn into_bits(self) -> u64 {
+                    self as $unsigned as u64
+                }
+            }
+        )*
+    };
+}
+
+impl_bits!(u32: u32, i32: u32, u64: u64, i64: u64);
+
+/// An operation implemented by a backend
+pub struct Implementation {
+    pub op: &'static Operation,
+    /// Calls the operation with the bits of its arguments
+    call: fn(&[u64]) -> u64,
+}
+
+impl Implementation {
+    /// Wraps the function `symbol`, e.g. `__wasm_soft_float_f_32_add`.
+    /// Use [`implementations!`](crate::implementations) to create these.
+    pub fn new(symbol: &str, call: fn(&[u64]) -> u64) -> Self {
+        let op = symbol
+            .strip_prefix(crate::SOFTFLOAT_PREFIX)
+            .and_then(Operation::by_name)
+            .unwrap_or_else(|| panic!("`{}` is not a soft float operation", symbol));
+        Self { op, call }
+    }
+
+    /// Calls the operation with the bits of its arguments, returning the bits of its result
+    pub fn call(&self, args: &[u64]) -> u64 {
+        (self.call)(args) & self.op.result.mask()
+    }
+}
+
+/// Creates an array of [`Implementation`]s, either of all operations of a
+/// [`SoftFloatBackend`](crate::backend::SoftFloatBackend) or of the given unary and binary
+/// `__wasm_soft_float_*` functions
+#[macro_export]
+macro_rules! implementations {
+    ($backend: ty $(,)?) => {{
+        // `for_each_operation!` can only expand to items, so this defines a function returning the array
+        $crate::for_each_operation!($crate::__backend_implementations, $backend);
+        implementations()
+    }};
+    (unary: [$($unop: ident),* $(,)?], binary: [$($binop: ident),* $(,)?] $(,)?) => {
+        [
+            $(
+                $crate::ops::Implementation::new(stringify!($unop), |args| {
+                    use $crate::ops::Bits;
+                    $unop(Bits::from_bits(args[0])).into_bits()
+                }),
+            )*
+            $(
+                $crate::ops::Implementation::new(stringify!($binop), |args| {
+                    use $crate::ops::Bits;
+                    $binop(Bits::from_bits(args[0]), Bits::from_bits(args[1])).into_bits()
+                }),
+            )*
+        ]
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __backend_implementations {
+    ($backend: ty; $($name: ident: $instr: ident $text: literal [$($opcode: literal),+] ($($param: ident),*) -> $result: ident, $category: ident;)*) => {
+        fn implementations() -> ::std::vec::Vec<$crate::ops::Implementation> {
+            ::std::vec![
+                $(
+                    $crate::__backend_implementation!($backend, $name ($($param),*) -> $result),
+                )*
+            ]
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __backend_implementation {
+    ($backend: ty, $name: ident ($a: ident) -> $result: ident) => {
+        $crate::ops::Implementation::new(concat!("__wasm_soft_float_", stringify!($name)), |args| {
+            use $crate::{
+                backend::{SoftFloatBackend, WasmAbi},
+                ops::Bits,
+            };
+            let result: $result =
+                <$backend as SoftFloatBackend>::$name(WasmAbi::from_abi(<$a as Bits>::from_bits(args[0])))
+                    .into_abi();
+            result.into_bits()
+        })
+    };
+    ($backend: ty, $name: ident ($a: ident, $b: ident) -> $result: ident) => {
+        $crate::ops::Implementation::new(concat!("__wasm_soft_float_", stringify!($name)), |args| {
+            use $crate::{
+                backend::{SoftFloatBackend, WasmAbi},
+                ops::Bits,
+            };
+            let result: $result = <$backend as SoftFloatBackend>::$name(
+                WasmAbi::from_abi(<$a as Bits>::from_bits(args[0])),
+                WasmAbi::from_abi(<$b as Bits>::from_bits(args[1])),
+            )
+            .into_abi();
+            result.into_bits()
+        })
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_unique() {
+        for (i, op) in OPERATIONS.iter().enumerate() {
+            assert!(
+                OPERATIONS[i + 1..]
+                    .iter()
+                    .all(|other| other.name != op.name),
+                "{} is registered twice",
+                op.name
+            );
+            assert!(
+                OPERATIONS[i + 1..]
+                    .iter()
+                    .all(|other| other.opcode != op.opcode),
+                "opcode of {} is registered twice",
+                op.name
+            );
+        }
+    }
+
+    #[test]
+    fn operand_types() {
+        let ty = |name| Operation::by_name(name).unwrap().operand();
+        assert_eq!(ty("f_32_add"), "f32");
+        assert_eq!(ty("f_32_demote_f_64"), "f64");
+        assert_eq!(ty("f_64_convert_si_32"), "i32");
+        assert_eq!(ty("i_32_trunc_sf_64"), "f64");
+        assert_eq!(ty("i_64_trunc_u_sat_f_32"), "f32");
+        assert_eq!(ty("f_64_reinterpret_i_64"), "i64");
+    }
+
+    #[test]
+    fn signature_matches_instruction() {
+        for op in OPERATIONS {
+            // e.g. `i32.trunc_f64_s` takes an f64 (passed as i64) and returns an i32
+            let ty = op.instruction.split_once('.').unwrap().0;
+            let result = match op.category {
+                Category::Comparison => "i32",
+                _ => ty,
+            };
+            assert_eq!(op.result, bits_type(result), "{}", op.name);
+            assert!(
+                op.params.iter().all(|&p| p == bits_type(op.operand())),
+                "{}",
+                op.name
+            );
+        }
+    }
+
+    #[test]
+    fn nan_results() {
+        let returns_any_nan = |name| Operation::by_name(name).unwrap().returns_any_nan();
+        for name in [
+            "f_32_add",
+            "f_64_sqrt",
+            "f_32_nearest",
+            "f_64_max",
+            "f_64_promote_f_32",
+        ] {
+            assert!(returns_any_nan(name), "{}", name);
+        }
+        for name in [
+            "f_32_neg",
+            "f_64_copysign",
+            "f_32_reinterpret_i_32",
+            "f_64_convert_ui_32",
+            "i_32_trunc_sf_64",
+            "f_32_lt",
+        ] {
+            assert!(!returns_any_nan(name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn traps_out_of_range() {
+        let op = Operation::by_name("i_32_trunc_sf_64").unwrap();
+        assert!(!op.traps(&[(-2147483648.9f64).to_bits()]));
+        assert!(op.traps(&[(-2147483649f64).to_bits()]));
+        assert!(!op.traps(&[(2147483647.9f64).to_bits()]));
+        assert!(op.traps(&[(2147483648f64).to_bits()]));
+        assert!(op.traps(&[f64::NAN.to_bits()]));
+        let op = Operation::by_name("i_64_trunc_uf_32").unwrap();
+        assert!(!op.traps(&[(-0.9f32).to_bits() as u64]));
+        assert!(op.traps(&[(-1f32).to_bits() as u64]));
+        let op = Operation::by_name("i_64_trunc_s_sat_f_32").unwrap();
+        assert!(!op.traps(&[f32::NAN.to_bits() as u64]));
+        assert!(!Operation::by_name("f_32_add").unwrap().traps(&[0, 0]));
+    }
+
+    fn bits_type(ty: &str) -> ValType {
+        match ty {
+            "f32" | "i32" => ValType::I32,
+            "f64" | "i64" => ValType::I64,
+            _ => panic!("unknown type {}", ty),
+        }
+    }
+}
@@ -1,58 +1,572 @@
 //! Some helpers to test a backend against the wasm spec
 
-use crate::float::*;
-use crate::*;
+use std::{
+    fmt::Debug,
+    panic::{self, UnwindSafe},
+    sync::Once,
+    thread,
+};
 
+use crate::{
+    backend::SoftFloatBackend,
+    float::{F32, F64},
+    testfloat::{f32_operands, f64_operands, Level},
+    trunc::TRAP_MESSAGE,
+};
+
+/// Checks a property of [`test`](crate::test) with the operations `$ops` on random inputs
 #[macro_export]
 macro_rules! impl_test_fn {
-    ($name:ident ($($arg:ident : $t:ty),*)) => {
+    ($ops: ident, $name: ident: $property: ident ($($arg: ident : $t: ty),*)) => {
         quickcheck::quickcheck! {
             fn $name($($arg: $t),*) -> bool {
-                $crate::test::$name(BINOPS32, $($arg),*)
+                $crate::test::$property(&$ops, $($arg),*)
             }
         }
     };
 }
 
-/// Implements the test suite for a backend
+/// Implements the test suite for a [`SoftFloatBackend`].
 /// In order to use this, you need to have a `quickcheck` dev-dependency.
-/// Also make sure to have the `__wasm_soft_float_*` functions in scope.
 #[doc(hidden)]
 #[macro_export]
-#[allow(clippy::crate_in_macro_def)]
 macro_rules! impl_tests {
-    () => {
+    ($backend: ty $(,)?) => {
         #[cfg(test)]
         mod tests {
             use super::*;
-            const BINOPS32: $crate::test::Binops32 = $crate::test::Binops32 {
-                __wasm_soft_float_f_32_add,
-                __wasm_soft_float_f_32_sub,
-            };
+            const OPS32: $crate::test::Ops<u32> = $crate::test::Ops::f32::<$backend>();
+            const OPS64: $crate::test::Ops<u64> = $crate::test::Ops::f64::<$backend>();
+
+            $crate::impl_test_fn!(OPS32, add_neg_is_zero_32: add_neg_is_zero(a: u32));
+            $crate::impl_test_fn!(OPS64, add_neg_is_zero_64: add_neg_is_zero(a: u64));
+            $crate::impl_test_fn!(OPS32, sub_is_add_neg_32: sub_is_add_neg(a: u32, b: u32));
+            $crate::impl_test_fn!(OPS64, sub_is_add_neg_64: sub_is_add_neg(a: u64, b: u64));
+            $crate::impl_test_fn!(OPS32, is_commutative_32: is_commutative(a: u32, b: u32));
+            $crate::impl_test_fn!(OPS64, is_commutative_64: is_commutative(a: u64, b: u64));
+            $crate::impl_test_fn!(OPS32, identities_hold_32: identities_hold(a: u32));
+            $crate::impl_test_fn!(OPS64, identities_hold_64: identities_hold(a: u64));
+            $crate::impl_test_fn!(OPS32, sign_is_symmetric_32: sign_is_symmetric(a: u32, b: u32));
+            $crate::impl_test_fn!(OPS64, sign_is_symmetric_64: sign_is_symmetric(a: u64, b: u64));
+            $crate::impl_test_fn!(OPS32, signaling_nans_are_quieted_32: signaling_nans_are_quieted(payload: u32, b: u32));
+            $crate::impl_test_fn!(OPS64, signaling_nans_are_quieted_64: signaling_nans_are_quieted(payload: u64, b: u64));
+            $crate::impl_test_fn!(OPS32, sqrt_of_square_32: sqrt_of_square(significand: i16, exponent: i8));
+            $crate::impl_test_fn!(OPS64, sqrt_of_square_64: sqrt_of_square(significand: i16, exponent: i8));
+            $crate::impl_test_fn!(OPS32, rounding_is_monotonic_32: rounding_is_monotonic(a: u32, b: u32));
+            $crate::impl_test_fn!(OPS64, rounding_is_monotonic_64: rounding_is_monotonic(a: u64, b: u64));
+            $crate::impl_test_fn!(OPS32, min_max_follow_the_spec_32: min_max_follow_the_spec(a: u32, b: u32));
+            $crate::impl_test_fn!(OPS64, min_max_follow_the_spec_64: min_max_follow_the_spec(a: u64, b: u64));
+            $crate::impl_test_fn!(OPS32, conversions_round_trip_32: conversions_round_trip(v: i64, shift: u8));
+            $crate::impl_test_fn!(OPS64, conversions_round_trip_64: conversions_round_trip(v: i64, shift: u8));
+            $crate::impl_test_fn!(OPS32, truncation_traps_exactly_32: truncation_traps_exactly(a: u32));
+            $crate::impl_test_fn!(OPS64, truncation_traps_exactly_64: truncation_traps_exactly(a: u64));
+            $crate::impl_test_fn!(OPS32, truncation_saturates_32: truncation_saturates(a: u32));
+            $crate::impl_test_fn!(OPS64, truncation_saturates_64: truncation_saturates(a: u64));
+            $crate::impl_test_fn!(OPS32, truncation_near_integers_32: truncation_near_integers(v: i64, shift: u8, fraction: i8));
+            $crate::impl_test_fn!(OPS64, truncation_near_integers_64: truncation_near_integers(v: i64, shift: u8, fraction: i8));
+            $crate::impl_test_fn!(OPS32, comparison_trichotomy_32: comparison_trichotomy(a: u32, b: u32));
+            $crate::impl_test_fn!(OPS64, comparison_trichotomy_64: comparison_trichotomy(a: u64, b: u64));
+
+            quickcheck::quickcheck! {
+                fn promote_demote_round_trip(v: u32) -> bool {
+                    $crate::test::promote_demote_round_trip::<$backend>(v)
+                }
+            }
+
+            #[test]
+            fn min_max_of_zeros() {
+                $crate::test::min_max_of_zeros(&OPS32);
+                $crate::test::min_max_of_zeros(&OPS64);
+            }
 
-            impl_test_fn!(add_neg_is_zero(a: u32));
-            impl_test_fn!(sub_is_add_neg(a: u32, b: u32));
+            #[test]
+            fn testfloat_level_1() {
+                $crate::test::testfloat32(&OPS32, $crate::testfloat::Level::One);
+                $crate::test::testfloat64(&OPS64, $crate::testfloat::Level::One);
+            }
+
+            /// This takes a while, run it with `cargo test --release -- --ignored testfloat`.
+            #[test]
+            #[ignore]
+            fn testfloat_level_2() {
+                $crate::test::testfloat32(&OPS32, $crate::testfloat::Level::Two);
+                $crate::test::testfloat64(&OPS64, $crate::testfloat::Level::Two);
+            }
         }
     };
 }
 
-pub struct Binops32 {
-    pub __wasm_soft_float_f_32_add: Binop32,
-    pub __wasm_soft_float_f_32_sub: Binop32,
+/// The operations of a backend on `f32` (`T = u32`) or `f64` (`T = u64`), on the bits of the floats
+pub struct Ops<T> {
+    pub add: Binop<T>,
+    pub sub: Binop<T>,
+    pub mul: Binop<T>,
+    pub div: Binop<T>,
+    pub neg: Unop<T>,
+    pub eq: Comparison<T>,
+    pub ne: Comparison<T>,
+    pub lt: Comparison<T>,
+    pub gt: Comparison<T>,
+    pub le: Comparison<T>,
+    pub ge: Comparison<T>,
+    pub from_i32: fn(i32) -> T,
+    pub from_u32: fn(u32) -> T,
+    pub from_i64: fn(i64) -> T,
+    pub from_u64: fn(u64) -> T,
+    pub ceil: Unop<T>,
+    pub floor: Unop<T>,
+    pub trunc: Unop<T>,
+    pub nearest: Unop<T>,
+    pub to_i32: Truncation<T, i32>,
+    pub to_u32: Truncation<T, u32>,
+    pub to_i64: Truncation<T, i64>,
+    pub to_u64: Truncation<T, u64>,
+    pub to_i32_sat: Truncation<T, i32>,
+    pub to_u32_sat: Truncation<T, u32>,
+    pub to_i64_sat: Truncation<T, i64>,
+    pub to_u64_sat: Truncation<T, u64>,
+    pub sqrt: Unop<T>,
+    pub min: Binop<T>,
+    pub max: Binop<T>,
+}
+
+macro_rules! impl_ops {
+    (
+        $name: ident: $float: ident ($bits: ty),
+        binops: [$($binop: ident: $binop_fn: ident),*],
+        comparisons: [$($comparison: ident: $comparison_fn: ident),*],
+        unops: [$($unop: ident: $unop_fn: ident),*],
+        from: [$($from: ident: $from_fn: ident),*],
+        to: [$($to: ident: $to_fn: ident),*] $(,)?
+    ) => {
+        impl Ops<$bits> {
+            #[doc = concat!("The `", stringify!($name), "` operations of the backend `B`")]
+            pub const fn $name<B: SoftFloatBackend>() -> Self {
+                Self {
+                    $($binop: |a, b| B::$binop_fn($float::from_bits(a), $float::from_bits(b)).to_bits(),)*
+                    $($comparison: |a, b| B::$comparison_fn($float::from_bits(a), $float::from_bits(b)) as u32,)*
+                    $($unop: |v| B::$unop_fn($float::from_bits(v)).to_bits(),)*
+                    $($from: |v| B::$from_fn(v).to_bits(),)*
+                    $($to: |v| B::$to_fn($float::from_bits(v)),)*
+                }
+            }
+        }
+    };
+}
+
+impl_ops!(
+    f32: F32(u32),
+    binops: [add: f_32_add, sub: f_32_sub, mul: f_32_mul, div: f_32_div, min: f_32_min, max: f_32_max],
+    comparisons: [eq: f_32_eq, ne: f_32_ne, lt: f_32_lt, gt: f_32_gt, le: f_32_le, ge: f_32_ge],
+    unops: [
+        neg: f_32_neg, ceil: f_32_ceil, floor: f_32_floor, trunc: f_32_trunc, nearest: f_32_nearest,
+        sqrt: f_32_sqrt
+    ],
+    from: [
+        from_i32: f_32_convert_si_32, from_u32: f_32_convert_ui_32,
+        from_i64: f_32_convert_si_64, from_u64: f_32_convert_ui_64
+    ],
+    to: [
+        to_i32: i_32_trunc_sf_32, to_u32: i_32_trunc_uf_32, to_i64: i_64_trunc_sf_32, to_u64: i_64_trunc_uf_32,
+        to_i32_sat: i_32_trunc_s_sat_f_32, to_u32_sat: i_32_trunc_u_sat_f_32,
+        to_i64_sat: i_64_trunc_s_sat_f_32, to_u64_sat: i_64_trunc_u_sat_f_32
+    ],
+);
+
+impl_ops!(
+    f64: F64(u64),
+    binops: [add: f_64_add, sub: f_64_sub, mul: f_64_mul, div: f_64_div, min: f_64_min, max: f_64_max],
+    comparisons: [eq: f_64_eq, ne: f_64_ne, lt: f_64_lt, gt: f_64_gt, le: f_64_le, ge: f_64_ge],
+    unops: [
+        neg: f_64_neg, ceil: f_64_ceil, floor: f_64_floor, trunc: f_64_trunc, nearest: f_64_nearest,
+        sqrt: f_64_sqrt
+    ],
+    from: [
+        from_i32: f_64_convert_si_32, from_u32: f_64_convert_ui_32,
+        from_i64: f_64_convert_si_64, from_u64: f_64_convert_ui_64
+    ],
+    to: [
+        to_i32: i_32_trunc_sf_64, to_u32: i_32_trunc_uf_64, to_i64: i_64_trunc_sf_64, to_u64: i_64_trunc_uf_64,
+        to_i32_sat: i_32_trunc_s_sat_f_64, to_u32_sat: i_32_trunc_u_sat_f_64,
+        to_i64_sat: i_64_trunc_s_sat_f_64, to_u64_sat: i_64_trunc_u_sat_f_64
+    ],
+);
+
+pub type Unop<T> = fn(T) -> T;
+pub type Binop<T> = fn(T, T) -> T;
+pub type Comparison<T> = fn(T, T) -> u32;
+pub type Truncation<T, I> = fn(T) -> I;
+
+/// The native implementation of a binary operation
+type Reference<T> = fn(T, T) -> T;
+
+/// The bits of an `f32` (`u32`) or an `f64` (`u64`), so that the properties are written once for both
+pub trait Float: Copy + PartialEq + Debug {
+    /// The number of bits of the significand, without the implicit bit
+    const FRACTION_BITS: u32;
+
+    /// The bits of `v`, rounded to `f32` for `u32`
+    fn from_f64(v: f64) -> Self;
+
+    /// The value of the bits, which is exact since every `f32` is an `f64`
+    fn to_f64(self) -> f64;
+
+    fn is_nan(self) -> bool {
+        self.to_f64().is_nan()
+    }
+
+    /// A signaling NaN with the low bits of `payload` as its payload, which is never zero
+    fn signaling_nan(payload: Self) -> Self;
+
+    /// Whether this is a quiet NaN, which wasm calls an arithmetic NaN
+    fn is_quiet_nan(self) -> bool;
+}
+
+impl Float for u32 {
+    const FRACTION_BITS: u32 = 23;
+
+    fn from_f64(v: f64) -> Self {
+        (v as f32).to_bits()
+    }
+
+    fn to_f64(self) -> f64 {
+        f32::from_bits(self) as f64
+    }
+
+    fn signaling_nan(payload: Self) -> Self {
+        0x7f80_0000 | (payload & 0x003f_ffff).max(1)
+    }
+
+    fn is_quiet_nan(self) -> bool {
+        self & 0x7fc0_0000 == 0x7fc0_0000
+    }
 }
 
-pub type Binop32 = extern "C" fn(u32, u32) -> u32;
+impl Float for u64 {
+    const FRACTION_BITS: u32 = 52;
+
+    fn from_f64(v: f64) -> Self {
+        v.to_bits()
+    }
+
+    fn to_f64(self) -> f64 {
+        f64::from_bits(self)
+    }
+
+    fn signaling_nan(payload: Self) -> Self {
+        0x7ff0_0000_0000_0000 | (payload & 0x0007_ffff_ffff_ffff).max(1)
+    }
+
+    fn is_quiet_nan(self) -> bool {
+        self & 0x7ff8_0000_0000_0000 == 0x7ff8_0000_0000_0000
+    }
+}
+
+/// Whether two results are equal. The spec does not specify the payload of NaN results, so all NaNs are.
+fn same<T: Float>(a: T, b: T) -> bool {
+    a == b || (a.is_nan() && b.is_nan())
+}
+
+pub fn add_neg_is_zero<T: Float>(ops: &Ops<T>, a: T) -> bool {
+    let v = a.to_f64();
+    v.is_infinite() || v.is_nan() || (ops.add)(a, (ops.neg)(a)) == T::from_f64(0.0)
+}
+
+pub fn sub_is_add_neg<T: Float>(ops: &Ops<T>, a: T, b: T) -> bool {
+    same((ops.sub)(a, b), (ops.add)(a, (ops.neg)(b)))
+}
 
-pub fn add_neg_is_zero(binops: Binops32, a: u32) -> bool {
-    let fa = F32::from_bits(a);
-    fa.is_infinite()
-        || fa.is_nan()
-        || (binops.__wasm_soft_float_f_32_add)(a, __wasm_soft_float_f_32_neg(a)) == 0f32.to_bits()
+/// Swapping the operands of `add`, `mul`, `min`, `max` and the comparisons does not change the result
+pub fn is_commutative<T: Float>(ops: &Ops<T>, a: T, b: T) -> bool {
+    [ops.add, ops.mul, ops.min, ops.max]
+        .into_iter()
+        .all(|op| same(op(a, b), op(b, a)))
+        && (ops.eq)(a, b) == (ops.eq)(b, a)
+        && (ops.ne)(a, b) == (ops.ne)(b, a)
+        && (ops.lt)(a, b) == (ops.gt)(b, a)
+        && (ops.le)(a, b) == (ops.ge)(b, a)
 }
 
-pub fn sub_is_add_neg(binops: Binops32, a: u32, b: u32) -> bool {
-    f32::from_bits(a).is_nan()
-        || f32::from_bits(b).is_nan()
-        || (binops.__wasm_soft_float_f_32_sub)(a, b)
-            == (binops.__wasm_soft_float_f_32_add)(a, __wasm_soft_float_f_32_neg(b))
+/// `a * 1`, `1 * a`, `a / 1` and `a + -0` are `a`, even if it is zero, infinite or NaN
+pub fn identities_hold<T: Float>(ops: &Ops<T>, a: T) -> bool {
+    let (one, neg_zero) = (T::from_f64(1.0), T::from_f64(-0.0));
+    same((ops.mul)(a, one), a)
+        && same((ops.mul)(one, a), a)
+        && same((ops.div)(a, one), a)
+        && same((ops.add)(a, neg_zero), a)
+}
+
+/// Rounding to nearest is symmetric, so negating operands negates the result:
+/// `-a * b == -(a * b)`, `-a / b == -(a / b)` and `-a + -b == -(a + b)` unless `a + b` is an exact zero,
+/// which is always `+0`
+pub fn sign_is_symmetric<T: Float>(ops: &Ops<T>, a: T, b: T) -> bool {
+    let neg = ops.neg;
+    let sum = (ops.add)(a, b);
+    same((ops.mul)(neg(a), b), neg((ops.mul)(a, b)))
+        && same((ops.div)(neg(a), b), neg((ops.div)(a, b)))
+        && (sum.to_f64() == 0.0 || same((ops.add)(neg(a), neg(b)), neg(sum)))
+}
+
+/// Wasm only produces quiet NaNs, so the arithmetic and rounding operations quiet a signaling NaN operand
+pub fn signaling_nans_are_quieted<T: Float>(ops: &Ops<T>, payload: T, b: T) -> bool {
+    let nan = T::signaling_nan(payload);
+    [ops.add, ops.sub, ops.mul, ops.div, ops.min, ops.max]
+        .into_iter()
+        .all(|op| op(nan, b).is_quiet_nan() && op(b, nan).is_quiet_nan())
+        && [ops.sqrt, ops.ceil, ops.floor, ops.trunc, ops.nearest]
+            .into_iter()
+            .all(|op| op(nan).is_quiet_nan())
+}
+
+/// `sqrt(x * x) == |x|` for values with few significant bits and a moderate exponent,
+/// whose square is an exact normal number even as `f32`
+pub fn sqrt_of_square<T: Float>(ops: &Ops<T>, significand: i16, exponent: i8) -> bool {
+    // at most 11 significant bits and exponents between -42 and 42
+    let x = f64::from(significand >> 4) * 2f64.powi(i32::from(exponent) / 3);
+    (ops.sqrt)((ops.mul)(T::from_f64(x), T::from_f64(x))) == T::from_f64(x.abs())
+}
+
+/// `ceil`, `floor`, `trunc` and `nearest` are monotonic and return one of the two integers next to their
+/// operand, with its sign. `floor` does not round up and `ceil` does not round down.
+pub fn rounding_is_monotonic<T: Float>(ops: &Ops<T>, a: T, b: T) -> bool {
+    let (x, y) = (a.to_f64(), b.to_f64());
+    let roundings = [ops.ceil, ops.floor, ops.trunc, ops.nearest];
+    if x.is_nan() {
+        return roundings.into_iter().all(|round| round(a).is_nan());
+    }
+    let (small, large) = if x <= y { (a, b) } else { (b, a) };
+    roundings.into_iter().all(|round| {
+        let r = round(a).to_f64();
+        r == r.trunc()
+            && r.is_sign_negative() == x.is_sign_negative()
+            && x.floor() <= r
+            && r <= x.ceil()
+            && (y.is_nan() || round(small).to_f64() <= round(large).to_f64())
+    }) && (ops.floor)(a).to_f64() <= x
+        && (ops.ceil)(a).to_f64() >= x
+}
+
+/// `min` and `max` return NaN if either operand is NaN and the smaller or larger operand otherwise,
+/// where `-0` is smaller than `+0`
+pub fn min_max_follow_the_spec<T: Float>(ops: &Ops<T>, a: T, b: T) -> bool {
+    let (min, max) = (ops.min, ops.max);
+    let (x, y) = (a.to_f64(), b.to_f64());
+    if x.is_nan() || y.is_nan() {
+        return min(a, b).is_nan() && max(a, b).is_nan();
+    }
+    let (smaller, larger) = if x < y || (x == y && x.is_sign_negative()) {
+        (a, b)
+    } else {
+        (b, a)
+    };
+    min(a, b) == smaller && max(a, b) == larger
+}
+
+/// Checks `min` and `max` of all combinations of zeros
+pub fn min_max_of_zeros<T: Float>(ops: &Ops<T>) {
+    let (min, max) = (ops.min, ops.max);
+    let (zero, neg_zero) = (T::from_f64(0.0), T::from_f64(-0.0));
+    for (a, b) in [
+        (zero, neg_zero),
+        (neg_zero, zero),
+        (zero, zero),
+        (neg_zero, neg_zero),
+    ] {
+        let (expected_min, expected_max) = match (a == zero, b == zero) {
+            (true, true) => (zero, zero),
+            (false, false) => (neg_zero, neg_zero),
+            _ => (neg_zero, zero),
+        };
+        assert_eq!(min(a, b), expected_min, "min({:#x?}, {:#x?})", a, b);
+        assert_eq!(max(a, b), expected_max, "max({:#x?}, {:#x?})", a, b);
+    }
+}
+
+/// Integers with at most as many significant bits as the significand are converted exactly,
+/// and truncating them gives the integer again
+pub fn conversions_round_trip<T: Float>(ops: &Ops<T>, v: i64, shift: u8) -> bool {
+    // keeps the sign and at most `FRACTION_BITS + 1` significant bits
+    let v = v >> u32::from(shift % 64).max(62 - T::FRACTION_BITS);
+    let expected = T::from_f64(v as f64);
+    let round_trips = [
+        i32::try_from(v)
+            .ok()
+            .map(|v| (ops.from_i32)(v) == expected && (ops.to_i32)(expected) == v),
+        u32::try_from(v)
+            .ok()
+            .map(|v| (ops.from_u32)(v) == expected && (ops.to_u32)(expected) == v),
+        Some((ops.from_i64)(v) == expected && (ops.to_i64)(expected) == v),
+        u64::try_from(v)
+            .ok()
+            .map(|v| (ops.from_u64)(v) == expected && (ops.to_u64)(expected) == v),
+    ];
+    round_trips.into_iter().flatten().all(|ok| ok)
+}
+
+/// The trapping conversions trap exactly for NaN and the values whose truncation is out of range
+/// and truncate all others
+pub fn truncation_traps_exactly<T: Float + UnwindSafe>(ops: &Ops<T>, a: T) -> bool {
+    fn traps_exactly<T, I>(to: Truncation<T, I>, a: T) -> bool
+    where
+        T: Float + UnwindSafe,
+        I: TryFrom<i128> + PartialEq,
+    {
+        catch_trap(move || to(a)) == truncated(a.to_f64())
+    }
+    traps_exactly(ops.to_i32, a)
+        && traps_exactly(ops.to_u32, a)
+        && traps_exactly(ops.to_i64, a)
+        && traps_exactly(ops.to_u64, a)
+}
+
+/// The saturating conversions never trap, they convert NaN to zero and clamp the values that are out of range.
+/// Rust's `as` casts do exactly the same.
+pub fn truncation_saturates<T: Float>(ops: &Ops<T>, a: T) -> bool {
+    let v = a.to_f64();
+    (ops.to_i32_sat)(a) == v as i32
+        && (ops.to_u32_sat)(a) == v as u32
+        && (ops.to_i64_sat)(a) == v as i64
+        && (ops.to_u64_sat)(a) == v as u64
+}
+
+/// [`truncation_traps_exactly`] and [`truncation_saturates`] for values close to an integer of any magnitude,
+/// since random bits are rarely close to the boundaries of the integer types
+pub fn truncation_near_integers<T: Float + UnwindSafe>(
+    ops: &Ops<T>,
+    v: i64,
+    shift: u8,
+    fraction: i8,
+) -> bool {
+    let v = T::from_f64((v >> (shift % 64)) as f64 + fraction as f64 / 64.0);
+    truncation_traps_exactly(ops, v) && truncation_saturates(ops, v)
+}
+
+/// Calls `f` and returns `None` if it traps, i.e. panics with the message of [`trunc::trap`](crate::trunc::trap).
+/// The message of these panics is not printed, all other panics are passed on.
+pub fn catch_trap<R>(f: impl FnOnce() -> R + UnwindSafe) -> Option<R> {
+    static QUIET_TRAPS: Once = Once::new();
+    QUIET_TRAPS.call_once(|| {
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !is_trap(info.payload()) {
+                hook(info);
+            }
+        }));
+    });
+    match panic::catch_unwind(f) {
+        Ok(result) => Some(result),
+        Err(payload) if is_trap(&*payload) => None,
+        Err(payload) => panic::resume_unwind(payload),
+    }
+}
+
+fn is_trap(payload: &(dyn std::any::Any + Send)) -> bool {
+    payload.downcast_ref::<String>().map(String::as_str) == Some(TRAP_MESSAGE)
+        || payload.downcast_ref::<&str>() == Some(&TRAP_MESSAGE)
+}
+
+/// Truncates `v` to `I` like wasm, with native float instructions. `None` if the conversion traps.
+pub(crate) fn truncated<I: TryFrom<i128>>(v: f64) -> Option<I> {
+    // NaN and infinities are not finite, and everything below 2^100 converts to an i128 exactly
+    let v = v.trunc();
+    if v.is_finite() && v.abs() < 2f64.powi(100) {
+        I::try_from(v as i128).ok()
+    } else {
+        None
+    }
+}
+
+/// Exactly one of `a < b`, `a == b` and `a > b` holds, unless either is NaN and none does.
+/// `ne`, `le` and `ge` agree with them.
+pub fn comparison_trichotomy<T: Float>(ops: &Ops<T>, a: T, b: T) -> bool {
+    let (lt, eq, gt) = ((ops.lt)(a, b), (ops.eq)(a, b), (ops.gt)(a, b));
+    let unordered = a.is_nan() || b.is_nan();
+    lt + eq + gt == u32::from(!unordered)
+        && eq == u32::from(a.to_f64() == b.to_f64())
+        && (ops.ne)(a, b) == 1 - eq
+        && (ops.le)(a, b) == lt | eq
+        && (ops.ge)(a, b) == gt | eq
+}
+
+/// Promoting an `f32` and demoting it again gives the same value, and both are NaN if it is.
+/// Signaling NaNs are quieted by both conversions.
+pub fn promote_demote_round_trip<B: SoftFloatBackend>(v: u32) -> bool {
+    let promote = |v| B::f_64_promote_f_32(F32::from_bits(v)).to_bits();
+    let demote = |v| B::f_32_demote_f_64(F64::from_bits(v)).to_bits();
+    let promoted = promote(v);
+    let nan = u32::signaling_nan(v);
+    same(promoted, (f32::from_bits(v) as f64).to_bits())
+        && same(demote(promoted), v)
+        && promote(nan).is_quiet_nan()
+        && demote(u64::signaling_nan(v.into())).is_quiet_nan()
+}
+
+/// Checks the arithmetic operations against the native ones, which are correctly rounded,
+/// on every pair of [`testfloat`](crate::testfloat) operands of the given level.
+/// NaN results only have to be NaN.
+pub fn testfloat32(ops: &Ops<u32>, level: Level) {
+    let ops: [(&str, Binop<u32>, Reference<f32>); 4] = [
+        ("add", ops.add, |a, b| a + b),
+        ("sub", ops.sub, |a, b| a - b),
+        ("mul", ops.mul, |a, b| a * b),
+        ("div", ops.div, |a, b| a / b),
+    ];
+    for_each_pair(&f32_operands(level), |a, b| {
+        for (name, op, reference) in ops {
+            let (result, expected) = (op(a, b), reference(f32::from_bits(a), f32::from_bits(b)));
+            assert!(
+                result == expected.to_bits()
+                    || (f32::from_bits(result).is_nan() && expected.is_nan()),
+                "f32 {} of {:#x} and {:#x} is {:#x}, expected {:#x}",
+                name,
+                a,
+                b,
+                result,
+                expected.to_bits()
+            );
+        }
+    });
+}
+
+/// The `f64` version of [`testfloat32`]
+pub fn testfloat64(ops: &Ops<u64>, level: Level) {
+    let ops: [(&str, Binop<u64>, Reference<f64>); 4] = [
+        ("add", ops.add, |a, b| a + b),
+        ("sub", ops.sub, |a, b| a - b),
+        ("mul", ops.mul, |a, b| a * b),
+        ("div", ops.div, |a, b| a / b),
+    ];
+    for_each_pair(&f64_operands(level), |a, b| {
+        for (name, op, reference) in ops {
+            let (result, expected) = (op(a, b), reference(f64::from_bits(a), f64::from_bits(b)));
+            assert!(
+                result == expected.to_bits()
+                    || (f64::from_bits(result).is_nan() && expected.is_nan()),
+                "f64 {} of {:#x} and {:#x} is {:#x}, expected {:#x}",
+                name,
+                a,
+                b,
+                result,
+                expected.to_bits()
+            );
+        }
+    });
+}
+
+/// Calls `check` with every pair of `operands`, spread over all cores
+fn for_each_pair<T: Copy + Sync>(operands: &[T], check: impl Fn(T, T) + Sync) {
+    let threads = thread::available_parallelism().map_or(4, |n| n.get());
+    let chunk = operands.len().div_ceil(threads);
+    thread::scope(|scope| {
+        for chunk in operands.chunks(chunk) {
+            let check = &check;
+            scope.spawn(move || {
+                for &a in chunk {
+                    for &b in operands {
+                        check(a, b);
+                    }
+                }
+            });
+        }
+    });
 }
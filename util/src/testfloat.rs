@@ -0,0 +1,185 @@
+//! Systematic test cases modeled on the level 1 and level 2 case sets of
+//! [Berkeley TestFloat](http://www.jhauser.us/arithmetic/TestFloat.html).
+//!
+//! Random inputs rarely hit the interesting boundaries of an operation, like rounding ties, overflow into
+//! infinity, the transition between subnormal and normal numbers or carries from the significand into
+//! the exponent. Instead, the operands here combine every sign with exponents and significands from
+//! fixed patterns:
+//! - the exponents of zero and subnormals, the smallest normals, numbers around one, the largest finite
+//!   numbers and infinity / NaN, and for level 2 also the exponents where the ulp becomes one, where products
+//!   overflow or underflow and where conversions to integers start to fail
+//! - significands with few bits set, since these round exactly or produce ties, and with many bits set,
+//!   since these carry into the exponent when rounding up. Level 2 has every single bit, every run of
+//!   leading and trailing ones and every significand with a single bit cleared.
+//!
+//! Binary operations are tested with every pair of operands of the same level.
+
+/// How thorough the test cases are
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    /// A few thousand pairs of operands, quick enough for every test run
+    One,
+    /// Millions of pairs of operands
+    Two,
+}
+
+/// A binary floating point format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Format {
+    pub exponent_bits: u32,
+    pub fraction_bits: u32,
+}
+
+pub const F32: Format = Format {
+    exponent_bits: 8,
+    fraction_bits: 23,
+};
+
+pub const F64: Format = Format {
+    exponent_bits: 11,
+    fraction_bits: 52,
+};
+
+impl Format {
+    fn max_exponent(self) -> u64 {
+        (1 << self.exponent_bits) - 1
+    }
+
+    fn bias(self) -> u64 {
+        self.max_exponent() >> 1
+    }
+
+    /// The biased exponents of the operands
+    pub fn exponents(self, level: Level) -> Vec<u64> {
+        let (max, bias, fraction) = (self.max_exponent(), self.bias(), self.fraction_bits as u64);
+        let mut exponents = vec![0, 1, bias - 1, bias, bias + 1, max - 1, max];
+        if level == Level::Two {
+            exponents.extend([
+                2,
+                // adding these to one rounds
+                bias - fraction - 1,
+                bias - fraction,
+                bias - 2,
+                bias + 2,
+                // the ulp becomes one
+                bias + fraction - 1,
+                bias + fraction,
+                bias + fraction + 1,
+                // products and quotients overflow or underflow
+                bias / 2,
+                bias + bias / 2,
+                // conversions to integers overflow
+                bias + 31,
+                bias + 32,
+                bias + 63,
+                bias + 64,
+                max - 2,
+            ]);
+        }
+        exponents.sort_unstable();
+        exponents.dedup();
+        exponents
+    }
+
+    /// The significands (without the implicit bit) of the operands
+    pub fn significands(self, level: Level) -> Vec<u64> {
+        let bits = self.fraction_bits;
+        let all = (1u64 << bits) - 1;
+        let top = 1 << (bits - 1);
+        let mut significands = match level {
+            Level::One => vec![0, 1, 2, top, top | 1, all - 1, all],
+            Level::Two => {
+                let mut significands = self.significands(Level::One);
+                for i in 0..bits {
+                    significands.extend([
+                        // a single bit
+                        1 << i,
+                        // leading ones
+                        all & !((1 << i) - 1),
+                        // trailing ones
+                        (1 << (i + 1)) - 1,
+                        // a single cleared bit
+                        all & !(1 << i),
+                    ]);
+                }
+                significands
+            }
+        };
+        significands.sort_unstable();
+        significands.dedup();
+        significands
+    }
+
+    /// All combinations of the signs, exponents and significands, as bits
+    pub fn operands(self, level: Level) -> Vec<u64> {
+        let mut operands = Vec::new();
+        for sign in [0, 1] {
+            for &exponent in &self.exponents(level) {
+                for &significand in &self.significands(level) {
+                    operands.push(
+                        sign << (self.exponent_bits + self.fraction_bits)
+                            | exponent << self.fraction_bits
+                            | significand,
+                    );
+                }
+            }
+        }
+        operands
+    }
+}
+
+/// The `f32` operands of the given level, as bits
+pub fn f32_operands(level: Level) -> Vec<u32> {
+    F32.operands(level).into_iter().map(|v| v as u32).collect()
+}
+
+/// The `f64` operands of the given level, as bits
+pub fn f64_operands(level: Level) -> Vec<u64> {
+    F64.operands(level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn operands_hit_the_boundaries() {
+        let operands = f32_operands(Level::One);
+        for value in [
+            0.0f32,
+            -0.0,
+            1.0,
+            -1.5,
+            f32::MIN_POSITIVE,
+            f32::MAX,
+            f32::INFINITY,
+            f32::NEG_INFINITY,
+            f32::from_bits(1),
+            f32::from_bits(0x007f_ffff),
+        ] {
+            assert!(operands.contains(&value.to_bits()), "{}", value);
+        }
+        assert!(operands.iter().any(|v| f32::from_bits(*v).is_nan()));
+
+        let operands = f64_operands(Level::Two);
+        for value in [
+            2f64.powi(52),
+            2f64.powi(63) + 2048.0,
+            u64::MAX as f64,
+            0.5 + f64::EPSILON / 2.0,
+        ] {
+            assert!(operands.contains(&value.to_bits()), "{}", value);
+        }
+    }
+
+    #[test]
+    fn levels_grow() {
+        for format in [F32, F64] {
+            let one = format.operands(Level::One);
+            let two = format.operands(Level::Two);
+            assert!(one.len() < 200);
+            assert!(one.iter().all(|v| two.contains(v)));
+            assert!(two.len() > 20 * one.len());
+        }
+    }
+}
@@ -0,0 +1,217 @@
+//! The trapping float to int conversions.
+//!
+//! `i32.trunc_f32_s` and friends trap if their operand is NaN or if its truncation does not fit into the result
+//! type, and only then. The libraries behind the backends either saturate or have unspecified results for these
+//! inputs, so every backend calls [`check_f32`] or [`check_f64`] first and only converts valid operands:
+//! ```ignore
+//! fn i_32_trunc_sf_32(v: F32) -> i32 {
+//!     wasm_soft_float_utils::trunc::check_f32::<i32>(v.to_bits());
+//!     // convert `v`, which is in range now
+//! }
+//! ```
+//! In wasm, [`trap`] is the `unreachable` instruction. On the host it panics instead, so that tests can catch it
+//! with [`std::panic::catch_unwind`]. That is why [`export_backend!`](crate::export_backend) exports all functions
+//! with the `"C-unwind"` ABI, a panic must not unwind out of an `extern "C"` function.
+//!
+//! The saturating conversions (`i32.trunc_sat_f32_s` and friends) never trap. [`f32_to_int_sat`] and
+//! [`f64_to_int_sat`] implement them for all backends.
+
+use crate::float::{F32, F64};
+
+/// The result types of the float to int conversions
+pub trait Int: Copy {
+    const BITS: u32;
+    const SIGNED: bool;
+    const MIN: Self;
+    const MAX: Self;
+
+    /// The integer with the given sign and absolute value, which must be in range
+    fn from_magnitude(negative: bool, magnitude: u64) -> Self;
+}
+
+macro_rules! impl_int {
+    ($($t: ty: $signed: literal),*) => {
+        $(
+            impl Int for $t {
+                const BITS: u32 = <$t>::BITS;
+                const SIGNED: bool = $signed;
+                const MIN: Self = <$t>::MIN;
+                const MAX: Self = <$t>::MAX;
+
+                #[inline(always)]
+                fn from_magnitude(negative: bool, magnitude: u64) -> Self {
+                    // two's complement, so this also works for `MIN`
+                    (if negative { magnitude.wrapping_neg() } else { magnitude }) as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_int!(i32: true, u32: false, i64: true, u64: false);
+
+/// Traps if truncating the `f32` with the bits `v` to `I` traps in wasm
+#[inline(always)]
+pub fn check_f32<I: Int>(v: u32) {
+    if F32::from_bits(v).trunc_traps(I::BITS, I::SIGNED) {
+        trap();
+    }
+}
+
+/// Traps if truncating the `f64` with the bits `v` to `I` traps in wasm
+#[inline(always)]
+pub fn check_f64<I: Int>(v: u64) {
+    if F64::from_bits(v).trunc_traps(I::BITS, I::SIGNED) {
+        trap();
+    }
+}
+
+/// Truncates the `f32` with the bits `v` to `I` with integer instructions, trapping like wasm.
+/// Backends that do not convert with their library use this.
+pub fn f32_to_int<I: Int>(v: u32) -> I {
+    check_f32::<I>(v);
+    let v = F32::from_bits(v);
+    I::from_magnitude(!v.is_sign_positive(), v.trunc_magnitude())
+}
+
+/// Truncates the `f64` with the bits `v` to `I` with integer instructions, trapping like wasm.
+/// Backends that do not convert with their library use this.
+pub fn f64_to_int<I: Int>(v: u64) -> I {
+    check_f64::<I>(v);
+    let v = F64::from_bits(v);
+    I::from_magnitude(!v.is_sign_positive(), v.trunc_magnitude())
+}
+
+/// Truncates the `f32` with the bits `v` to `I`, saturating instead of trapping: NaN becomes zero
+/// and out of range values become [`Int::MIN`] or [`Int::MAX`]
+pub fn f32_to_int_sat<I: Int>(v: u32) -> I {
+    let v = F32::from_bits(v);
+    if v.is_nan() {
+        I::from_magnitude(false, 0)
+    } else if v.trunc_traps(I::BITS, I::SIGNED) {
+        if v.is_sign_positive() {
+            I::MAX
+        } else {
+            I::MIN
+        }
+    } else {
+        I::from_magnitude(!v.is_sign_positive(), v.trunc_magnitude())
+    }
+}
+
+/// Truncates the `f64` with the bits `v` to `I`, saturating instead of trapping: NaN becomes zero
+/// and out of range values become [`Int::MIN`] or [`Int::MAX`]
+pub fn f64_to_int_sat<I: Int>(v: u64) -> I {
+    let v = F64::from_bits(v);
+    if v.is_nan() {
+        I::from_magnitude(false, 0)
+    } else if v.trunc_traps(I::BITS, I::SIGNED) {
+        if v.is_sign_positive() {
+            I::MAX
+        } else {
+            I::MIN
+        }
+    } else {
+        I::from_magnitude(!v.is_sign_positive(), v.trunc_magnitude())
+    }
+}
+
+/// The panic message of [`trap`] on the host
+pub const TRAP_MESSAGE: &str = "invalid conversion to integer";
+
+/// Traps: `unreachable` in wasm, a panic everywhere else
+#[cold]
+#[inline(never)]
+pub fn trap() -> ! {
+    #[cfg(target_arch = "wasm32")]
+    core::arch::wasm32::unreachable();
+    #[cfg(not(target_arch = "wasm32"))]
+    panic!("{}", TRAP_MESSAGE);
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck::quickcheck;
+
+    use super::*;
+    use crate::test::{catch_trap, truncated};
+
+    fn check<I: Int + TryFrom<i128> + PartialEq>(v: f64, f32_bits: Option<u32>) -> bool {
+        catch_trap(|| f64_to_int::<I>(v.to_bits())) == truncated(v)
+            && f32_bits.is_none_or(|bits| {
+                catch_trap(|| f32_to_int::<I>(bits)) == truncated(f32::from_bits(bits) as f64)
+            })
+    }
+
+    fn check_all(v: f64, f32_bits: Option<u32>) -> bool {
+        check::<i32>(v, f32_bits)
+            && check::<u32>(v, f32_bits)
+            && check::<i64>(v, f32_bits)
+            && check::<u64>(v, f32_bits)
+    }
+
+    quickcheck! {
+        fn traps_like_wasm_f32(bits: u32) -> bool {
+            check_all(f32::from_bits(bits) as f64, Some(bits))
+        }
+
+        fn traps_like_wasm_f64(bits: u64) -> bool {
+            check_all(f64::from_bits(bits), None)
+        }
+
+        // Rust's `as` casts saturate exactly like wasm
+        fn saturates_like_as_casts(bits: u64, bits32: u32) -> bool {
+            let (v, w) = (f64::from_bits(bits), f32::from_bits(bits32));
+            f64_to_int_sat::<i32>(bits) == v as i32
+                && f64_to_int_sat::<u32>(bits) == v as u32
+                && f64_to_int_sat::<i64>(bits) == v as i64
+                && f64_to_int_sat::<u64>(bits) == v as u64
+                && f32_to_int_sat::<i32>(bits32) == w as i32
+                && f32_to_int_sat::<u32>(bits32) == w as u32
+                && f32_to_int_sat::<i64>(bits32) == w as i64
+                && f32_to_int_sat::<u64>(bits32) == w as u64
+        }
+    }
+
+    #[test]
+    fn traps_exactly_at_the_boundaries() {
+        let boundaries = [
+            -18446744073709551616.0,
+            -9223372036854777856.0,
+            -9223372036854775808.0,
+            -4294967296.0,
+            -2147483649.0,
+            -2147483648.0,
+            -1.0,
+            -0.0,
+            2147483648.0,
+            4294967296.0,
+            9223372036854775808.0,
+            18446744073709551616.0,
+            f64::INFINITY,
+            f64::NAN,
+        ];
+        // the boundaries and their neighbours, in both formats
+        for v in boundaries {
+            for v in [v, -v] {
+                let bits = v.to_bits();
+                for bits in [bits.wrapping_sub(1), bits, bits.wrapping_add(1)] {
+                    assert!(check_all(f64::from_bits(bits), None), "{:#x}", bits);
+                }
+                let bits = (v as f32).to_bits();
+                for bits in [bits.wrapping_sub(1), bits, bits.wrapping_add(1)] {
+                    assert!(check_all(f32::from_bits(bits) as f64, Some(bits)), "{:#x}", bits);
+                }
+            }
+        }
+        assert_eq!(f32_to_int::<i32>((-2147483648f32).to_bits()), i32::MIN);
+        assert_eq!(f64_to_int::<i32>((-2147483648.9f64).to_bits()), i32::MIN);
+        assert_eq!(f64_to_int::<i64>((-9223372036854775808f64).to_bits()), i64::MIN);
+        assert_eq!(f64_to_int::<u32>((-0.9f64).to_bits()), 0);
+        assert_eq!(catch_trap(|| f32_to_int::<u32>((-1f32).to_bits())), None);
+        assert_eq!(f32_to_int_sat::<u32>((-1f32).to_bits()), 0);
+        assert_eq!(f64_to_int_sat::<i64>(f64::NAN.to_bits()), 0);
+        assert_eq!(f64_to_int_sat::<i32>((-2147483649f64).to_bits()), i32::MIN);
+        assert_eq!(f32_to_int_sat::<u64>(f32::INFINITY.to_bits()), u64::MAX);
+    }
+}
@@ -1,16 +1,18 @@
+use core::ops::Neg;
+
 use quickcheck::quickcheck;
-use wasm_soft_float_utils::*;
+use wasm_soft_float_utils::float::F32;
 
 quickcheck! {
     fn abs_works(f: f32) -> bool {
-        __wasm_soft_float_f_32_abs(f.to_bits()) == f.abs().to_bits()
+        F32::from_bits(f.to_bits()).abs().to_bits() == f.abs().to_bits()
     }
 
     fn neg_works(f: f32) -> bool {
-        __wasm_soft_float_f_32_neg(f.to_bits()) == (-f).to_bits()
+        F32::from_bits(f.to_bits()).neg().to_bits() == (-f).to_bits()
     }
 
     fn copysign_works(f: f32, g: f32) -> bool {
-        __wasm_soft_float_f_32_copysign(f.to_bits(), g.to_bits()) == f.copysign(g).to_bits()
+        F32::from_bits(f.to_bits()).copy_sign(F32::from_bits(g.to_bits())).to_bits() == f.copysign(g).to_bits()
     }
 }
@@ -0,0 +1,242 @@
+//! Checks the functions generated by `export_backend!` using a backend based on the native float operations.
+
+use quickcheck::quickcheck;
+use wasm_soft_float_utils::{
+    backend::SoftFloatBackend, export_backend, float::*, test::catch_trap, trunc,
+};
+
+struct Native;
+
+fn f32(v: F32) -> f32 {
+    f32::from_bits(v.to_bits())
+}
+fn f64(v: F64) -> f64 {
+    f64::from_bits(v.to_bits())
+}
+fn soft32(v: f32) -> F32 {
+    F32::from_bits(v.to_bits())
+}
+fn soft64(v: f64) -> F64 {
+    F64::from_bits(v.to_bits())
+}
+
+impl SoftFloatBackend for Native {
+    fn f_32_lt(a: F32, b: F32) -> bool {
+        f32(a) < f32(b)
+    }
+    fn f_32_gt(a: F32, b: F32) -> bool {
+        f32(a) > f32(b)
+    }
+    fn f_32_le(a: F32, b: F32) -> bool {
+        f32(a) <= f32(b)
+    }
+    fn f_32_ge(a: F32, b: F32) -> bool {
+        f32(a) >= f32(b)
+    }
+    fn f_64_lt(a: F64, b: F64) -> bool {
+        f64(a) < f64(b)
+    }
+    fn f_64_gt(a: F64, b: F64) -> bool {
+        f64(a) > f64(b)
+    }
+    fn f_64_le(a: F64, b: F64) -> bool {
+        f64(a) <= f64(b)
+    }
+    fn f_64_ge(a: F64, b: F64) -> bool {
+        f64(a) >= f64(b)
+    }
+
+    fn f_32_ceil(v: F32) -> F32 {
+        soft32(f32(v).ceil())
+    }
+    fn f_32_floor(v: F32) -> F32 {
+        soft32(f32(v).floor())
+    }
+    fn f_32_trunc(v: F32) -> F32 {
+        soft32(f32(v).trunc())
+    }
+    fn f_32_nearest(v: F32) -> F32 {
+        soft32(f32(v).round_ties_even())
+    }
+    fn f_32_sqrt(v: F32) -> F32 {
+        soft32(f32(v).sqrt())
+    }
+    fn f_32_add(a: F32, b: F32) -> F32 {
+        soft32(f32(a) + f32(b))
+    }
+    fn f_32_sub(a: F32, b: F32) -> F32 {
+        soft32(f32(a) - f32(b))
+    }
+    fn f_32_mul(a: F32, b: F32) -> F32 {
+        soft32(f32(a) * f32(b))
+    }
+    fn f_32_div(a: F32, b: F32) -> F32 {
+        soft32(f32(a) / f32(b))
+    }
+    fn f_32_min(a: F32, b: F32) -> F32 {
+        soft32(f32(a).min(f32(b)))
+    }
+    fn f_32_max(a: F32, b: F32) -> F32 {
+        soft32(f32(a).max(f32(b)))
+    }
+
+    fn f_64_ceil(v: F64) -> F64 {
+        soft64(f64(v).ceil())
+    }
+    fn f_64_floor(v: F64) -> F64 {
+        soft64(f64(v).floor())
+    }
+    fn f_64_trunc(v: F64) -> F64 {
+        soft64(f64(v).trunc())
+    }
+    fn f_64_nearest(v: F64) -> F64 {
+        soft64(f64(v).round_ties_even())
+    }
+    fn f_64_sqrt(v: F64) -> F64 {
+        soft64(f64(v).sqrt())
+    }
+    fn f_64_add(a: F64, b: F64) -> F64 {
+        soft64(f64(a) + f64(b))
+    }
+    fn f_64_sub(a: F64, b: F64) -> F64 {
+        soft64(f64(a) - f64(b))
+    }
+    fn f_64_mul(a: F64, b: F64) -> F64 {
+        soft64(f64(a) * f64(b))
+    }
+    fn f_64_div(a: F64, b: F64) -> F64 {
+        soft64(f64(a) / f64(b))
+    }
+    fn f_64_min(a: F64, b: F64) -> F64 {
+        soft64(f64(a).min(f64(b)))
+    }
+    fn f_64_max(a: F64, b: F64) -> F64 {
+        soft64(f64(a).max(f64(b)))
+    }
+
+    fn i_32_trunc_sf_32(v: F32) -> i32 {
+        trunc::check_f32::<i32>(v.to_bits());
+        f32(v) as i32
+    }
+    fn i_32_trunc_uf_32(v: F32) -> u32 {
+        trunc::check_f32::<u32>(v.to_bits());
+        f32(v) as u32
+    }
+    fn i_32_trunc_sf_64(v: F64) -> i32 {
+        trunc::check_f64::<i32>(v.to_bits());
+        f64(v) as i32
+    }
+    fn i_32_trunc_uf_64(v: F64) -> u32 {
+        trunc::check_f64::<u32>(v.to_bits());
+        f64(v) as u32
+    }
+    fn i_64_trunc_sf_32(v: F32) -> i64 {
+        trunc::check_f32::<i64>(v.to_bits());
+        f32(v) as i64
+    }
+    fn i_64_trunc_uf_32(v: F32) -> u64 {
+        trunc::check_f32::<u64>(v.to_bits());
+        f32(v) as u64
+    }
+    fn i_64_trunc_sf_64(v: F64) -> i64 {
+        trunc::check_f64::<i64>(v.to_bits());
+        f64(v) as i64
+    }
+    fn i_64_trunc_uf_64(v: F64) -> u64 {
+        trunc::check_f64::<u64>(v.to_bits());
+        f64(v) as u64
+    }
+    fn f_32_convert_si_32(v: i32) -> F32 {
+        soft32(v as f32)
+    }
+    fn f_32_convert_ui_32(v: u32) -> F32 {
+        soft32(v as f32)
+    }
+    fn f_32_convert_si_64(v: i64) -> F32 {
+        soft32(v as f32)
+    }
+    fn f_32_convert_ui_64(v: u64) -> F32 {
+        soft32(v as f32)
+    }
+    fn f_32_demote_f_64(v: F64) -> F32 {
+        soft32(f64(v) as f32)
+    }
+    fn f_64_convert_si_32(v: i32) -> F64 {
+        soft64(v as f64)
+    }
+    fn f_64_convert_ui_32(v: u32) -> F64 {
+        soft64(v as f64)
+    }
+    fn f_64_convert_si_64(v: i64) -> F64 {
+        soft64(v as f64)
+    }
+    fn f_64_convert_ui_64(v: u64) -> F64 {
+        soft64(v as f64)
+    }
+    fn f_64_promote_f_32(v: F32) -> F64 {
+        soft64(f32(v) as f64)
+    }
+
+    // `as` already saturates
+    fn i_32_trunc_s_sat_f_32(v: F32) -> i32 {
+        f32(v) as i32
+    }
+    fn i_32_trunc_u_sat_f_32(v: F32) -> u32 {
+        f32(v) as u32
+    }
+    fn i_32_trunc_s_sat_f_64(v: F64) -> i32 {
+        f64(v) as i32
+    }
+    fn i_32_trunc_u_sat_f_64(v: F64) -> u32 {
+        f64(v) as u32
+    }
+    fn i_64_trunc_s_sat_f_32(v: F32) -> i64 {
+        f32(v) as i64
+    }
+    fn i_64_trunc_u_sat_f_32(v: F32) -> u64 {
+        f32(v) as u64
+    }
+    fn i_64_trunc_s_sat_f_64(v: F64) -> i64 {
+        f64(v) as i64
+    }
+    fn i_64_trunc_u_sat_f_64(v: F64) -> u64 {
+        f64(v) as u64
+    }
+}
+
+export_backend!(Native);
+
+extern "C-unwind" {
+    fn __wasm_soft_float_f_32_add(a: i32, b: i32) -> i32;
+    fn __wasm_soft_float_f_64_eq(a: i64, b: i64) -> i32;
+    fn __wasm_soft_float_f_32_neg(v: i32) -> i32;
+    fn __wasm_soft_float_i_32_trunc_uf_64(v: i64) -> i32;
+    fn __wasm_soft_float_f_64_convert_si_32(v: i32) -> i64;
+}
+
+quickcheck! {
+    fn add_is_exported(a: f32, b: f32) -> bool {
+        let res = unsafe { __wasm_soft_float_f_32_add(a.to_bits() as i32, b.to_bits() as i32) };
+        res as u32 == (a + b).to_bits()
+    }
+
+    fn default_eq_is_exported(a: f64, b: f64) -> bool {
+        let res = unsafe { __wasm_soft_float_f_64_eq(a.to_bits() as i64, b.to_bits() as i64) };
+        res == (a == b) as i32
+    }
+
+    fn default_neg_is_exported(a: f32) -> bool {
+        let res = unsafe { __wasm_soft_float_f_32_neg(a.to_bits() as i32) };
+        res as u32 == (-a).to_bits()
+    }
+
+    fn unsigned_result_is_exported(a: f64) -> bool {
+        let res = catch_trap(|| unsafe { __wasm_soft_float_i_32_trunc_uf_64(a.to_bits() as i64) });
+        res.map(|res| res as u32) == (a > -1.0 && a < 4294967296.0).then_some(a as u32)
+    }
+
+    fn signed_param_is_exported(a: i32) -> bool {
+        let res = unsafe { __wasm_soft_float_f_64_convert_si_32(a) };
+        res as u64 == (a as f64).to_bits()
+    }
+}
@@ -0,0 +1,59 @@
+//! Generates random modules, transpiles them and checks that the result is valid,
+//! does not contain floats anymore and behaves exactly like the original module.
+
+#![no_main]
+
+use std::fmt;
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+use libfuzzer_sys::fuzz_target;
+use wasm_float_transpiler::verify::{check_transpilation, VerifyOptions};
+use wasm_smith::{Module, SwarmConfig};
+
+/// A module generated by `wasm-smith`, which is printed as WAT by `cargo fuzz fmt`
+struct FloatModule(Vec<u8>);
+
+impl<'a> Arbitrary<'a> for FloatModule {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut config = SwarmConfig::arbitrary(u)?;
+        // with SIMD and references disabled, about half of all values are floats
+        config.simd_enabled = false;
+        config.reference_types_enabled = false;
+        config.exceptions_enabled = false;
+        config.memory64_enabled = false;
+        config.multi_value_enabled = true;
+        config.saturating_float_to_int_enabled = true;
+        config.sign_extension_enabled = true;
+        // imports can only be stubbed if they are functions, so there are none
+        config.max_imports = 0;
+        config.max_memories = config.max_memories.min(1);
+        config.max_tables = config.max_tables.min(1);
+        config.allow_start_export = false;
+        // NaNs stored in memory must have the same bits in both modules
+        config.canonicalize_nans = true;
+        Ok(FloatModule(Module::new(config, u)?.to_bytes()))
+    }
+}
+
+impl fmt::Debug for FloatModule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match wasmprinter::print_bytes(&self.0) {
+            Ok(wat) => f.write_str(&wat),
+            Err(_) => write!(f, "{:?}", self.0),
+        }
+    }
+}
+
+fuzz_target!(|module: FloatModule| {
+    // `walrus` does not support everything `wasm-smith` generates
+    if walrus::Module::from_buffer(&module.0).is_err() {
+        return;
+    }
+    let options = VerifyOptions {
+        samples: 10,
+        fuel: 10_000,
+        ..VerifyOptions::default()
+    };
+    let report = check_transpilation(&module.0, &options).unwrap();
+    assert!(report.divergence.is_none(), "{}", report);
+});
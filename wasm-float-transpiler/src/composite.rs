@@ -0,0 +1,182 @@
+//! Combines the soft float functions of several backends into one module.
+//!
+//! Every backend covers a different set of operations, so it can make sense to
+//! e.g. take the arithmetic from one backend and the rounding operations from another one.
+//! The backends are given as compiled wasm modules. The functions chosen from them are merged
+//! into the transpiled module using [`merge_modules`], before the float operations are replaced.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt,
+};
+
+use anyhow::{anyhow, ensure, Context, Result};
+use walrus::{ExportItem, Module};
+use wasm_soft_float_utils::ops::{Category, Operation};
+
+use crate::{
+    merge::{merge_modules, ExportConflictPolicy, MergeOptions},
+    replace::used_operations,
+    SOFTFLOAT_PREFIX,
+};
+
+/// Name under which the soft float functions already contained in the transpiled module are reported
+pub const INPUT_PROVIDER: &str = "input";
+
+/// A compiled backend that exports `__wasm_soft_float_*` functions
+pub struct Provider {
+    pub name: String,
+    pub module: Module,
+}
+
+/// Selects the operations a [`Rule`] applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Selector {
+    Operation(&'static Operation),
+    Category(Category),
+}
+
+impl Selector {
+    /// Parses an operation name like `f_32_sqrt` or a category name like `rounding`
+    pub fn parse(s: &str) -> Result<Self> {
+        if let Some(op) = Operation::by_name(s) {
+            return Ok(Selector::Operation(op));
+        }
+        Category::by_name(s)
+            .map(Selector::Category)
+            .ok_or_else(|| anyhow!("`{}` is neither an operation nor a category", s))
+    }
+
+    fn matches(self, op: &Operation) -> bool {
+        match self {
+            Selector::Operation(selected) => selected.name == op.name,
+            Selector::Category(category) => category == op.category,
+        }
+    }
+}
+
+/// Takes the selected operations from the provider with the given name
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub selector: Selector,
+    pub provider: String,
+}
+
+/// Which provider each operation was taken from
+#[derive(Debug, Default)]
+pub struct CompositionReport {
+    pub mapping: BTreeMap<&'static str, String>,
+}
+
+impl fmt::Display for CompositionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "soft float operations:")?;
+        for (op, provider) in &self.mapping {
+            writeln!(f, "  {}: {}", op, provider)?;
+        }
+        Ok(())
+    }
+}
+
+/// Chooses a provider for every soft float operation used in `module` and merges the chosen
+/// functions into it, so they can be found by [`replace_float_operations`](crate::replace::replace_float_operations).
+///
+/// Rules for a single operation take precedence over rules for a category,
+/// later rules take precedence over earlier ones.
+/// Operations without a matching rule are taken from `module` itself if it contains them,
+/// or otherwise from the first provider that implements them.
+pub fn compose(
+    mut module: Module,
+    providers: Vec<Provider>,
+    rules: &[Rule],
+) -> Result<(Module, CompositionReport)> {
+    for rule in rules {
+        ensure!(
+            rule.provider == INPUT_PROVIDER || providers.iter().any(|p| p.name == rule.provider),
+            "unknown backend `{}`",
+            rule.provider
+        );
+    }
+
+    let mut report = CompositionReport::default();
+    for op in used_operations(&module) {
+        // unsupported instructions are reported when replacing them
+        let op = match Operation::by_name(op) {
+            Some(op) => op,
+            None => continue,
+        };
+        let symbol = op.symbol();
+        let implements = |provider: &str| {
+            if provider == INPUT_PROVIDER {
+                exports_function(&module, &symbol)
+            } else {
+                providers
+                    .iter()
+                    .any(|p| p.name == provider && exports_function(&p.module, &symbol))
+            }
+        };
+
+        let rule = rules
+            .iter()
+            .rev()
+            .filter(|rule| rule.selector.matches(op))
+            .min_by_key(|rule| matches!(rule.selector, Selector::Category(_)));
+        let provider = match rule {
+            Some(rule) => {
+                ensure!(
+                    implements(&rule.provider),
+                    "backend `{}` does not implement `{}`",
+                    rule.provider,
+                    op.name
+                );
+                rule.provider.clone()
+            }
+            None if implements(INPUT_PROVIDER) => INPUT_PROVIDER.to_string(),
+            None => match providers.iter().find(|p| implements(&p.name)) {
+                Some(p) => p.name.clone(),
+                None => continue,
+            },
+        };
+        report.mapping.insert(op.name, provider);
+    }
+
+    for provider in providers {
+        let symbols: HashSet<String> = report
+            .mapping
+            .iter()
+            .filter(|(_, p)| **p == provider.name)
+            .map(|(op, _)| format!("{}{}", SOFTFLOAT_PREFIX, op))
+            .collect();
+        if symbols.is_empty() {
+            continue;
+        }
+
+        // the functions of the input module are replaced by the ones of the provider
+        let replaced = module
+            .exports
+            .iter()
+            .filter(|e| symbols.contains(&e.name))
+            .map(|e| e.id())
+            .collect::<Vec<_>>();
+        for export in replaced {
+            module.exports.delete(export);
+        }
+
+        let options = MergeOptions {
+            export_conflicts: ExportConflictPolicy::Error,
+            source_exports: Some(symbols),
+        };
+        module = merge_modules(module, provider.module, &options)
+            .with_context(|| format!("failed to merge backend `{}`", provider.name))?
+            .0;
+    }
+
+    Ok((module, report))
+}
+
+fn exports_function(module: &Module, name: &str) -> bool {
+    module
+        .exports
+        .iter()
+        .any(|e| e.name == name && matches!(e.item, ExportItem::Function(_)))
+}
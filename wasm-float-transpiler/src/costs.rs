@@ -0,0 +1,100 @@
+//! Measures how many instructions the soft float functions of a backend execute.
+//!
+//! Every `__wasm_soft_float_*` function exported by the backend module is called with operands drawn
+//! from a fixed mix of zeros, subnormals, normals, infinities and NaNs (or integers for conversions
+//! from integers) in the [`wasmi`] interpreter. The fuel consumed by each call, which is roughly one per
+//! executed instruction, is summarized as the minimum, mean and maximum per operation.
+//! The resulting table can be used directly as a cost table for [metering](crate::metering).
+
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use wasm_soft_float_utils::ops::{self, OPERATIONS};
+
+use crate::sample::{self, SplitMix64};
+use wasmi::{core::ValueType, Config, Engine, Linker, Module, Store, Value};
+
+/// The default number of calls per operation
+pub const DEFAULT_SAMPLES: usize = 10_000;
+
+/// The fuel consumed by the calls of one operation
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Measurement {
+    pub min: u64,
+    pub mean: f64,
+    pub max: u64,
+}
+
+/// Calls every soft float function exported by the backend module `wasm` `samples` times and
+/// returns the fuel consumed by them, keyed by operation name.
+/// Operations the backend does not implement are left out, and so are calls that trapped,
+/// like conversions of values that are out of range.
+pub fn measure_costs(wasm: &[u8], samples: usize) -> Result<BTreeMap<&'static str, Measurement>> {
+    let mut config = Config::default();
+    config.consume_fuel(true);
+    let engine = Engine::new(&config);
+    let module = Module::new(&engine, wasm).context("could not load backend module")?;
+    let mut store = Store::new(&engine, ());
+    let instance = Linker::<()>::new(&engine)
+        .instantiate(&mut store, &module)
+        .context("could not instantiate backend module, it must not have any imports")?
+        .start(&mut store)?;
+    store.add_fuel(u64::MAX / 2).map_err(|e| anyhow!("{}", e))?;
+
+    let mut costs = BTreeMap::new();
+    for op in OPERATIONS {
+        let func = match instance.get_func(&store, &op.symbol()) {
+            Some(func) => func,
+            None => continue,
+        };
+        let ty = func.ty(&store);
+        let expected: Vec<_> = op.params.iter().map(|&p| value_type(p)).collect();
+        if ty.params() != expected || ty.results() != [value_type(op.result)] {
+            bail!("{} has the wrong signature", op.symbol());
+        }
+
+        let operand_ty = op.operand();
+        let mut rng = SplitMix64(0);
+        let mut results = [Value::I32(0)];
+        let (mut min, mut max, mut sum, mut calls) = (u64::MAX, 0, 0u128, 0u64);
+        for _ in 0..samples {
+            let params: Vec<_> = op
+                .params
+                .iter()
+                .map(|_| operand(&mut rng, operand_ty))
+                .collect();
+            let before = store.fuel_consumed().expect("fuel metering is enabled");
+            if func.call(&mut store, &params, &mut results).is_err() {
+                continue;
+            }
+            let fuel = store.fuel_consumed().expect("fuel metering is enabled") - before;
+            min = min.min(fuel);
+            max = max.max(fuel);
+            sum += fuel as u128;
+            calls += 1;
+        }
+        if calls > 0 {
+            let mean = sum as f64 / calls as f64;
+            costs.insert(op.name, Measurement { min, mean, max });
+        }
+    }
+    Ok(costs)
+}
+
+fn value_type(ty: ops::ValType) -> ValueType {
+    match ty {
+        ops::ValType::I32 => ValueType::I32,
+        ops::ValType::I64 => ValueType::I64,
+    }
+}
+
+/// Returns a random operand of the given type
+fn operand(rng: &mut SplitMix64, ty: &str) -> Value {
+    match ty {
+        "f32" => Value::I32(sample::float(rng, 8, 23) as u32 as i32),
+        "f64" => Value::I64(sample::float(rng, 11, 52) as i64),
+        "i32" => Value::I32(sample::integer(rng) as i32),
+        _ => Value::I64(sample::integer(rng) as i64),
+    }
+}
@@ -0,0 +1,398 @@
+//! Evaluates float operations whose operands are constants at transpile time.
+//!
+//! The operations are evaluated with the `__wasm_soft_float_*` functions exported by the module itself,
+//! by running them in [`wasmi`], so the results are bit-identical to the ones at runtime no matter which backend
+//! (or combination of backends) the module embeds. Calls of imports from these functions trap, so they are not folded.
+//! Modules that import the operations from the host are folded with the host imports of [`wasm_soft_float_host`]
+//! instead, see [`fold_constants_with_host_imports`].
+//! Operations resulting in NaN are not folded, since backends are free to choose the NaN payload,
+//! and neither are conversions that would trap.
+
+use std::fmt;
+
+use anyhow::{anyhow, Context, Result};
+use rayon::prelude::*;
+use walrus::{
+    ir::{dfs_pre_order_mut, Const, Instr, InstrLocId, InstrSeq, Value, VisitorMut},
+    Module,
+};
+use wasm_soft_float_host::define_soft_float;
+use wasm_soft_float_utils::{
+    float::{F32, F64},
+    ops::{Category, Operation, ValType, OPERATIONS},
+};
+use wasmi::{
+    core::Trap, Engine, Extern, ExternType, Func, Global, Instance, Linker, Memory, Store, Table,
+};
+
+use crate::replace::{float_operation, op_signature, DEFAULT_IMPORT_MODULE};
+
+/// How many float operations were replaced by constants
+#[derive(Debug, Default)]
+pub struct FoldReport {
+    pub folded: usize,
+}
+
+impl fmt::Display for FoldReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "constant folding eliminated {} soft float calls",
+            self.folded
+        )
+    }
+}
+
+/// Replaces float operations with constant operands by their result,
+/// evaluated with the soft float functions exported by `module`.
+///
+/// The result is inserted as an integer constant, like all floats after the replacement,
+/// so this has to be followed by [`replace_float_operations`](crate::replace::replace_float_operations).
+/// Fails if the module cannot be instantiated to evaluate its soft float functions.
+pub fn fold_constants(module: &mut Module) -> Result<FoldReport> {
+    // the soft float functions do not need the start function, which could call imports
+    let start = module.start.take();
+    let backend = module.emit_wasm();
+    module.start = start;
+    fold_with(module, &backend, None)
+}
+
+/// Like [`fold_constants`], but evaluates the operations with the host imports of [`wasm_soft_float_host`],
+/// for modules that are transpiled with [`Mode::HostImport`](crate::replace::Mode::HostImport)
+/// and run with these host imports.
+pub fn fold_constants_with_host_imports(module: &mut Module) -> Result<FoldReport> {
+    fold_with(module, &host_backend_module(), Some(DEFAULT_IMPORT_MODULE))
+}
+
+/// Folds the operations in `module` with the soft float functions exported by `backend`
+fn fold_with(
+    module: &mut Module,
+    backend: &[u8],
+    host_imports: Option<&str>,
+) -> Result<FoldReport> {
+    let engine = Engine::default();
+    let backend = wasmi::Module::new(&engine, backend)
+        .context("cannot load the module to evaluate its soft float functions")?;
+    // instantiate once up front, so the threads below cannot fail
+    Backend::new(&engine, &backend, host_imports)
+        .context("cannot instantiate the module to evaluate its soft float functions")?;
+    let folded = module
+        .funcs
+        .par_iter_local_mut()
+        .map_init(
+            || {
+                Backend::new(&engine, &backend, host_imports)
+                    .expect("the module was instantiated before")
+            },
+            |backend, (_, func)| {
+                let mut folder = ConstantFolder { backend, folded: 0 };
+                dfs_pre_order_mut(&mut folder, func, func.entry_block());
+                folder.folded
+            },
+        )
+        .sum();
+    Ok(FoldReport { folded })
+}
+
+/// A module re-exporting every soft float operation of the host imports
+fn host_backend_module() -> Vec<u8> {
+    let mut module = Module::default();
+    for op in OPERATIONS {
+        let (params, results) = op_signature(op.name).expect("every operation has a signature");
+        let ty = module.types.add(&params, &results);
+        let (func, _) = module.add_import_func(DEFAULT_IMPORT_MODULE, op.name, ty);
+        module.exports.add(&op.symbol(), func);
+    }
+    module.emit_wasm()
+}
+
+/// An instance of the backend module. Imports from `host_imports` are the soft float host functions,
+/// all other imports are replaced by ones that do nothing or trap when called.
+/// Instances cannot be shared between threads, so every thread has its own.
+struct Backend {
+    store: Store<()>,
+    instance: Instance,
+}
+
+impl Backend {
+    fn new(engine: &Engine, module: &wasmi::Module, host_imports: Option<&str>) -> Result<Self> {
+        let mut store = Store::new(engine, ());
+        let mut linker = Linker::new(engine);
+        if let Some(import_module) = host_imports {
+            define_soft_float(&mut linker, import_module)?;
+        }
+        for import in module.imports() {
+            if Some(import.module()) == host_imports {
+                continue;
+            }
+            let item: Extern = match import.ty() {
+                ExternType::Func(ty) => {
+                    let message =
+                        format!("called the import `{}.{}`", import.module(), import.name());
+                    Func::new(&mut store, ty.clone(), move |_, _, _| {
+                        Err(Trap::new(message.clone()))
+                    })
+                    .into()
+                }
+                ExternType::Global(ty) => Global::new(
+                    &mut store,
+                    wasmi::Value::default(ty.content()),
+                    ty.mutability(),
+                )
+                .into(),
+                ExternType::Memory(ty) => Memory::new(&mut store, *ty)
+                    .map_err(|e| anyhow!("{}", e))?
+                    .into(),
+                ExternType::Table(ty) => {
+                    Table::new(&mut store, *ty, wasmi::Value::default(ty.element()))
+                        .map_err(|e| anyhow!("{}", e))?
+                        .into()
+                }
+            };
+            linker.define(import.module(), import.name(), item)?;
+        }
+        let instance = linker
+            .instantiate(&mut store, module)?
+            .ensure_no_start(&mut store)?;
+        Ok(Self { store, instance })
+    }
+
+    /// Calls the soft float function implementing `op`,
+    /// or returns `None` if the module does not export it or it traps
+    fn call(&mut self, op: &Operation, operands: &[u64]) -> Option<u64> {
+        let func = self.instance.get_func(&self.store, &op.symbol())?;
+        let params: Vec<_> = op
+            .params
+            .iter()
+            .zip(operands)
+            .map(|(ty, &bits)| match ty {
+                ValType::I32 => wasmi::Value::I32(bits as u32 as i32),
+                ValType::I64 => wasmi::Value::I64(bits as i64),
+            })
+            .collect();
+        let mut results = [wasmi::Value::I32(0)];
+        func.call(&mut self.store, &params, &mut results).ok()?;
+        match results {
+            [wasmi::Value::I32(v)] => Some(v as u32 as u64),
+            [wasmi::Value::I64(v)] => Some(v as u64),
+            _ => None,
+        }
+    }
+}
+
+struct ConstantFolder<'a> {
+    backend: &'a mut Backend,
+    folded: usize,
+}
+
+impl VisitorMut for ConstantFolder<'_> {
+    fn start_instr_seq_mut(&mut self, seq: &mut InstrSeq) {
+        let mut instrs: Vec<(Instr, InstrLocId)> = Vec::with_capacity(seq.instrs.len());
+        for (instr, loc) in seq.instrs.drain(..) {
+            let op = float_operation(&instr).and_then(Operation::by_name);
+            if let Some(op) = op {
+                // the operands are the constants directly before the operation
+                let arity = op.params.len();
+                let operands = instrs
+                    .len()
+                    .checked_sub(arity)
+                    .map(|start| &instrs[start..])
+                    .and_then(|operands| operands.iter().map(|(i, _)| const_bits(i)).collect());
+                if let Some(value) =
+                    operands.and_then(|operands: Vec<u64>| evaluate(self.backend, op, &operands))
+                {
+                    instrs.truncate(instrs.len() - arity);
+                    instrs.push((Instr::Const(Const { value }), loc));
+                    self.folded += 1;
+                    continue;
+                }
+            }
+            instrs.push((instr, loc));
+        }
+        seq.instrs = instrs;
+    }
+}
+
+/// Returns the bits of a constant instruction
+fn const_bits(instr: &Instr) -> Option<u64> {
+    match instr {
+        Instr::Const(Const { value }) => match *value {
+            Value::I32(v) => Some(v as u32 as u64),
+            Value::I64(v) => Some(v as u64),
+            Value::F32(v) => Some(v.to_bits() as u64),
+            Value::F64(v) => Some(v.to_bits()),
+            Value::V128(_) => None,
+        },
+        _ => None,
+    }
+}
+
+/// Evaluates `op` with the given operand bits, or returns `None` if it cannot be folded
+fn evaluate(backend: &mut Backend, op: &Operation, operands: &[u64]) -> Option<Value> {
+    // the backends trap like the native instructions, e.g. for conversions that are out of range
+    let result = backend.call(op, operands)?;
+    let is_nan = op.category != Category::Comparison
+        && match op.instruction.split('.').next() {
+            Some("f32") => F32::from_bits(result as u32).is_nan(),
+            Some("f64") => F64::from_bits(result).is_nan(),
+            _ => false,
+        };
+    if is_nan {
+        return None;
+    }
+    Some(match op.result {
+        ValType::I32 => Value::I32(result as u32 as i32),
+        ValType::I64 => Value::I64(result as i64),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use walrus::{ExportItem, FunctionKind};
+
+    use super::*;
+
+    /// A backend implementing every operation with the native instruction
+    fn native_backend() -> String {
+        let reinterpret = |ty: &str| match ty {
+            "f32" => "f32.reinterpret_i32",
+            "f64" => "f64.reinterpret_i64",
+            _ => "",
+        };
+        let mut wat = String::new();
+        for op in OPERATIONS {
+            let ty = |ty: &ValType| match ty {
+                ValType::I32 => "i32",
+                ValType::I64 => "i64",
+            };
+            let params: Vec<_> = op.params.iter().map(ty).collect();
+            let operands: String = (0..params.len())
+                .map(|i| format!("local.get {} {} ", i, reinterpret(op.operand())))
+                .collect();
+            let result = match (op.category, op.instruction.split_once('.').unwrap().0) {
+                (Category::Comparison, _) => "",
+                (_, "f32") => "i32.reinterpret_f32",
+                (_, "f64") => "i64.reinterpret_f64",
+                _ => "",
+            };
+            wat += &format!(
+                "(func (export \"{}\") (param {}) (result {}) {}{} {})\n",
+                op.symbol(),
+                params.join(" "),
+                ty(&op.result),
+                operands,
+                op.instruction,
+                result
+            );
+        }
+        wat
+    }
+
+    /// Folds the body of a function returning `result` in a module with the given backend
+    /// and returns its instructions and the folded count
+    fn fold_with(backend: &str, result: &str, body: &str) -> (Vec<Instr>, usize) {
+        let wat = format!(
+            "(module {} (func (export \"test\") (result {}) {}))",
+            backend, result, body
+        );
+        let mut module = Module::from_buffer(&wat::parse_str(wat).unwrap()).unwrap();
+        let report = fold_constants(&mut module).unwrap();
+        let func = match module
+            .exports
+            .iter()
+            .find(|e| e.name == "test")
+            .unwrap()
+            .item
+        {
+            ExportItem::Function(func) => module.funcs.get(func),
+            _ => unreachable!(),
+        };
+        let func = match &func.kind {
+            FunctionKind::Local(func) => func,
+            _ => unreachable!(),
+        };
+        let instrs = func.block(func.entry_block()).instrs.iter();
+        (
+            instrs.map(|(instr, _)| instr.clone()).collect(),
+            report.folded,
+        )
+    }
+
+    /// Folds the body of a function returning `result` with [`native_backend`]
+    fn fold(result: &str, body: &str) -> (Vec<Instr>, usize) {
+        fold_with(&native_backend(), result, body)
+    }
+
+    /// The bits of the constants in `instrs`
+    fn consts(instrs: &[Instr]) -> Vec<u64> {
+        instrs.iter().filter_map(const_bits).collect()
+    }
+
+    #[test]
+    fn folds_constants() {
+        let (instrs, folded) = fold("f32", "f32.const 2 f32.const 3 f32.mul");
+        assert_eq!(folded, 1);
+        assert_eq!(instrs.len(), 1);
+        assert_eq!(consts(&instrs), [6f32.to_bits() as u64]);
+
+        // the result of a fold can be the operand of the next one
+        let (instrs, folded) = fold("i32", "f64.const 1.5 f64.neg f64.const -1 f64.lt");
+        assert_eq!(folded, 2);
+        assert_eq!(consts(&instrs), [1]);
+    }
+
+    #[test]
+    fn keeps_nan_results() {
+        for body in [
+            "f32.const 0 f32.const 0 f32.div",
+            "f32.const inf f32.const -inf f32.add",
+            "f32.const nan f32.const 1 f32.mul",
+        ] {
+            let (instrs, folded) = fold("f32", body);
+            assert_eq!(folded, 0, "{}", body);
+            assert_eq!(instrs.len(), 3, "{}", body);
+        }
+    }
+
+    #[test]
+    fn keeps_trapping_conversions() {
+        for operand in ["3e9", "-2147483904", "nan", "inf"] {
+            let body = format!("f32.const {} i32.trunc_f32_s", operand);
+            let (instrs, folded) = fold("i32", &body);
+            assert_eq!(folded, 0, "{}", body);
+            assert_eq!(instrs.len(), 2, "{}", body);
+        }
+        let (instrs, folded) = fold("i32", "f32.const -2147483648 i32.trunc_f32_s");
+        assert_eq!(folded, 1);
+        assert_eq!(consts(&instrs), [i32::MIN as u32 as u64]);
+    }
+
+    #[test]
+    fn uses_the_embedded_backend() {
+        // a backend that adds wrong, and calls an import for multiplications
+        let backend = r#"
+            (import "env" "log" (func $log (param i32)))
+            (memory (import "env" "memory") 1)
+            (func (export "__wasm_soft_float_f_32_add") (param i32 i32) (result i32)
+                i32.const 42)
+            (func (export "__wasm_soft_float_f_32_mul") (param i32 i32) (result i32)
+                local.get 0
+                call $log
+                local.get 1)
+            (func $start
+                unreachable)
+            (start $start)
+        "#;
+        let (instrs, folded) = fold_with(backend, "f32", "f32.const 2 f32.const 3 f32.add");
+        assert_eq!(folded, 1);
+        assert_eq!(consts(&instrs), [42]);
+
+        let (instrs, folded) = fold_with(backend, "f32", "f32.const 2 f32.const 3 f32.mul");
+        assert_eq!(folded, 0);
+        assert_eq!(instrs.len(), 3);
+
+        // without a backend, nothing is folded
+        let (_, folded) = fold_with("", "f32", "f32.const 2 f32.const 3 f32.add");
+        assert_eq!(folded, 0);
+    }
+}
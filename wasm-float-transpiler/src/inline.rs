@@ -0,0 +1,186 @@
+//! Inlines small soft float functions into their call sites.
+//!
+//! After the replacement, every float instruction is a call, even if the soft float function is
+//! only a handful of instructions like `f_32_abs`. Functions with at most `threshold` instructions
+//! are copied into every caller as a block, using the function copying of [`merge`](crate::merge).
+//! The arguments are stored in fresh locals of the caller, the other locals of the callee
+//! are reset to zero, and `return`s become branches out of the block.
+//! Functions calling themselves are never inlined.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
+
+use walrus::{
+    ir::{
+        dfs_in_order, Block, Call, Const, Instr, InstrLocId, InstrSeq, InstrSeqId, InstrSeqType,
+        LocalSet, RefNull, Value, Visitor,
+    },
+    FunctionId, FunctionKind, LocalFunction, Module, ValType,
+};
+
+use crate::merge::{copy_body, copy_function, copy_locals};
+
+/// The default maximum number of instructions of an inlined function
+pub const DEFAULT_INLINE_THRESHOLD: u64 = 20;
+
+/// How many calls of which soft float operations were inlined
+#[derive(Debug, Default)]
+pub struct InlineReport {
+    pub inlined: BTreeMap<String, usize>,
+}
+
+impl fmt::Display for InlineReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "inlined soft float calls:")?;
+        for (op, calls) in &self.inlined {
+            writeln!(f, "  {}: {}", op, calls)?;
+        }
+        Ok(())
+    }
+}
+
+/// Inlines every soft float function in `softfloat_funcs` with at most `threshold` instructions
+/// into all of its callers. `softfloat_funcs` maps operation names to the functions implementing them,
+/// as returned by [`replace_float_operations`](crate::replace::replace_float_operations).
+/// Only functions of the module itself can be inlined, not imports.
+///
+/// The inlined functions are not removed, this is left to the gc pass.
+pub fn inline_soft_float_functions(
+    module: &mut Module,
+    softfloat_funcs: &HashMap<String, FunctionId>,
+    threshold: u64,
+) -> InlineReport {
+    let mut report = InlineReport::default();
+    // the types are not changed by copying
+    let types = module.types.iter().map(|t| (t.id(), t.id())).collect();
+
+    // copy the callees into a separate module first, so they can be read while modifying their callers.
+    // All ids in them except for locals and instruction sequences still refer to `module`.
+    let mut callees = Module::default();
+    let mut inlined = HashMap::new();
+    let mut ops: Vec<_> = softfloat_funcs.iter().collect();
+    ops.sort();
+    for (op, &id) in ops {
+        let func = match &module.funcs.get(id).kind {
+            FunctionKind::Local(func) if func.size() <= threshold && !calls(func, id) => func,
+            _ => continue,
+        };
+        let copy = copy_function(module, &mut callees, func, None, &types, 0);
+        inlined.insert(id, (copy, op.as_str()));
+    }
+
+    for (_, caller) in module.funcs.iter_local_mut() {
+        let mut seqs = InstrSeqs::default();
+        dfs_in_order(&mut seqs, caller, caller.entry_block());
+
+        for seq in seqs.0 {
+            let instrs = std::mem::take(caller.builder_mut().instr_seq(seq).instrs_mut());
+            let mut new_instrs = Vec::with_capacity(instrs.len());
+            for (instr, loc) in instrs {
+                let (callee, op) = match &instr {
+                    Instr::Call(Call { func }) => match inlined.get(func) {
+                        Some(&(callee, op)) => (callees.funcs.get(callee), op),
+                        None => {
+                            new_instrs.push((instr, loc));
+                            continue;
+                        }
+                    },
+                    _ => {
+                        new_instrs.push((instr, loc));
+                        continue;
+                    }
+                };
+                let callee = match &callee.kind {
+                    FunctionKind::Local(callee) => callee,
+                    _ => unreachable!("only local functions are copied"),
+                };
+
+                // move the arguments from the stack to the locals of the callee
+                let locals = copy_locals(&callees.locals, &mut module.locals, callee);
+                for arg in callee.args.iter().rev() {
+                    new_instrs.push((LocalSet { local: locals[arg] }.into(), loc));
+                }
+                // the other locals start at zero in every call
+                let mut others: Vec<_> = locals
+                    .iter()
+                    .filter(|(local, _)| !callee.args.contains(local))
+                    .map(|(_, &local)| local)
+                    .collect();
+                others.sort();
+                for local in others {
+                    let ty = module.locals.get(local).ty();
+                    new_instrs.push((zero(ty), loc));
+                    new_instrs.push((LocalSet { local }.into(), loc));
+                }
+
+                let results = callees.types.get(callee.ty()).results();
+                let ty = match results {
+                    [] => InstrSeqType::Simple(None),
+                    [ty] => InstrSeqType::Simple(Some(*ty)),
+                    _ => InstrSeqType::new(&mut module.types, &[], results),
+                };
+                let builder = caller.builder_mut();
+                let body = builder.dangling_instr_seq(ty).id();
+                copy_body(callee, builder, body, &locals, &types, 0, true);
+                new_instrs.push((Block { seq: body }.into(), loc));
+                *report.inlined.entry(op.to_string()).or_default() += 1;
+            }
+            *caller.builder_mut().instr_seq(seq).instrs_mut() = new_instrs;
+        }
+    }
+    report
+}
+
+/// Checks if `func` calls the function `id`
+fn calls(func: &LocalFunction, id: FunctionId) -> bool {
+    struct Calls(FunctionId, bool);
+    impl<'instr> Visitor<'instr> for Calls {
+        fn visit_instr(&mut self, instr: &'instr Instr, _: &'instr InstrLocId) {
+            if let Instr::Call(Call { func }) = instr {
+                self.1 |= *func == self.0;
+            }
+        }
+    }
+    let mut visitor = Calls(id, false);
+    dfs_in_order(&mut visitor, func, func.entry_block());
+    visitor.1
+}
+
+/// Collects the ids of all instruction sequences of a function
+#[derive(Default)]
+struct InstrSeqs(Vec<InstrSeqId>);
+
+impl<'instr> Visitor<'instr> for InstrSeqs {
+    fn start_instr_seq(&mut self, seq: &'instr InstrSeq) {
+        self.0.push(seq.id());
+    }
+}
+
+/// The initial value of a local
+fn zero(ty: ValType) -> Instr {
+    match ty {
+        ValType::I32 => Const {
+            value: Value::I32(0),
+        }
+        .into(),
+        ValType::I64 => Const {
+            value: Value::I64(0),
+        }
+        .into(),
+        ValType::F32 => Const {
+            value: Value::F32(0.0),
+        }
+        .into(),
+        ValType::F64 => Const {
+            value: Value::F64(0.0),
+        }
+        .into(),
+        ValType::V128 => Const {
+            value: Value::V128(0),
+        }
+        .into(),
+        ValType::Externref | ValType::Funcref => RefNull { ty }.into(),
+    }
+}
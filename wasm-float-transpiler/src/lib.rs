@@ -0,0 +1,29 @@
+//! Replaces floating point instructions in wasm modules with calls to deterministic
+//! software implementations.
+//!
+//! This is the library behind the `wasm-float-transpiler` binary.
+
+#[cfg(any(
+    all(feature = "bs", feature = "apfloat"),
+    all(feature = "bs", feature = "softfp"),
+    all(feature = "apfloat", feature = "softfp"),
+))]
+compile_error!("only one of the `bs`, `apfloat` and `softfp` features can be enabled");
+#[cfg(not(any(feature = "bs", feature = "apfloat", feature = "softfp")))]
+compile_error!("one of the `bs`, `apfloat` and `softfp` features must be enabled");
+
+pub mod composite;
+pub mod costs;
+pub mod fold;
+pub mod inline;
+pub mod libm;
+pub mod merge;
+pub mod metering;
+pub mod nan;
+pub mod peephole;
+pub mod replace;
+mod sample;
+mod traversal;
+pub mod verify;
+
+pub use wasm_soft_float_utils::SOFTFLOAT_PREFIX;
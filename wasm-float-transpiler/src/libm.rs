@@ -0,0 +1,137 @@
+//! Redirects calls to libm functions like `sin` or `powf` to the transcendental functions of
+//! the `wasm-soft-float-math` crate, e.g. `__wasm_soft_float_f_64_sin`.
+//!
+//! The libm functions are recognized by their import name or, for functions compiled into the module,
+//! by their name in the name section, so the input module must not be stripped.
+//! Calls are redirected before the float operations are replaced. The signatures of both functions are
+//! only equal after their float types have been replaced by integer types.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
+
+use anyhow::{bail, ensure, Result};
+use walrus::{
+    ir::{dfs_pre_order_mut, Call, Instr, VisitorMut},
+    ExportItem, FunctionId, FunctionKind, Module, ValType,
+};
+
+use crate::{replace::change_type, SOFTFLOAT_PREFIX};
+
+/// The recognized libm functions and the soft float functions that replace them
+pub const LIBM_FUNCTIONS: &[(&str, &str)] = &[
+    ("sin", "f_64_sin"),
+    ("sinf", "f_32_sin"),
+    ("cos", "f_64_cos"),
+    ("cosf", "f_32_cos"),
+    ("tan", "f_64_tan"),
+    ("tanf", "f_32_tan"),
+    ("exp", "f_64_exp"),
+    ("expf", "f_32_exp"),
+    ("log", "f_64_ln"),
+    ("logf", "f_32_ln"),
+    ("log2", "f_64_log_2"),
+    ("log2f", "f_32_log_2"),
+    ("log10", "f_64_log_10"),
+    ("log10f", "f_32_log_10"),
+    ("pow", "f_64_pow"),
+    ("powf", "f_32_pow"),
+];
+
+/// Which libm functions were redirected to which soft float functions
+#[derive(Debug, Default)]
+pub struct RedirectReport {
+    pub redirected: BTreeMap<String, &'static str>,
+}
+
+impl fmt::Display for RedirectReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "redirected libm functions:")?;
+        for (libm, op) in &self.redirected {
+            writeln!(f, "  {} -> {}{}", libm, SOFTFLOAT_PREFIX, op)?;
+        }
+        Ok(())
+    }
+}
+
+/// Replaces all calls to recognized libm functions with calls to the `__wasm_soft_float_*` function
+/// exported by the module. Fails if the module contains a libm function it does not export a replacement for.
+pub fn redirect_libm_calls(module: &mut Module) -> Result<RedirectReport> {
+    let mut report = RedirectReport::default();
+    let mut redirects = HashMap::new();
+    let mut missing = Vec::new();
+
+    for func in module.funcs.iter() {
+        let name = match &func.kind {
+            FunctionKind::Import(import) => Some(module.imports.get(import.import).name.as_str()),
+            _ => func.name.as_deref(),
+        };
+        let (libm, op) = match name.and_then(|name| LIBM_FUNCTIONS.iter().find(|(f, _)| *f == name))
+        {
+            Some(&entry) => entry,
+            None => continue,
+        };
+        let replacement = match exported_function(module, &format!("{}{}", SOFTFLOAT_PREFIX, op)) {
+            Some(replacement) => replacement,
+            None => {
+                missing.push(libm);
+                continue;
+            }
+        };
+        ensure!(
+            float_signature(module, func.id()) == float_signature(module, replacement),
+            "`{}` does not have the signature of `{}{}`",
+            libm,
+            SOFTFLOAT_PREFIX,
+            op
+        );
+        redirects.insert(func.id(), replacement);
+        report.redirected.insert(libm.to_string(), op);
+    }
+    if !missing.is_empty() {
+        bail!(
+            "Could not find soft float functions for the following libm functions: {}\r\nPlease include the wasm-soft-float-math crate.",
+            missing.join(", ")
+        );
+    }
+
+    let mut redirector = CallRedirector(redirects);
+    for (_, func) in module.funcs.iter_local_mut() {
+        dfs_pre_order_mut(&mut redirector, func, func.entry_block());
+    }
+    Ok(report)
+}
+
+fn exported_function(module: &Module, name: &str) -> Option<FunctionId> {
+    module.exports.iter().find_map(|e| match e.item {
+        ExportItem::Function(id) if e.name == name => Some(id),
+        _ => None,
+    })
+}
+
+/// The signature of the function after replacing the float types
+fn float_signature(module: &Module, func: FunctionId) -> (Vec<ValType>, Vec<ValType>) {
+    let ty = module.types.get(module.funcs.get(func).ty());
+    let int = |t: &ValType| {
+        let mut t = *t;
+        change_type(&mut t);
+        t
+    };
+    (
+        ty.params().iter().map(int).collect(),
+        ty.results().iter().map(int).collect(),
+    )
+}
+
+struct CallRedirector(HashMap<FunctionId, FunctionId>);
+
+impl VisitorMut for CallRedirector {
+    fn visit_instr_mut(&mut self, instr: &mut Instr, _instr_loc: &mut walrus::InstrLocId) {
+        if let Instr::Call(Call { func }) = instr {
+            if let Some(replacement) = self.0.get(func) {
+                *func = *replacement;
+            }
+        }
+    }
+}
@@ -1,34 +1,370 @@
+use std::collections::HashSet;
+
 use anyhow::*;
+use wasm_float_transpiler::{
+    composite::{compose, Provider, Rule, Selector},
+    costs::{measure_costs, DEFAULT_SAMPLES},
+    fold::fold_constants,
+    inline::{inline_soft_float_functions, DEFAULT_INLINE_THRESHOLD},
+    libm::redirect_libm_calls,
+    merge::{merge_modules, ExportConflictPolicy, MergeOptions},
+    metering::{inject_metering, CostTable, Meter},
+    nan::canonicalize_nans,
+    peephole,
+    replace::{self, Mode, DEFAULT_IMPORT_MODULE},
+    verify::{verify_equivalence, VerifyOptions},
+    SOFTFLOAT_PREFIX,
+};
 
-mod replace;
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
 
-const SOFTFLOAT_PREFIX: &str = "__wasm_soft_float_";
+    match args.first().map(String::as_str) {
+        Some("merge") => merge(&args[1..]),
+        Some("costs") => costs(&args[1..]),
+        Some("verify-equivalence") => verify(&args[1..]),
+        _ => transpile(&args),
+    }
+}
 
-fn main() -> Result<()> {
-    let mut args = std::env::args();
-    let input = args
-        .nth(1)
+/// `wasm-float-transpiler <input.wasm> <output.wasm> [--host-imports[=<module>]] [--redirect-libm] [--no-fold] [--no-peephole]
+///     [--canonical-nans] [--inline-threshold <instructions>] [--cost-table <costs.json|costs.toml> --meter import=<module>.<name>|global=<name>]
+///     [--backend <name>=<backend.wasm>]... [--use <operation|category>=<name>]...`
+///
+/// Float operations with constant operands are evaluated with the soft float functions of the module,
+/// unless `--no-fold` or `--host-imports` is given.
+/// With `--canonical-nans`, every NaN returned by a soft float call is replaced with the canonical NaN,
+/// so the result does not depend on the backend.
+/// Afterwards, redundant soft float calls are removed, unless `--no-peephole` is given,
+/// and soft float functions with at most `--inline-threshold` instructions are inlined (0 disables inlining).
+/// With `--cost-table` and `--meter`, the cost of every soft float call is charged before the call.
+fn transpile(args: &[String]) -> Result<()> {
+    let mut inputs = Vec::new();
+    let mut mode = Mode::Embedded;
+    let mut providers = Vec::new();
+    let mut rules = Vec::new();
+    let mut redirect_libm = false;
+    let mut fold = true;
+    let mut optimize = true;
+    let mut canonical_nans = false;
+    let mut inline_threshold = DEFAULT_INLINE_THRESHOLD;
+    let mut cost_table = None;
+    let mut meter = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            let value = args
+                .next()
+                .ok_or_else(|| anyhow!("missing value for `{}`", arg))?;
+            value
+                .split_once('=')
+                .ok_or_else(|| anyhow!("expected `<key>=<value>` for `{}`", arg))
+        };
+        match arg.as_str() {
+            "--host-imports" => mode = Mode::HostImport(DEFAULT_IMPORT_MODULE.to_string()),
+            "--redirect-libm" => redirect_libm = true,
+            "--no-fold" => fold = false,
+            "--no-peephole" => optimize = false,
+            "--canonical-nans" => canonical_nans = true,
+            "--inline-threshold" => {
+                inline_threshold = args
+                    .next()
+                    .ok_or_else(|| anyhow!("missing value for `{}`", arg))?
+                    .parse()
+                    .with_context(|| format!("invalid value for `{}`", arg))?;
+            }
+            "--cost-table" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| anyhow!("missing value for `{}`", arg))?;
+                cost_table = Some(CostTable::from_file(path)?);
+            }
+            "--meter" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| anyhow!("missing value for `{}`", arg))?;
+                meter = Some(Meter::parse(value)?);
+            }
+            "--backend" => {
+                let (name, path) = value()?;
+                providers.push(Provider {
+                    name: name.to_string(),
+                    module: walrus::Module::from_file(path)?,
+                });
+            }
+            "--use" => {
+                let (selector, provider) = value()?;
+                rules.push(Rule {
+                    selector: Selector::parse(selector)?,
+                    provider: provider.to_string(),
+                });
+            }
+            _ => match arg.strip_prefix("--host-imports=") {
+                Some(import_module) => mode = Mode::HostImport(import_module.to_string()),
+                None => inputs.push(arg),
+            },
+        }
+    }
+
+    let mut inputs = inputs.into_iter();
+    let input = inputs
+        .next()
         .ok_or_else(|| anyhow!("must provide the input wasm file as the first argument"))?;
-    let output = args
+    let output = inputs
         .next()
         .ok_or_else(|| anyhow!("must provide the output wasm file as the second argument"))?;
 
-    let mut module = walrus::Module::from_file(&input)?;
+    let mut module = walrus::Module::from_file(input)?;
+
+    let composition = if providers.is_empty() && rules.is_empty() {
+        None
+    } else {
+        ensure!(
+            mode == Mode::Embedded,
+            "`--backend` and `--use` cannot be combined with `--host-imports`"
+        );
+        let (composed, report) = compose(module, providers, &rules)?;
+        module = composed;
+        Some(report)
+    };
+
+    let redirects = if redirect_libm {
+        ensure!(
+            mode == Mode::Embedded,
+            "`--redirect-libm` cannot be combined with `--host-imports`"
+        );
+        Some(redirect_libm_calls(&mut module)?)
+    } else {
+        None
+    };
 
     // TODO: also check if it even has float operations first
-    if !module
-        .exports
-        .iter()
-        .any(|e| e.name.starts_with(SOFTFLOAT_PREFIX))
+    if mode == Mode::Embedded
+        && !module
+            .exports
+            .iter()
+            .any(|e| e.name.starts_with(SOFTFLOAT_PREFIX))
     {
         bail!("Could not find soft float operations in input module!\r\nPlease include the wasm-soft-floats crate as a dependency and include `pub use wasm_soft_floats::*;` somewhere in your crate.");
     }
 
-    replace::replace_float_operations(&mut module)?;
+    // the host imports are only known at runtime
+    let folded = if fold && mode == Mode::Embedded {
+        Some(
+            fold_constants(&mut module)
+                .context("constant folding failed, use `--no-fold` to skip it")?,
+        )
+    } else {
+        None
+    };
+
+    let softfloat_funcs = replace::replace_float_operations(&mut module, &mode)?;
+
+    // before the peephole pass, which would drop the NaN of `promote; demote` unchanged,
+    // and before inlining, which removes the calls
+    let canonicalized = if canonical_nans {
+        Some(canonicalize_nans(&mut module, &softfloat_funcs))
+    } else {
+        None
+    };
+
+    let optimized = if optimize {
+        Some(peephole::optimize(&mut module, &mode, &softfloat_funcs)?)
+    } else {
+        None
+    };
+
+    // charge before inlining, so the inlined calls are charged as well
+    let metered = match (&meter, &cost_table) {
+        (Some(meter), Some(costs)) => Some(inject_metering(
+            &mut module,
+            meter,
+            costs,
+            &softfloat_funcs,
+        )?),
+        (None, None) => None,
+        _ => bail!("`--cost-table` and `--meter` must be used together"),
+    };
+
+    let inlined = if inline_threshold > 0 {
+        Some(inline_soft_float_functions(
+            &mut module,
+            &softfloat_funcs,
+            inline_threshold,
+        ))
+    } else {
+        None
+    };
 
     walrus::passes::gc::run(&mut module);
 
     let wasm = module.emit_wasm();
     std::fs::write(output, wasm)?;
+
+    if let Some(report) = composition {
+        print!("{}", report);
+    }
+    if let Some(report) = redirects {
+        print!("{}", report);
+    }
+    if let Some(report) = folded.filter(|report| report.folded > 0) {
+        print!("{}", report);
+    }
+    if let Some(report) = canonicalized.filter(|report| !report.canonicalized.is_empty()) {
+        print!("{}", report);
+    }
+    if let Some(report) = optimized.filter(|report| report.removed > 0) {
+        print!("{}", report);
+    }
+    if let Some(report) = inlined.filter(|report| !report.inlined.is_empty()) {
+        print!("{}", report);
+    }
+    if let Some(report) = metered {
+        print!("{}", report);
+    }
+    Ok(())
+}
+
+/// `wasm-float-transpiler merge <target.wasm> <source.wasm> -o <output.wasm>
+///     [--on-export-conflict keep-target|error|prefix=<prefix>] [--keep-export <name>]...`
+///
+/// Copies everything from the source module into the target module.
+fn merge(args: &[String]) -> Result<()> {
+    let mut inputs = Vec::new();
+    let mut output = None;
+    let mut options = MergeOptions::default();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| anyhow!("missing value for `{}`", arg))
+        };
+        match arg.as_str() {
+            "-o" | "--output" => output = Some(value()?),
+            "--on-export-conflict" => {
+                let value = value()?;
+                options.export_conflicts = match value.as_str() {
+                    "keep-target" => ExportConflictPolicy::KeepTarget,
+                    "error" => ExportConflictPolicy::Error,
+                    _ => match value.strip_prefix("prefix=") {
+                        Some(prefix) => ExportConflictPolicy::PrefixSource(prefix.to_string()),
+                        None => bail!("unknown export conflict policy `{}`", value),
+                    },
+                };
+            }
+            "--keep-export" => {
+                let value = value()?;
+                options
+                    .source_exports
+                    .get_or_insert_with(HashSet::new)
+                    .insert(value.clone());
+            }
+            _ => inputs.push(arg),
+        }
+    }
+
+    let (target, source) = match inputs.as_slice() {
+        [target, source] => (target, source),
+        _ => bail!("must provide exactly two input wasm files, the target and the source module"),
+    };
+    let output = output.ok_or_else(|| anyhow!("must provide the output wasm file with `-o`"))?;
+
+    let (mut module, report) = merge_modules(
+        walrus::Module::from_file(target)?,
+        walrus::Module::from_file(source)?,
+        &options,
+    )?;
+
+    let wasm = module.emit_wasm();
+    wasmparser::validate(&wasm).context("merged module is invalid")?;
+    std::fs::write(output, wasm)?;
+
+    print!("{}", report);
+    Ok(())
+}
+
+/// `wasm-float-transpiler costs <backend.wasm> [-o <costs.json>] [--samples <n>]`
+///
+/// Measures the instructions executed by every soft float function of a backend compiled to wasm
+/// and writes them as a JSON cost table, or prints them if no output file is given.
+fn costs(args: &[String]) -> Result<()> {
+    let mut inputs = Vec::new();
+    let mut output = None;
+    let mut samples = DEFAULT_SAMPLES;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| anyhow!("missing value for `{}`", arg))
+        };
+        match arg.as_str() {
+            "-o" | "--output" => output = Some(value()?),
+            "--samples" => samples = value()?.parse().context("`--samples` must be a number")?,
+            _ => inputs.push(arg),
+        }
+    }
+
+    let backend = match inputs.as_slice() {
+        [backend] => backend,
+        _ => bail!("must provide exactly one backend wasm file"),
+    };
+    let costs = measure_costs(&std::fs::read(backend)?, samples)?;
+    let json = serde_json::to_string_pretty(&costs)?;
+    match output {
+        Some(output) => std::fs::write(output, json)?,
+        None => println!("{}", json),
+    }
+    Ok(())
+}
+
+/// `wasm-float-transpiler verify-equivalence <original.wasm> <transpiled.wasm> [--host-imports[=<module>]]
+///     [--samples <n>] [--seed <n>] [--fuel <n>] [--strict-nans]`
+///
+/// Calls every exported function of both modules with the same generated arguments
+/// and fails at the first call that behaves differently.
+fn verify(args: &[String]) -> Result<()> {
+    let mut inputs = Vec::new();
+    let mut options = VerifyOptions::default();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| anyhow!("missing value for `{}`", arg))
+        };
+        match arg.as_str() {
+            "--host-imports" => options.host_imports = Some(DEFAULT_IMPORT_MODULE.to_string()),
+            "--samples" => {
+                options.samples = value()?.parse().context("`--samples` must be a number")?
+            }
+            "--seed" => options.seed = value()?.parse().context("`--seed` must be a number")?,
+            "--fuel" => options.fuel = value()?.parse().context("`--fuel` must be a number")?,
+            "--strict-nans" => options.strict_nans = true,
+            _ => match arg.strip_prefix("--host-imports=") {
+                Some(import_module) => options.host_imports = Some(import_module.to_string()),
+                None => inputs.push(arg),
+            },
+        }
+    }
+
+    let (original, transpiled) = match inputs.as_slice() {
+        [original, transpiled] => (original, transpiled),
+        _ => bail!(
+            "must provide exactly two input wasm files, the original and the transpiled module"
+        ),
+    };
+    let report = verify_equivalence(
+        &std::fs::read(original)?,
+        &std::fs::read(transpiled)?,
+        &options,
+    )?;
+
+    print!("{}", report);
+    if report.divergence.is_some() {
+        bail!("the transpiled module does not behave like the original one");
+    }
     Ok(())
 }
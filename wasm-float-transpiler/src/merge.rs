@@ -1,36 +1,256 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
 
-use anyhow::*;
+use anyhow::{anyhow, bail, ensure, Result};
 use rayon::prelude::*;
 use walrus::{
     ir::{
-        dfs_in_order, dfs_pre_order_mut, AtomicNotify, AtomicRmw, AtomicWait, Block, Br, BrIf,
-        BrTable, Cmpxchg, IfElse, Instr, InstrSeqId, Load, LoadSimd, LocalGet, LocalSet, LocalTee,
-        Loop, Store, Visitor, VisitorMut,
+        dfs_in_order, dfs_pre_order_mut, AtomicNotify, AtomicRmw, AtomicWait, BinaryOp, Binop,
+        Block, Br, BrIf, BrTable, Cmpxchg, Const, IfElse, Instr, InstrSeqId, InstrSeqType, Load,
+        LoadSimd, LocalGet, LocalSet, LocalTee, Loop, Store, Value, Visitor, VisitorMut,
     },
-    DataKind, FunctionBuilder, FunctionId, FunctionKind, LocalFunction, LocalId, Module,
-    ModuleLocals,
+    ActiveData, ActiveDataLocation, DataId, DataKind, ElementKind, ExportItem, FunctionBuilder,
+    FunctionId, FunctionKind, GlobalId, GlobalKind, ImportKind, InitExpr, LocalFunction, LocalId,
+    MemoryId, Module, ModuleLocals, TableId, TypeId,
 };
 
 use crate::traversal::{visit_instructions, TraversalInstr};
 
+/// Size of a wasm memory page in bytes
+const PAGE_SIZE: u32 = 65536;
+
+/// What to do if an export of the source module has the same name as an export of the target module.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum ExportConflictPolicy {
+    /// Keep the export of the target module and drop the one of the source module.
+    #[default]
+    KeepTarget,
+    /// Export the item of the source module under its name with the given prefix prepended.
+    PrefixSource(String),
+    /// Fail the merge.
+    Error,
+}
+
+/// Options for [`merge_modules`].
+#[derive(Debug, Clone, Default)]
+pub struct MergeOptions {
+    /// How to resolve export names that exist in both modules.
+    pub export_conflicts: ExportConflictPolicy,
+    /// The exports of the source module to keep in the merged module.
+    /// `None` keeps all exports that can be carried over.
+    pub source_exports: Option<HashSet<String>>,
+}
+
+impl MergeOptions {
+    fn keeps_source_export(&self, name: &str) -> bool {
+        match &self.source_exports {
+            Some(allowed) => allowed.contains(name),
+            None => true,
+        }
+    }
+}
+
+/// Describes where the items of the source module ended up in the merged module.
+#[derive(Debug, Default)]
+pub struct MergeReport {
+    /// All functions of the source module, together with their id in the merged module.
+    pub functions: Vec<RemappedFunction>,
+    /// The byte offset at which the memory of the source module starts in the merged memory.
+    pub memory_offset: u32,
+    /// The index at which the table of the source module starts in the merged table.
+    pub table_offset: u32,
+    /// Human readable descriptions of the items of the source module that were not merged.
+    pub dropped: Vec<String>,
+}
+
+/// A function of the source module and its copy in the merged module.
+#[derive(Debug)]
+pub struct RemappedFunction {
+    pub name: Option<String>,
+    pub source: FunctionId,
+    pub target: FunctionId,
+    /// Whether this function is imported, in which case it was mapped to an import of the merged module.
+    pub imported: bool,
+}
+
+impl fmt::Display for MergeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "memory offset: {} bytes", self.memory_offset)?;
+        writeln!(f, "table offset: {}", self.table_offset)?;
+        writeln!(f, "functions:")?;
+        for func in &self.functions {
+            writeln!(
+                f,
+                "  {} {} -> {}{}",
+                func.name.as_deref().unwrap_or("<unnamed>"),
+                func.source.index(),
+                func.target.index(),
+                if func.imported { " (import)" } else { "" }
+            )?;
+        }
+        writeln!(f, "dropped:")?;
+        for item in &self.dropped {
+            writeln!(f, "  {}", item)?;
+        }
+        Ok(())
+    }
+}
+
+/// Maps the ids of items in the source module to the ids of their copies in the target module.
+#[derive(Default)]
+struct IdMap {
+    functions: HashMap<FunctionId, FunctionId>,
+    globals: HashMap<GlobalId, GlobalId>,
+    memories: HashMap<MemoryId, MemoryId>,
+    tables: HashMap<TableId, TableId>,
+    types: HashMap<TypeId, TypeId>,
+    data: HashMap<DataId, DataId>,
+}
+
 /// Merges the two modules into a single module.
-/// The combined module will have the functions from both.
+/// The combined module will have the functions, globals, memory, table and data from both.
+/// All exports of `a` are kept, the exports of `b` are added according to `options`.
+///
+/// The memory and table of `b` are placed behind the ones of `a`,
+/// and all memory accesses and indirect calls in the functions of `b` are shifted accordingly.
 /// # WARNING
 /// This is very experimental and will probably break in many cases.
-/// It works for simple modules that don't use memory or tables.
-pub fn merge_modules(mut a: Module, mut b: Module) -> Result<Module> {
-    let mem_offset = merge_memories(&mut b, &mut a)?;
-    println!("mem_offset: {}", mem_offset);
+/// Passive element segments of `b` are not adjusted. If the memory of `b` is placed behind the one of `a`,
+/// `b` must not use bulk memory operations, `memory.size` or `memory.grow`, since their addresses and sizes
+/// cannot be shifted.
+pub fn merge_modules(
+    mut a: Module,
+    b: Module,
+    options: &MergeOptions,
+) -> Result<(Module, MergeReport)> {
+    let mut ids = IdMap::default();
+    let mut report = MergeReport::default();
+
+    // the types are needed by both imports and function bodies
+    for ty in b.types.iter() {
+        ids.types
+            .insert(ty.id(), a.types.add(ty.params(), ty.results()));
+    }
+
+    merge_imported_functions(&b, &mut a, &mut ids)?;
+    merge_globals(&b, &mut a, &mut ids)?;
+    report.memory_offset = merge_memories(&b, &mut a, &mut ids)?;
+    merge_data(&b, &mut a, &mut ids, report.memory_offset)?;
+    report.table_offset = merge_tables(&b, &mut a, &mut ids)?;
+    merge_functions(
+        &b,
+        &mut a,
+        &mut ids,
+        report.memory_offset,
+        report.table_offset,
+    )?;
+    merge_elements(&b, &mut a, &ids, report.table_offset)?;
+    merge_exports(&b, &mut a, &ids, options, &mut report)?;
+
+    if let Some(start) = b.start {
+        report.dropped.push(format!(
+            "start function {}",
+            b.funcs.get(start).name.as_deref().unwrap_or("<unnamed>")
+        ));
+    }
 
-    merge_functions(&b, &mut a, mem_offset)?;
-    // TODO: copy everything else, like globals, tables, elements, data etc.
+    report.functions = b
+        .funcs
+        .iter()
+        .map(|f| RemappedFunction {
+            name: f.name.clone(),
+            source: f.id(),
+            target: ids.functions[&f.id()],
+            imported: matches!(f.kind, FunctionKind::Import(_)),
+        })
+        .collect();
+
+    Ok((a, report))
+}
+
+/// Maps the imported functions of `source` to the same imports in `target`, adding them if needed.
+fn merge_imported_functions(source: &Module, target: &mut Module, ids: &mut IdMap) -> Result<()> {
+    for func in source.funcs.iter() {
+        let imported = match &func.kind {
+            FunctionKind::Import(imported) => imported,
+            _ => continue,
+        };
+        let import = source.imports.get(imported.import);
+        let ty = ids.types[&imported.ty];
+
+        let new_id = match target.imports.find(&import.module, &import.name) {
+            Some(id) => match target.imports.get(id).kind {
+                ImportKind::Function(existing) => {
+                    ensure!(
+                        target.funcs.get(existing).ty() == ty,
+                        "import {}.{} has different types in both modules",
+                        import.module,
+                        import.name
+                    );
+                    existing
+                }
+                _ => bail!(
+                    "import {}.{} has different kinds in both modules",
+                    import.module,
+                    import.name
+                ),
+            },
+            None => target.add_import_func(&import.module, &import.name, ty).0,
+        };
+        ids.functions.insert(func.id(), new_id);
+    }
+    Ok(())
+}
 
-    Ok(a)
+/// Copies the globals of `source` to `target`. Imported globals are mapped to the same import in `target`.
+fn merge_globals(source: &Module, target: &mut Module, ids: &mut IdMap) -> Result<()> {
+    for global in source.globals.iter() {
+        let new_id = match &global.kind {
+            GlobalKind::Import(import_id) => {
+                let import = source.imports.get(*import_id);
+                match target.imports.find(&import.module, &import.name) {
+                    Some(id) => match target.imports.get(id).kind {
+                        ImportKind::Global(existing) => existing,
+                        _ => bail!(
+                            "import {}.{} has different kinds in both modules",
+                            import.module,
+                            import.name
+                        ),
+                    },
+                    None => {
+                        target
+                            .add_import_global(
+                                &import.module,
+                                &import.name,
+                                global.ty,
+                                global.mutable,
+                            )
+                            .0
+                    }
+                }
+            }
+            GlobalKind::Local(init) => {
+                let init = match init {
+                    // only imported globals can be referenced and those were added before
+                    InitExpr::Global(g) => InitExpr::Global(ids.globals[g]),
+                    InitExpr::RefFunc(_) => {
+                        bail!("globals initialized with a function reference are not supported")
+                    }
+                    init => *init,
+                };
+                target.globals.add_local(global.ty, global.mutable, init)
+            }
+        };
+        ids.globals.insert(global.id(), new_id);
+    }
+    Ok(())
 }
 
-/// copies memory from `b` to `a`, returning the offset at which `b` lives after that
-fn merge_memories(source: &mut Module, target: &mut Module) -> Result<u32> {
+/// Combines the memories of `source` and `target`,
+/// returning the byte offset at which the memory of `source` lives after that
+fn merge_memories(source: &Module, target: &mut Module, ids: &mut IdMap) -> Result<u32> {
     // TODO: handle imported memories?
     let target_mems = target.memories.iter().count();
     let source_mems = source.memories.iter().count();
@@ -39,9 +259,10 @@ fn merge_memories(source: &mut Module, target: &mut Module) -> Result<u32> {
         (0, 0) => 0,
         (0, 1) => {
             let mem = source.memories.iter().next().expect("length checked above");
-            target
+            let new_id = target
                 .memories
                 .add_local(mem.shared, mem.initial, mem.maximum);
+            ids.memories.insert(mem.id(), new_id);
             0
         }
         (1, 0) => 0, // nothing to do, since we modify `target` in place
@@ -57,32 +278,73 @@ fn merge_memories(source: &mut Module, target: &mut Module) -> Result<u32> {
             let old_target_len = target_mem.initial;
             target_mem.shared = target_mem.shared || source_mem.shared;
             target_mem.initial += source_mem.initial;
-
-            // TODO: no idea if this even works like that?
-            target_mem.maximum = Some(
-                target_mem.maximum.unwrap_or(target_mem.initial)
-                    + source_mem.maximum.unwrap_or(source_mem.initial),
-            );
+            // the combined memory can only be bounded if both of them are
+            target_mem.maximum = match (target_mem.maximum, source_mem.maximum) {
+                (Some(target_max), Some(source_max)) => Some(target_max + source_max),
+                _ => None,
+            };
             // TODO: import?
 
-            // copy all data segments from `source` to `target`
-            source_mem.data_segments.iter().for_each(|data_id| {
-                let data = source.data.get(*data_id);
-                target_mem
-                    .data_segments
-                    .insert(target.data.add(clone_kind(&data.kind), data.value.clone()));
-                // remove, so we don't double copy it
-                source.data.delete(*data_id);
-            });
-            old_target_len
+            ids.memories.insert(source_mem.id(), target_mem.id());
+            old_target_len.checked_mul(PAGE_SIZE).ok_or_else(|| {
+                anyhow!(
+                    "memory of the target module is too large to place the source memory behind it"
+                )
+            })?
         }
         _ => bail!("wasm module with more than one memory is not supported"),
     })
 }
 
-/// copies the table from `source` to `target`, returning the offset at which `b` lives after that
-fn merge_tables(source: &mut Module, target: &mut Module) -> Result<u32> {
-    // TODO: handle imported memories?
+/// Copies all data segments from `source` to `target`, shifting active segments by `memory_offset` bytes
+fn merge_data(
+    source: &Module,
+    target: &mut Module,
+    ids: &mut IdMap,
+    memory_offset: u32,
+) -> Result<()> {
+    for data in source.data.iter() {
+        let kind = match &data.kind {
+            DataKind::Active(ActiveData { memory, location }) => {
+                let location = match location {
+                    ActiveDataLocation::Absolute(offset) => ActiveDataLocation::Absolute(
+                        offset.checked_add(memory_offset).ok_or_else(|| {
+                            anyhow!(
+                                "data segment offset {} is out of range after shifting it by {}",
+                                offset,
+                                memory_offset
+                            )
+                        })?,
+                    ),
+                    ActiveDataLocation::Relative(_) => {
+                        bail!("data segments with a global offset are not supported")
+                    }
+                };
+                DataKind::Active(ActiveData {
+                    memory: ids.memories[memory],
+                    location,
+                })
+            }
+            DataKind::Passive => DataKind::Passive,
+        };
+        let memory = match &kind {
+            DataKind::Active(active) => Some(active.memory),
+            DataKind::Passive => None,
+        };
+
+        let new_id = target.data.add(kind, data.value.clone());
+        if let Some(memory) = memory {
+            target.memories.get_mut(memory).data_segments.insert(new_id);
+        }
+        ids.data.insert(data.id(), new_id);
+    }
+    Ok(())
+}
+
+/// Combines the tables of `source` and `target`,
+/// returning the index at which the table of `source` lives after that
+fn merge_tables(source: &Module, target: &mut Module, ids: &mut IdMap) -> Result<u32> {
+    // TODO: handle imported tables?
     let target_tables = target.tables.iter().count();
     let source_tables = source.tables.iter().count();
 
@@ -90,9 +352,10 @@ fn merge_tables(source: &mut Module, target: &mut Module) -> Result<u32> {
         (0, 0) => 0,
         (0, 1) => {
             let tbl = source.tables.iter().next().expect("length checked above");
-            target
+            let new_id = target
                 .tables
                 .add_local(tbl.initial, tbl.maximum, tbl.element_ty);
+            ids.tables.insert(tbl.id(), new_id);
             0
         }
         (1, 0) => 0, // nothing to do, since we modify `target` in place
@@ -104,84 +367,231 @@ fn merge_tables(source: &mut Module, target: &mut Module) -> Result<u32> {
                 .next()
                 .expect("length checked above");
             let source_tbl = source.tables.iter().next().expect("length checked above");
+            ensure!(
+                target_tbl.element_ty == source_tbl.element_ty,
+                "tables have different element types"
+            );
 
             let old_target_len = target_tbl.initial;
             target_tbl.initial += source_tbl.initial;
-            // TODO: no idea if this even works like that?
-            target_tbl.maximum = target_tbl
-                .maximum
-                .map(|a_max| a_max + source_tbl.maximum.unwrap_or_default());
+            // the combined table can only be bounded if both of them are
+            target_tbl.maximum = match (target_tbl.maximum, source_tbl.maximum) {
+                (Some(target_max), Some(source_max)) => Some(target_max + source_max),
+                _ => None,
+            };
             // TODO: import?
 
-            // copy all element segments from `source` to `target`
-            source_tbl.elem_segments.iter().for_each(|elem_id| {
-                let elem = source.elements.get(*elem_id);
-                target_tbl.elem_segments.insert(target.elements.add(
-                    elem.kind,
-                    elem.ty,
-                    elem.members.clone(),
-                ));
-                // remove from `b` so we don't double copy it
-                source.elements.delete(*elem_id);
-            });
+            ids.tables.insert(source_tbl.id(), target_tbl.id());
             old_target_len
         }
-        _ => bail!("wasm module with more than one memory is not supported"),
+        _ => bail!("wasm module with more than one table is not supported"),
     })
 }
 
-fn clone_kind(kind: &DataKind) -> DataKind {
-    match kind {
-        DataKind::Active(a) => DataKind::Active(a.clone()),
-        DataKind::Passive => DataKind::Passive,
+/// Copies all element segments from `source` to `target`, shifting active segments by `table_offset`.
+/// Must be called after the functions were copied.
+fn merge_elements(
+    source: &Module,
+    target: &mut Module,
+    ids: &IdMap,
+    table_offset: u32,
+) -> Result<()> {
+    for elem in source.elements.iter() {
+        let kind = match elem.kind {
+            ElementKind::Active { table, offset } => {
+                let offset = match offset {
+                    InitExpr::Value(Value::I32(offset)) => {
+                        let shifted = i32::try_from(table_offset)
+                            .ok()
+                            .and_then(|table_offset| offset.checked_add(table_offset));
+                        InitExpr::Value(Value::I32(shifted.ok_or_else(|| {
+                            anyhow!(
+                                "element segment offset {} is out of range after shifting it by {}",
+                                offset,
+                                table_offset
+                            )
+                        })?))
+                    }
+                    _ => bail!("element segments with a non-constant offset are not supported"),
+                };
+                ElementKind::Active {
+                    table: ids.tables[&table],
+                    offset,
+                }
+            }
+            kind => kind,
+        };
+        let members = elem
+            .members
+            .iter()
+            .map(|f| f.map(|f| ids.functions[&f]))
+            .collect();
+
+        let new_id = target.elements.add(kind, elem.ty, members);
+        if let ElementKind::Active { table, .. } = kind {
+            target.tables.get_mut(table).elem_segments.insert(new_id);
+        }
+    }
+    Ok(())
+}
+
+/// Adds the exports of `source` to `target`, resolving name clashes according to `options`.
+fn merge_exports(
+    source: &Module,
+    target: &mut Module,
+    ids: &IdMap,
+    options: &MergeOptions,
+    report: &mut MergeReport,
+) -> Result<()> {
+    if let Some(allowed) = &options.source_exports {
+        for name in allowed {
+            ensure!(
+                source.exports.iter().any(|e| &e.name == name),
+                "source module has no export named `{}`",
+                name
+            );
+        }
+    }
+
+    for export in source.exports.iter() {
+        if !options.keeps_source_export(&export.name) {
+            report
+                .dropped
+                .push(format!("export `{}` (not requested)", export.name));
+            continue;
+        }
+
+        let item = match export.item {
+            ExportItem::Function(id) => ExportItem::Function(ids.functions[&id]),
+            ExportItem::Global(id) => ExportItem::Global(ids.globals[&id]),
+            ExportItem::Memory(id) => ExportItem::Memory(ids.memories[&id]),
+            ExportItem::Table(id) => ExportItem::Table(ids.tables[&id]),
+        };
+
+        let name = if target.exports.iter().any(|e| e.name == export.name) {
+            match &options.export_conflicts {
+                ExportConflictPolicy::KeepTarget => {
+                    report.dropped.push(format!(
+                        "export `{}` (name is taken by the target module)",
+                        export.name
+                    ));
+                    continue;
+                }
+                ExportConflictPolicy::PrefixSource(prefix) => {
+                    let name = format!("{}{}", prefix, export.name);
+                    ensure!(
+                        !target.exports.iter().any(|e| e.name == name),
+                        "prefixed export `{}` already exists in the target module",
+                        name
+                    );
+                    name
+                }
+                ExportConflictPolicy::Error => {
+                    bail!("export `{}` exists in both modules", export.name)
+                }
+            }
+        } else {
+            export.name.clone()
+        };
+
+        target.exports.add(&name, item);
     }
+
+    Ok(())
 }
 
-fn merge_functions(source: &Module, target: &mut Module, memory_offset: u32) -> Result<()> {
+/// Copies all local functions from `source` to `target` and fixes all references to other items in them
+fn merge_functions(
+    source: &Module,
+    target: &mut Module,
+    ids: &mut IdMap,
+    memory_offset: u32,
+    table_offset: u32,
+) -> Result<()> {
     let source_functions = source.funcs.iter().filter_map(|f| match &f.kind {
         FunctionKind::Local(local) => Some((f.id(), f.name.clone(), local)),
         _ => None,
     });
 
-    let mut copied_functions = HashMap::new();
     let mut copied_functions_ids = HashSet::new();
     // move functions from
     for (id, name, function) in source_functions {
-        let new_id = copy_function(source, target, function, name);
-        // let new_id = a_funcs.add_local(b_fun.);
-        copied_functions.insert(id, new_id);
+        let new_id = copy_function(source, target, function, name, &ids.types, table_offset);
+        ids.functions.insert(id, new_id);
         copied_functions_ids.insert(new_id);
     }
 
+    // fix references to all items now that every function has its new id
+    let ids = &*ids;
     target
         .funcs
         .par_iter_local_mut()
         .filter(|(id, _)| copied_functions_ids.contains(id))
-        .for_each(|(_, f)| {
-            dfs_pre_order_mut(
-                &mut RefFixer {
-                    func_map: &copied_functions,
-                    memory_offset,
-                },
-                f,
-                f.entry_block(),
-            )
-        });
-
+        .try_for_each(|(_, f)| {
+            let mut fixer = RefFixer {
+                ids,
+                memory_offset,
+                error: None,
+            };
+            dfs_pre_order_mut(&mut fixer, f, f.entry_block());
+            fixer.error.map_or(Ok(()), Err)
+        })?;
+
+    /// Replaces the ids of `source` with the ones of `target`.
+    /// Locals and instruction sequences are already replaced by `copy_function`.
     struct RefFixer<'a> {
-        func_map: &'a HashMap<FunctionId, FunctionId>,
+        ids: &'a IdMap,
         memory_offset: u32,
+        /// The first instruction that cannot be shifted by `memory_offset`
+        error: Option<anyhow::Error>,
     }
 
-    impl<'a> VisitorMut for RefFixer<'a> {
-        // fix function calls
-        fn visit_call_mut(&mut self, instr: &mut walrus::ir::Call) {
-            if let Some(new_id) = self.func_map.get(&instr.func) {
-                instr.func = *new_id;
+    impl<'a> RefFixer<'a> {
+        /// Keeps the first error, later ones are usually caused by it
+        fn fail(&mut self, error: anyhow::Error) {
+            self.error.get_or_insert(error);
+        }
+
+        fn unsupported(&mut self, instr: &str) {
+            if self.memory_offset > 0 {
+                self.fail(anyhow!(
+                    "{} in the source module is not supported if its memory is placed behind the target memory",
+                    instr
+                ));
             }
         }
-        fn visit_call_indirect_mut(&mut self, _instr: &mut walrus::ir::CallIndirect) {
-            // TODO: how does this work?
+    }
+
+    /// Replaces `id` with its copy in `map`.
+    /// walrus visits the ids of every instruction twice (once through the `visit_*_mut` method
+    /// of the instruction and once directly), so ids that were already replaced are left alone.
+    /// The ids of both modules come from different arenas, so they never collide.
+    fn remap<T: Copy + Eq + std::hash::Hash>(map: &HashMap<T, T>, id: &mut T) {
+        if let Some(&new_id) = map.get(id) {
+            *id = new_id;
+        }
+    }
+
+    impl<'a> VisitorMut for RefFixer<'a> {
+        fn visit_function_id_mut(&mut self, function: &mut FunctionId) {
+            remap(&self.ids.functions, function);
+        }
+        fn visit_global_id_mut(&mut self, global: &mut GlobalId) {
+            remap(&self.ids.globals, global);
+        }
+        fn visit_memory_id_mut(&mut self, memory: &mut MemoryId) {
+            remap(&self.ids.memories, memory);
+        }
+        fn visit_table_id_mut(&mut self, table: &mut TableId) {
+            remap(&self.ids.tables, table);
+        }
+        fn visit_data_id_mut(&mut self, data: &mut DataId) {
+            remap(&self.ids.data, data);
+        }
+        // the types of instruction sequences are already replaced by `copy_function`,
+        // which leaves the types of indirect calls
+        fn visit_type_id_mut(&mut self, ty: &mut TypeId) {
+            remap(&self.ids.types, ty);
         }
 
         // shift all memory accesses by the offset
@@ -194,23 +604,42 @@ fn merge_functions(source: &Module, target: &mut Module, memory_offset: u32) ->
                 | Instr::AtomicNotify(AtomicNotify { arg, .. })
                 | Instr::AtomicWait(AtomicWait { arg, .. })
                 | Instr::LoadSimd(LoadSimd { arg, .. }) => {
-                    arg.offset += self.memory_offset;
+                    match arg.offset.checked_add(self.memory_offset) {
+                        Some(offset) => arg.offset = offset,
+                        None => self.fail(anyhow!(
+                            "memory access offset {} is out of range after shifting it by {}",
+                            arg.offset,
+                            self.memory_offset
+                        )),
+                    }
                 }
+                // the addresses of these are operands and the sizes count from the start of the memory,
+                // so they would use the memory of the target module.
+                // `data.drop` only refers to a data segment, which is remapped like the other ids.
+                Instr::MemoryCopy(_) => self.unsupported("memory.copy"),
+                Instr::MemoryFill(_) => self.unsupported("memory.fill"),
+                Instr::MemoryInit(_) => self.unsupported("memory.init"),
+                Instr::MemorySize(_) => self.unsupported("memory.size"),
+                Instr::MemoryGrow(_) => self.unsupported("memory.grow"),
                 _ => {} // ignore all other instructions
             }
         }
     }
 
-    // TODO: go over all *inserted* functions and change ids of call instructions
-
     Ok(())
 }
 
-fn copy_function(
+/// Copies the function `f` from `source` to `target`.
+/// All ids in the copied instructions except for locals, instruction sequences
+/// and instruction sequence types still refer to `source` and need to be fixed afterwards.
+/// `table_offset` is added to the index of all indirect calls.
+pub(crate) fn copy_function(
     source: &Module,
     target: &mut Module,
     f: &LocalFunction,
     name: Option<String>,
+    types: &HashMap<TypeId, TypeId>,
+    table_offset: u32,
 ) -> FunctionId {
     // create new builder with same type
     let ty = source.types.get(f.ty());
@@ -222,11 +651,38 @@ fn copy_function(
     // add locals used in function to target module
     let locals = copy_locals(&source.locals, &mut target.locals, f);
 
+    let body = function_builder.func_body_id();
+    copy_body(
+        f,
+        &mut function_builder,
+        body,
+        &locals,
+        types,
+        table_offset,
+        false,
+    );
+
+    let args = f.args.iter().map(|a| locals[a]).collect();
+    function_builder.finish(args, &mut target.funcs)
+}
+
+/// Copies the instructions of `f` into the instruction sequence `body` of `function_builder`,
+/// replacing locals, instruction sequences and instruction sequence types as described by `copy_function`.
+/// If `inline` is set, `return`s become branches out of `body`, so it can be used as a block in another function.
+pub(crate) fn copy_body(
+    f: &LocalFunction,
+    function_builder: &mut FunctionBuilder,
+    body: InstrSeqId,
+    locals: &HashMap<LocalId, LocalId>,
+    types: &HashMap<TypeId, TypeId>,
+    table_offset: u32,
+    inline: bool,
+) {
     // translates old sequence ids to new ones
     let mut seq_ids = HashMap::new();
-    seq_ids.insert(f.entry_block(), function_builder.func_body_id());
+    seq_ids.insert(f.entry_block(), body);
 
-    let mut instr_seqs = vec![function_builder.func_body_id()];
+    let mut instr_seqs = vec![body];
 
     let visit = |instr: TraversalInstr| {
         /// Adds the instruction to the current instruction sequence (last entry in `instr_seqs`)
@@ -242,28 +698,32 @@ fn copy_function(
         }
 
         match instr {
-            TraversalInstr::BlockStart(seq, _)
-            | TraversalInstr::LoopStart(seq, _)
-            | TraversalInstr::IfStart(seq, _)
-            | TraversalInstr::ElseStart(seq, _) => {
+            TraversalInstr::BlockStart(seq)
+            | TraversalInstr::LoopStart(seq)
+            | TraversalInstr::IfStart(seq)
+            | TraversalInstr::ElseStart(seq) => {
+                let ty = match seq.ty {
+                    InstrSeqType::MultiValue(ty) => InstrSeqType::MultiValue(types[&ty]),
+                    ty => ty,
+                };
                 // creating a dangling sequence that will be added in the corresponding `TraversalInstr::***End` variant
-                let new_seq_id = function_builder.dangling_instr_seq(seq.ty).id();
+                let new_seq_id = function_builder.dangling_instr_seq(ty).id();
                 seq_ids.insert(seq.id(), new_seq_id);
                 instr_seqs.push(new_seq_id);
             }
-            TraversalInstr::BlockEnd(_, _) => {
+            TraversalInstr::BlockEnd => {
                 let seq = instr_seqs.pop().expect("stack never empty");
-                add_instr(&mut function_builder, &instr_seqs, Block { seq });
+                add_instr(function_builder, &instr_seqs, Block { seq });
             }
-            TraversalInstr::LoopEnd(_, _) => {
+            TraversalInstr::LoopEnd => {
                 let seq = instr_seqs.pop().expect("stack never empty");
-                add_instr(&mut function_builder, &instr_seqs, Loop { seq });
+                add_instr(function_builder, &instr_seqs, Loop { seq });
             }
-            TraversalInstr::IfEnd(_, _, _) => {
+            TraversalInstr::IfEnd => {
                 let else_seq = instr_seqs.pop().expect("stack never empty");
                 let if_seq = instr_seqs.pop().expect("stack never empty");
                 add_instr(
-                    &mut function_builder,
+                    function_builder,
                     &instr_seqs,
                     IfElse {
                         consequent: if_seq,
@@ -271,24 +731,23 @@ fn copy_function(
                     },
                 );
             }
-            // TODO: replace memory access with new memory offset
             // replace local accesses with new local ids
             TraversalInstr::Instr(Instr::LocalGet(l)) => add_instr(
-                &mut function_builder,
+                function_builder,
                 &instr_seqs,
                 LocalGet {
                     local: locals[&l.local],
                 },
             ),
             TraversalInstr::Instr(Instr::LocalSet(l)) => add_instr(
-                &mut function_builder,
+                function_builder,
                 &instr_seqs,
                 LocalSet {
                     local: locals[&l.local],
                 },
             ),
             TraversalInstr::Instr(Instr::LocalTee(l)) => add_instr(
-                &mut function_builder,
+                function_builder,
                 &instr_seqs,
                 LocalTee {
                     local: locals[&l.local],
@@ -297,7 +756,7 @@ fn copy_function(
             // replace instruction sequence ids with new ones
             TraversalInstr::Instr(Instr::Br(Br { block })) => {
                 add_instr(
-                    &mut function_builder,
+                    function_builder,
                     &instr_seqs,
                     Br {
                         block: seq_ids[block],
@@ -310,7 +769,7 @@ fn copy_function(
                 //     panic!("invalid br_if");
                 // }
                 add_instr(
-                    &mut function_builder,
+                    function_builder,
                     &instr_seqs,
                     BrIf {
                         block: seq_ids[block],
@@ -319,7 +778,7 @@ fn copy_function(
             }
             TraversalInstr::Instr(Instr::BrTable(BrTable { blocks, default })) => {
                 add_instr(
-                    &mut function_builder,
+                    function_builder,
                     &instr_seqs,
                     BrTable {
                         blocks: blocks.iter().map(|b| seq_ids[b]).collect(),
@@ -327,25 +786,44 @@ fn copy_function(
                     },
                 );
             }
-            // TODO: also change other instructions with new ids
+            TraversalInstr::Instr(Instr::Return(_)) if inline => {
+                add_instr(function_builder, &instr_seqs, Br { block: body });
+            }
+            // move the table index behind the table of the target module
+            TraversalInstr::Instr(Instr::CallIndirect(call)) => {
+                if table_offset != 0 {
+                    add_instr(
+                        function_builder,
+                        &instr_seqs,
+                        Const {
+                            value: Value::I32(table_offset as i32),
+                        },
+                    );
+                    add_instr(
+                        function_builder,
+                        &instr_seqs,
+                        Binop {
+                            op: BinaryOp::I32Add,
+                        },
+                    );
+                }
+                add_instr(function_builder, &instr_seqs, call.clone());
+            }
             TraversalInstr::Instr(i) => {
                 // all other instructions are copied as is
-                add_instr(&mut function_builder, &instr_seqs, i.clone());
+                add_instr(function_builder, &instr_seqs, i.clone());
             }
             // not insterested in these
-            TraversalInstr::FunctionStart(_) => {}
-            TraversalInstr::FunctionEnd(_) => {}
+            TraversalInstr::FunctionStart => {}
+            TraversalInstr::FunctionEnd => {}
         }
     };
 
     visit_instructions(visit, f, f.entry_block());
-
-    let args = f.args.iter().map(|a| locals[a]).collect();
-    function_builder.finish(args, &mut target.funcs)
 }
 
-// find all locals used in the function and add them to the new module
-fn copy_locals(
+// find all parameters and locals used in the function and add them to the new module
+pub(crate) fn copy_locals(
     source_locals: &ModuleLocals,
     target_locals: &mut ModuleLocals,
     f: &LocalFunction,
@@ -355,6 +833,10 @@ fn copy_locals(
         target_locals,
         map: Default::default(),
     };
+    // parameters are copied even if they are unused
+    for arg in &f.args {
+        locals.visit_local_id(arg);
+    }
     dfs_in_order(&mut locals, f, f.entry_block());
 
     return locals.map;
@@ -0,0 +1,388 @@
+//! Charges the cost of every soft float operation to a gas meter.
+//!
+//! Environments that meter execution per wasm instruction only see a single `call` for every
+//! replaced float operation, although the soft float function executes many more instructions.
+//! This pass adds `i64.const <cost>; call <meter>` before every call of a soft float function,
+//! with the cost of the operation taken from a [`CostTable`]. The meter is either an imported
+//! function or a generated function decrementing a global counter, see [`Meter`].
+//!
+//! Calls made by the soft float functions themselves, directly or through other functions,
+//! are not charged, since their cost is already part of the cost of the calling operation.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+    path::Path,
+    sync::Arc,
+};
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use rayon::prelude::*;
+use serde::Deserialize;
+use walrus::{
+    ir::{
+        dfs_in_order, dfs_pre_order_mut, BinaryOp, Call, Const, Instr, InstrLocId, InstrSeq, Value,
+        Visitor, VisitorMut,
+    },
+    ExportItem, FunctionBuilder, FunctionId, FunctionKind, GlobalId, ImportKind, InitExpr,
+    LocalFunction, Module, ValType,
+};
+use wasm_soft_float_utils::ops::Operation;
+
+use crate::costs::Measurement;
+
+/// The key of the cost of operations that are not listed in a cost table
+pub const DEFAULT_COST_KEY: &str = "default";
+
+/// The cost of every soft float operation.
+///
+/// Cost tables are flat maps from operation names (e.g. `f_64_div`) to costs, with an optional
+/// `default` entry for all other operations, written in JSON or TOML:
+/// ```toml
+/// default = 50
+/// f_64_div = 300
+/// f_64_sqrt = 400
+/// ```
+/// Instead of a number, the cost can also be a [`Measurement`] with `min`, `mean` and `max`,
+/// as returned by [`measure_costs`](crate::costs::measure_costs), in which case `max` is charged.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CostTable {
+    pub costs: BTreeMap<&'static str, u64>,
+    pub default: Option<u64>,
+}
+
+impl CostTable {
+    pub fn from_json(s: &str) -> Result<Self> {
+        Self::from_entries(serde_json::from_str(s).context("invalid JSON cost table")?)
+    }
+
+    pub fn from_toml(s: &str) -> Result<Self> {
+        Self::from_entries(toml::from_str(s).context("invalid TOML cost table")?)
+    }
+
+    /// Reads a cost table from a `.json` or `.toml` file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let s = std::fs::read_to_string(path)
+            .with_context(|| format!("could not read cost table {}", path.display()))?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => Self::from_json(&s),
+            Some("toml") => Self::from_toml(&s),
+            _ => bail!(
+                "cost table {} must be a `.json` or `.toml` file",
+                path.display()
+            ),
+        }
+        .with_context(|| format!("in {}", path.display()))
+    }
+
+    fn from_entries(entries: BTreeMap<String, Cost>) -> Result<Self> {
+        let mut table = CostTable::default();
+        for (key, cost) in entries {
+            let cost = match cost {
+                Cost::Fixed(cost) => cost,
+                Cost::Measured(measurement) => measurement.max,
+            };
+            if key == DEFAULT_COST_KEY {
+                table.default = Some(cost);
+                continue;
+            }
+            let op = Operation::by_name(&key)
+                .ok_or_else(|| anyhow!("`{}` in cost table is not an operation", key))?;
+            table.costs.insert(op.name, cost);
+        }
+        Ok(table)
+    }
+
+    /// Returns the cost of the operation `op`
+    pub fn cost(&self, op: &str) -> Option<u64> {
+        self.costs.get(op).copied().or(self.default)
+    }
+}
+
+/// An entry of a cost table
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Cost {
+    Fixed(u64),
+    Measured(Measurement),
+}
+
+/// How the cost of an operation is charged
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Meter {
+    /// Call the function imported as `module.name`, which takes the cost as `i64` parameter
+    Import { module: String, name: String },
+    /// Subtract the cost from the mutable `i64` global exported with the given name,
+    /// trapping with `unreachable` if it drops below zero.
+    /// If the module does not export a global with this name, one is added with the initial value 0,
+    /// so the host has to set it before running the module.
+    Global(String),
+}
+
+impl Meter {
+    /// Parses `import=<module>.<name>` or `global=<name>`
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.split_once('=') {
+            Some(("import", import)) => {
+                let (module, name) = import
+                    .split_once('.')
+                    .ok_or_else(|| anyhow!("expected `import=<module>.<name>`, got `{}`", s))?;
+                Ok(Meter::Import {
+                    module: module.to_string(),
+                    name: name.to_string(),
+                })
+            }
+            Some(("global", name)) => Ok(Meter::Global(name.to_string())),
+            _ => bail!(
+                "expected `import=<module>.<name>` or `global=<name>`, got `{}`",
+                s
+            ),
+        }
+    }
+}
+
+/// How many calls of which soft float operations were charged, and their cost
+#[derive(Debug, Default)]
+pub struct MeteringReport {
+    pub metered: BTreeMap<String, MeteredOperation>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MeteredOperation {
+    pub calls: usize,
+    pub cost: u64,
+}
+
+impl fmt::Display for MeteringReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "metered soft float calls:")?;
+        for (op, metered) in &self.metered {
+            writeln!(
+                f,
+                "  {}: {} calls, cost {}",
+                op, metered.calls, metered.cost
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Charges the cost of every call to the soft float functions in `softfloat_funcs` using `meter`.
+/// `softfloat_funcs` maps operation names to the functions implementing them,
+/// as returned by [`replace_float_operations`](crate::replace::replace_float_operations).
+///
+/// Fails if a called operation has no cost in `costs`.
+pub fn inject_metering(
+    module: &mut Module,
+    meter: &Meter,
+    costs: &CostTable,
+    softfloat_funcs: &HashMap<String, FunctionId>,
+) -> Result<MeteringReport> {
+    let ops: HashMap<FunctionId, &'static str> = softfloat_funcs
+        .iter()
+        .filter_map(|(op, &func)| Some((func, Operation::by_name(op)?.name)))
+        .collect();
+    let internal = reachable(module, ops.keys().copied());
+
+    // only require costs for the operations that are actually charged
+    let mut missing: Vec<_> = module
+        .funcs
+        .iter_local()
+        .filter(|(id, _)| !internal.contains(id))
+        .flat_map(|(_, func)| called(func))
+        .filter_map(|callee| ops.get(&callee))
+        .filter(|op| costs.cost(op).is_none())
+        .collect();
+    if !missing.is_empty() {
+        missing.sort();
+        missing.dedup();
+        bail!(
+            "the cost table has no cost for the following operations: {}",
+            missing.into_iter().copied().collect::<Vec<_>>().join(", ")
+        );
+    }
+
+    let meter = add_meter(module, meter)?;
+    let metering = Arc::new(Metering {
+        costs: ops
+            .iter()
+            .filter_map(|(&func, &op)| Some((func, (op, costs.cost(op)?))))
+            .collect(),
+        meter,
+    });
+    let calls = module
+        .funcs
+        .par_iter_local_mut()
+        .filter(|(id, _)| !internal.contains(id) && *id != meter)
+        .map(|(_, func)| {
+            let mut visitor = MeteringVisitor {
+                metering: metering.clone(),
+                calls: HashMap::new(),
+            };
+            dfs_pre_order_mut(&mut visitor, func, func.entry_block());
+            visitor.calls
+        })
+        .reduce(HashMap::new, |mut a, b| {
+            for (op, calls) in b {
+                *a.entry(op).or_default() += calls;
+            }
+            a
+        });
+
+    let mut report = MeteringReport::default();
+    for (op, calls) in calls {
+        let cost = costs.cost(op).expect("checked above");
+        report
+            .metered
+            .insert(op.to_string(), MeteredOperation { calls, cost });
+    }
+    Ok(report)
+}
+
+/// Returns the function that charges the cost given as `i64` parameter
+fn add_meter(module: &mut Module, meter: &Meter) -> Result<FunctionId> {
+    let counter = match meter {
+        Meter::Import {
+            module: import_module,
+            name,
+        } => {
+            return Ok(match module.imports.find(import_module, name) {
+                Some(id) => match module.imports.get(id).kind {
+                    ImportKind::Function(func) => {
+                        let ty = module.types.get(module.funcs.get(func).ty());
+                        ensure!(
+                            ty.params() == [ValType::I64] && ty.results().is_empty(),
+                            "import {}.{} must take an i64 and return nothing",
+                            import_module,
+                            name
+                        );
+                        func
+                    }
+                    _ => bail!("import {}.{} is not a function", import_module, name),
+                },
+                None => {
+                    let ty = module.types.add(&[ValType::I64], &[]);
+                    module.add_import_func(import_module, name, ty).0
+                }
+            });
+        }
+        Meter::Global(name) => counter_global(module, name)?,
+    };
+
+    // (func (param $cost i64)
+    //     (global.set $counter (i64.sub (global.get $counter) (local.get $cost)))
+    //     (if (i64.lt_s (global.get $counter) (i64.const 0)) (then unreachable)))
+    let cost = module.locals.add(ValType::I64);
+    let mut builder = FunctionBuilder::new(&mut module.types, &[ValType::I64], &[]);
+    builder.name("soft_float_meter".to_string());
+    builder
+        .func_body()
+        .global_get(counter)
+        .local_get(cost)
+        .binop(BinaryOp::I64Sub)
+        .global_set(counter)
+        .global_get(counter)
+        .i64_const(0)
+        .binop(BinaryOp::I64LtS)
+        .if_else(
+            None,
+            |then| {
+                then.unreachable();
+            },
+            |_| {},
+        );
+    Ok(builder.finish(vec![cost], &mut module.funcs))
+}
+
+/// Returns the mutable `i64` global exported as `name`, adding it if there is none
+fn counter_global(module: &mut Module, name: &str) -> Result<GlobalId> {
+    if let Some(export) = module.exports.iter().find(|e| e.name == name) {
+        let global = match export.item {
+            ExportItem::Global(global) => module.globals.get(global),
+            _ => bail!("export `{}` is not a global", name),
+        };
+        if global.ty != ValType::I64 || !global.mutable {
+            bail!("global `{}` is not a mutable i64", name);
+        }
+        return Ok(global.id());
+    }
+    let global = module
+        .globals
+        .add_local(ValType::I64, true, InitExpr::Value(Value::I64(0)));
+    module.exports.add(name, global);
+    Ok(global)
+}
+
+/// Returns all functions called by `func`
+pub(crate) fn called(func: &LocalFunction) -> Vec<FunctionId> {
+    struct Calls(Vec<FunctionId>);
+    impl<'instr> Visitor<'instr> for Calls {
+        fn visit_instr(&mut self, instr: &'instr Instr, _: &'instr InstrLocId) {
+            if let Instr::Call(Call { func }) = instr {
+                self.0.push(*func);
+            }
+        }
+    }
+    let mut visitor = Calls(Vec::new());
+    dfs_in_order(&mut visitor, func, func.entry_block());
+    visitor.0
+}
+
+/// Returns the given functions and all local functions they call, directly or indirectly
+pub(crate) fn reachable(
+    module: &Module,
+    roots: impl Iterator<Item = FunctionId>,
+) -> HashSet<FunctionId> {
+    let mut reachable = HashSet::new();
+    let mut queue: Vec<_> = roots.collect();
+    while let Some(id) = queue.pop() {
+        if !reachable.insert(id) {
+            continue;
+        }
+        if let FunctionKind::Local(func) = &module.funcs.get(id).kind {
+            queue.extend(called(func));
+        }
+    }
+    reachable
+}
+
+struct Metering {
+    /// operation name and cost of every charged soft float function
+    costs: HashMap<FunctionId, (&'static str, u64)>,
+    meter: FunctionId,
+}
+
+struct MeteringVisitor {
+    metering: Arc<Metering>,
+    calls: HashMap<&'static str, usize>,
+}
+
+impl VisitorMut for MeteringVisitor {
+    fn start_instr_seq_mut(&mut self, seq: &mut InstrSeq) {
+        let mut instrs: Vec<(Instr, InstrLocId)> = Vec::with_capacity(seq.instrs.len());
+        for (instr, loc) in seq.instrs.drain(..) {
+            if let Instr::Call(Call { func }) = &instr {
+                if let Some(&(op, cost)) = self.metering.costs.get(func) {
+                    instrs.push((
+                        Const {
+                            value: Value::I64(cost as i64),
+                        }
+                        .into(),
+                        loc,
+                    ));
+                    instrs.push((
+                        Call {
+                            func: self.metering.meter,
+                        }
+                        .into(),
+                        loc,
+                    ));
+                    *self.calls.entry(op).or_default() += 1;
+                }
+            }
+            instrs.push((instr, loc));
+        }
+        seq.instrs = instrs;
+    }
+}
@@ -0,0 +1,180 @@
+//! Canonicalizes the NaNs returned by the soft float operations.
+//!
+//! Wasm lets most float operations return any NaN, and every backend picks different payloads.
+//! Modules that look at the bits of a NaN (e.g. with `i32.reinterpret_f32`) therefore behave differently
+//! depending on the backend they were transpiled with, which breaks consensus when switching backends.
+//! This pass replaces every NaN returned by a soft float call with the canonical NaN
+//! ([`F32::NAN`](wasm_soft_float_utils::float::F32::NAN) or [`F64::NAN`](wasm_soft_float_utils::float::F64::NAN)),
+//! using a few integer instructions after the call:
+//! ```wat
+//! local.set $result
+//! i32.const 0x7fc00000 ;; the canonical NaN
+//! local.get $result
+//! local.get $result
+//! i32.const 0x7fffffff
+//! i32.and
+//! i32.const 0x7f800000
+//! i32.gt_u             ;; NaNs are larger than infinity, ignoring the sign
+//! select
+//! ```
+//! Only the operations that can return any NaN are changed, see [`Operation::returns_any_nan`].
+//! The sign and reinterpret operations keep the bits of their operand, so they are deterministic already.
+//!
+//! Like metering, calls made by the soft float functions themselves are left alone,
+//! since only the result of the outermost call is visible to the module.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    sync::Arc,
+};
+
+use rayon::prelude::*;
+use walrus::{
+    ir::{
+        dfs_pre_order_mut, BinaryOp, Binop, Call, Const, Instr, InstrLocId, InstrSeq, LocalGet,
+        LocalSet, Select, Value, VisitorMut,
+    },
+    FunctionId, LocalId, Module, ValType,
+};
+use wasm_soft_float_utils::{
+    float::{F32, F64},
+    ops::{self, Operation},
+};
+
+use crate::metering::reachable;
+
+/// How many calls of which soft float operations got their NaN results canonicalized
+#[derive(Debug, Default)]
+pub struct CanonicalizationReport {
+    pub canonicalized: BTreeMap<String, usize>,
+}
+
+impl fmt::Display for CanonicalizationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "canonicalized NaN results of soft float calls:")?;
+        for (op, calls) in &self.canonicalized {
+            writeln!(f, "  {}: {} calls", op, calls)?;
+        }
+        Ok(())
+    }
+}
+
+/// Canonicalizes the NaN results of every call to the soft float functions in `softfloat_funcs`,
+/// which maps operation names to the functions implementing them,
+/// as returned by [`replace_float_operations`](crate::replace::replace_float_operations).
+pub fn canonicalize_nans(
+    module: &mut Module,
+    softfloat_funcs: &HashMap<String, FunctionId>,
+) -> CanonicalizationReport {
+    let ops: HashMap<FunctionId, &'static Operation> = softfloat_funcs
+        .iter()
+        .filter_map(|(op, &func)| Some((func, Operation::by_name(op)?)))
+        .collect();
+    let internal = reachable(module, ops.keys().copied());
+
+    // locals are shared by all functions, every function only gets the ones it uses
+    let canonicalization = Arc::new(Canonicalization {
+        ops: ops
+            .into_iter()
+            .filter(|(_, op)| op.returns_any_nan())
+            .collect(),
+        result_32: module.locals.add(ValType::I32),
+        result_64: module.locals.add(ValType::I64),
+    });
+    let calls = module
+        .funcs
+        .par_iter_local_mut()
+        .filter(|(id, _)| !internal.contains(id))
+        .map(|(_, func)| {
+            let mut visitor = CanonicalizationVisitor {
+                canonicalization: canonicalization.clone(),
+                calls: HashMap::new(),
+            };
+            dfs_pre_order_mut(&mut visitor, func, func.entry_block());
+            visitor.calls
+        })
+        .reduce(HashMap::new, |mut a, b| {
+            for (op, calls) in b {
+                *a.entry(op).or_default() += calls;
+            }
+            a
+        });
+
+    CanonicalizationReport {
+        canonicalized: calls
+            .into_iter()
+            .map(|(op, calls)| (op.to_string(), calls))
+            .collect(),
+    }
+}
+
+struct Canonicalization {
+    /// the operation of every soft float function that can return any NaN
+    ops: HashMap<FunctionId, &'static Operation>,
+    result_32: LocalId,
+    result_64: LocalId,
+}
+
+impl Canonicalization {
+    /// The instructions replacing a NaN result of `op` on the stack with the canonical NaN
+    fn instructions(&self, op: &Operation) -> [Instr; 9] {
+        let (local, nan, abs_mask, infinity, and, gt) = match op.result {
+            ops::ValType::I32 => (
+                self.result_32,
+                Value::I32(F32::NAN.to_bits() as i32),
+                Value::I32(i32::MAX),
+                Value::I32(F32::INFINITY.to_bits() as i32),
+                BinaryOp::I32And,
+                BinaryOp::I32GtU,
+            ),
+            ops::ValType::I64 => (
+                self.result_64,
+                Value::I64(F64::NAN.to_bits() as i64),
+                Value::I64(i64::MAX),
+                Value::I64(F64::INFINITY.to_bits() as i64),
+                BinaryOp::I64And,
+                BinaryOp::I64GtU,
+            ),
+        };
+        [
+            LocalSet { local }.into(),
+            Const { value: nan }.into(),
+            LocalGet { local }.into(),
+            LocalGet { local }.into(),
+            Const { value: abs_mask }.into(),
+            Binop { op: and }.into(),
+            Const { value: infinity }.into(),
+            Binop { op: gt }.into(),
+            Select { ty: None }.into(),
+        ]
+    }
+}
+
+struct CanonicalizationVisitor {
+    canonicalization: Arc<Canonicalization>,
+    calls: HashMap<&'static str, usize>,
+}
+
+impl VisitorMut for CanonicalizationVisitor {
+    fn start_instr_seq_mut(&mut self, seq: &mut InstrSeq) {
+        let mut instrs: Vec<(Instr, InstrLocId)> = Vec::with_capacity(seq.instrs.len());
+        for (instr, loc) in seq.instrs.drain(..) {
+            let op = match &instr {
+                Instr::Call(Call { func }) => self.canonicalization.ops.get(func).copied(),
+                _ => None,
+            };
+            instrs.push((instr, loc));
+            if let Some(op) = op {
+                instrs.extend(
+                    self.canonicalization
+                        .instructions(op)
+                        .into_iter()
+                        .map(|instr| (instr, loc)),
+                );
+                *self.calls.entry(op.name).or_default() += 1;
+            }
+        }
+        seq.instrs = instrs;
+    }
+}
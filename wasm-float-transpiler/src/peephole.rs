@@ -0,0 +1,235 @@
+//! Removes redundant soft float calls from the module after the float operations were replaced.
+//!
+//! The following patterns are rewritten, for both `f32` and `f64`:
+//! - reinterpretations (e.g. `call f_32_reinterpret_i_32`) are removed, since floats and integers
+//!   are represented by the same bits after the replacement
+//! - `call f_32_neg; call f_32_neg` is removed
+//! - `call f_32_neg; call f_32_abs` and `call f_32_abs; call f_32_abs` become `call f_32_abs`
+//! - `call f_64_promote_f_32; call f_32_demote_f_64` is replaced with integer instructions that keep every value
+//!   except NaN, which gets its quiet bit set like the conversions would, but keeps its payload instead of
+//!   the one chosen by the backend:
+//!   ```wat
+//!   local.tee $value
+//!   i32.const 0x00400000
+//!   i32.or               ;; the quiet NaN
+//!   local.get $value
+//!   local.get $value
+//!   i32.const 0x7fffffff
+//!   i32.and
+//!   i32.const 0x7f800000
+//!   i32.gt_u             ;; NaNs are larger than infinity, ignoring the sign
+//!   select
+//!   ```
+//!   [`canonicalize_nans`](crate::nan::canonicalize_nans) has to run first, since the instructions it adds
+//!   after the promotion keep the pair from being replaced
+//! - `call f_32_eq; i32.eqz` becomes `call f_32_ne` and vice versa.
+//!   The other comparisons are not inverted, since they are all false for NaN.
+
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use anyhow::Result;
+use rayon::prelude::*;
+use walrus::{
+    ir::{
+        dfs_pre_order_mut, BinaryOp, Binop, Call, Const, Instr, InstrLocId, InstrSeq, LocalGet,
+        LocalTee, Select, UnaryOp, Unop, Value, VisitorMut,
+    },
+    FunctionId, LocalId, Module, ValType,
+};
+use wasm_soft_float_utils::{
+    float::F32,
+    ops::{Category, Operation},
+};
+
+use crate::replace::{import_operation, Mode};
+
+/// How many soft float calls were removed by the peephole optimizer
+#[derive(Debug, Default)]
+pub struct PeepholeReport {
+    pub removed: usize,
+}
+
+impl fmt::Display for PeepholeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "peephole optimizer removed {} soft float calls",
+            self.removed
+        )
+    }
+}
+
+/// Rewrites redundant calls to the soft float functions in `softfloat_funcs`,
+/// which maps operation names to the functions implementing them,
+/// as returned by [`replace_float_operations`](crate::replace::replace_float_operations).
+///
+/// With [`Mode::HostImport`], the inverse of every used equality comparison is imported,
+/// unused imports are removed by the gc pass afterwards.
+pub fn optimize(
+    module: &mut Module,
+    mode: &Mode,
+    softfloat_funcs: &HashMap<String, FunctionId>,
+) -> Result<PeepholeReport> {
+    let mut softfloat_funcs = softfloat_funcs.clone();
+    if let Mode::HostImport(import_module) = mode {
+        for (op, inverse) in INVERSE_COMPARISONS {
+            if softfloat_funcs.contains_key(*op) && !softfloat_funcs.contains_key(*inverse) {
+                let func = import_operation(module, import_module, inverse)?;
+                softfloat_funcs.insert(inverse.to_string(), func);
+            }
+        }
+    }
+
+    let optimizer = Arc::new(Peephole {
+        ops: softfloat_funcs
+            .iter()
+            .filter_map(|(op, &func)| Some((func, Operation::by_name(op)?.name)))
+            .collect(),
+        funcs: softfloat_funcs,
+        // locals are shared by all functions, every function only gets the ones it uses
+        value_32: module.locals.add(ValType::I32),
+    });
+    let removed = module
+        .funcs
+        .par_iter_local_mut()
+        .map(|(_, func)| {
+            let mut visitor = PeepholeVisitor {
+                optimizer: optimizer.clone(),
+                removed: 0,
+            };
+            dfs_pre_order_mut(&mut visitor, func, func.entry_block());
+            visitor.removed
+        })
+        .sum();
+    Ok(PeepholeReport { removed })
+}
+
+/// Comparisons whose result can be inverted by calling the other one, even for NaN
+const INVERSE_COMPARISONS: &[(&str, &str)] = &[
+    ("f_32_eq", "f_32_ne"),
+    ("f_32_ne", "f_32_eq"),
+    ("f_64_eq", "f_64_ne"),
+    ("f_64_ne", "f_64_eq"),
+];
+
+struct Peephole {
+    /// operation name of every soft float function
+    ops: HashMap<FunctionId, &'static str>,
+    /// soft float function of every operation name
+    funcs: HashMap<String, FunctionId>,
+    value_32: LocalId,
+}
+
+impl Peephole {
+    /// Returns the operation implemented by the function called by `instr`
+    fn op(&self, instr: &Instr) -> Option<&'static str> {
+        match instr {
+            Instr::Call(Call { func }) => self.ops.get(func).copied(),
+            _ => None,
+        }
+    }
+
+    /// Rewrites the end of `instrs` once, returning how many calls were removed,
+    /// or `None` if no pattern matched
+    fn rewrite(&self, instrs: &mut Vec<(Instr, InstrLocId)>) -> Option<usize> {
+        let (last, _) = instrs.last()?;
+        if let Some(op) = self.op(last) {
+            if Operation::by_name(op)?.category == Category::Reinterpret {
+                instrs.pop();
+                return Some(1);
+            }
+        }
+        let [(prev, _), (last, _)] = match &instrs[..] {
+            [.., prev, last] => [prev, last],
+            _ => return None,
+        };
+        let removed = match (self.op(prev)?, last) {
+            ("f_32_neg", last) if self.op(last) == Some("f_32_neg") => 2,
+            ("f_64_neg", last) if self.op(last) == Some("f_64_neg") => 2,
+            ("f_64_promote_f_32", last) if self.op(last) == Some("f_32_demote_f_64") => {
+                let (_, loc) = instrs[instrs.len() - 1];
+                instrs.truncate(instrs.len() - 2);
+                instrs.extend(self.quiet_nan_32().into_iter().map(|instr| (instr, loc)));
+                return Some(2);
+            }
+            ("f_32_neg" | "f_32_abs", last) if self.op(last) == Some("f_32_abs") => {
+                instrs.swap_remove(instrs.len() - 2);
+                return Some(1);
+            }
+            ("f_64_neg" | "f_64_abs", last) if self.op(last) == Some("f_64_abs") => {
+                instrs.swap_remove(instrs.len() - 2);
+                return Some(1);
+            }
+            (
+                op,
+                Instr::Unop(Unop {
+                    op: UnaryOp::I32Eqz,
+                }),
+            ) => {
+                let (_, inverse) = INVERSE_COMPARISONS.iter().find(|(o, _)| *o == op)?;
+                let func = *self.funcs.get(*inverse)?;
+                instrs.pop();
+                let (call, _) = instrs.last_mut()?;
+                *call = Instr::Call(Call { func });
+                return Some(0);
+            }
+            _ => return None,
+        };
+        instrs.truncate(instrs.len() - removed);
+        Some(removed)
+    }
+
+    /// The instructions setting the quiet bit of an `f32` NaN on the stack and keeping all other values
+    fn quiet_nan_32(&self) -> [Instr; 10] {
+        let local = self.value_32;
+        [
+            LocalTee { local }.into(),
+            Const {
+                value: Value::I32(0x0040_0000),
+            }
+            .into(),
+            Binop {
+                op: BinaryOp::I32Or,
+            }
+            .into(),
+            LocalGet { local }.into(),
+            LocalGet { local }.into(),
+            Const {
+                value: Value::I32(i32::MAX),
+            }
+            .into(),
+            Binop {
+                op: BinaryOp::I32And,
+            }
+            .into(),
+            Const {
+                value: Value::I32(F32::INFINITY.to_bits() as i32),
+            }
+            .into(),
+            Binop {
+                op: BinaryOp::I32GtU,
+            }
+            .into(),
+            Select { ty: None }.into(),
+        ]
+    }
+}
+
+struct PeepholeVisitor {
+    optimizer: Arc<Peephole>,
+    removed: usize,
+}
+
+impl VisitorMut for PeepholeVisitor {
+    fn start_instr_seq_mut(&mut self, seq: &mut InstrSeq) {
+        let mut instrs: Vec<(Instr, InstrLocId)> = Vec::with_capacity(seq.instrs.len());
+        for instr in seq.instrs.drain(..) {
+            instrs.push(instr);
+            // removing instructions can make the ones before them match a pattern
+            while let Some(removed) = self.optimizer.rewrite(&mut instrs) {
+                self.removed += removed;
+            }
+        }
+        seq.instrs = instrs;
+    }
+}
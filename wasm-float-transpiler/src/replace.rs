@@ -1,42 +1,90 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::Arc,
+};
 
-use anyhow::Result;
-use const_format::{concatcp, map_ascii_case, Case};
+use anyhow::{bail, Result};
+use const_format::{map_ascii_case, Case};
 use rayon::prelude::*;
 use walrus::{
     ir::{
-        dfs_pre_order_mut, Binop, Call, Const, Instr, Load, LoadKind, RefNull, Select, Store,
-        StoreKind, Unop, Value, VisitorMut,
+        dfs_in_order, dfs_pre_order_mut, BinaryOp, Binop, Call, Const, Instr, InstrSeq,
+        InstrSeqType, Load, LoadKind, RefNull, Select, Store, StoreKind, UnaryOp, Unop, Value,
+        Visitor, VisitorMut,
     },
-    ExportItem, FunctionId, Module, ValType,
+    ExportItem, FunctionId, GlobalId, GlobalKind, ImportKind, InitExpr, Module, TypeId, ValType,
+};
+use wasm_soft_float_utils::{
+    for_each_operation,
+    ops::{self, Operation},
 };
 
 use crate::SOFTFLOAT_PREFIX;
 
-pub fn replace_float_operations(module: &mut Module) -> Result<()> {
-    // map from soft float function name to function id
-    let softfloat_funcs: HashMap<_, _> = module
-        .exports
-        .iter()
-        .filter(|e| e.name.starts_with(SOFTFLOAT_PREFIX))
-        .filter_map(|e| match e.item {
-            ExportItem::Function(id) => Some((e.name.as_str(), id)),
-            _ => None,
-        })
-        .collect();
-    let softfloat_funcs = Arc::new(softfloat_funcs);
+/// The import module used by [`Mode::HostImport`] if no other one is given.
+pub use wasm_soft_float_utils::DEFAULT_IMPORT_MODULE;
+
+/// Where the replaced float operations get their implementation from.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Mode {
+    /// Call the `__wasm_soft_float_*` functions exported by the module itself,
+    /// i.e. the backend is compiled into the module.
+    #[default]
+    Embedded,
+    /// Call functions imported from the given import module, which are provided by the host.
+    /// The imports are named after the operation (e.g. `f_32_add`) and are only added for operations that are used.
+    HostImport(String),
+}
+
+/// Replaces all float operations with calls to soft float functions and all float types with integer types.
+/// This includes the types of imported functions and globals, so the host passes floats as their bits.
+/// Returns the map from operation name to the function implementing it.
+pub fn replace_float_operations(
+    module: &mut Module,
+    mode: &Mode,
+) -> Result<HashMap<String, FunctionId>> {
+    // map from soft float operation name to function id
+    let softfloat_funcs: HashMap<String, FunctionId> = match mode {
+        Mode::Embedded => module
+            .exports
+            .iter()
+            .filter_map(|e| match (e.name.strip_prefix(SOFTFLOAT_PREFIX), e.item) {
+                (Some(name), ExportItem::Function(id)) => Some((name.to_string(), id)),
+                _ => None,
+            })
+            .collect(),
+        Mode::HostImport(import_module) => add_imports(module, import_module)?,
+    };
+    check_signatures(module, &softfloat_funcs)?;
+    let replacement_funcs = Arc::new(softfloat_funcs.clone());
 
     // replace all float operations with calls to soft float functions
-    module.funcs.par_iter_local_mut().for_each(|(_, func)| {
-        let mut visitor = FloatReplacer {
-            replacement_funcs: softfloat_funcs.clone(),
-        };
-        dfs_pre_order_mut(&mut visitor, func, func.entry_block());
-    });
+    let missing = module
+        .funcs
+        .par_iter_local_mut()
+        .map(|(_, func)| {
+            let mut visitor = FloatReplacer {
+                replacement_funcs: replacement_funcs.clone(),
+                missing: BTreeSet::new(),
+            };
+            dfs_pre_order_mut(&mut visitor, func, func.entry_block());
+            visitor.missing
+        })
+        .reduce(BTreeSet::new, |mut a, b| {
+            a.extend(b);
+            a
+        });
+    if !missing.is_empty() {
+        bail!(
+            "Could not find soft float functions for the following operations: {}",
+            missing.into_iter().collect::<Vec<_>>().join(", ")
+        );
+    }
 
-    // replace function types
-    for (_, func) in module.funcs.iter_local() {
-        let ty = module.types.get_mut(func.ty());
+    // replace all types, including the ones of imported functions, `call_indirect` and blocks
+    let types: Vec<TypeId> = module.types.iter().map(|ty| ty.id()).collect();
+    for id in types {
+        let ty = module.types.get_mut(id);
         for p in ty.params_mut() {
             change_type(p);
         }
@@ -49,6 +97,15 @@ pub fn replace_float_operations(module: &mut Module) -> Result<()> {
         change_type(&mut l.ty);
     }
 
+    let globals: Vec<GlobalId> = module.globals.iter().map(|global| global.id()).collect();
+    for id in globals {
+        let global = module.globals.get_mut(id);
+        change_type(&mut global.ty);
+        if let GlobalKind::Local(InitExpr::Value(value)) = &mut global.kind {
+            change_value(value);
+        }
+    }
+
     // remove soft float exports
     for export in module
         .exports
@@ -60,10 +117,114 @@ pub fn replace_float_operations(module: &mut Module) -> Result<()> {
         module.exports.delete(export);
     }
 
+    Ok(softfloat_funcs)
+}
+
+/// Makes sure that every soft float function has the signature of the operation it replaces,
+/// since calling a function with the wrong signature would produce an invalid module.
+fn check_signatures(module: &Module, softfloat_funcs: &HashMap<String, FunctionId>) -> Result<()> {
+    let mut mismatches = softfloat_funcs
+        .iter()
+        .filter_map(|(op, &func)| {
+            // functions that do not replace any operation are never called by us
+            let (params, results) = op_signature(op)?;
+            let ty = module.types.get(module.funcs.get(func).ty());
+            if ty.params() == params && ty.results() == results {
+                return None;
+            }
+            Some(format!(
+                "{} has type {}, expected {}",
+                op,
+                format_signature(ty.params(), ty.results()),
+                format_signature(&params, &results)
+            ))
+        })
+        .collect::<Vec<_>>();
+    if !mismatches.is_empty() {
+        mismatches.sort();
+        bail!(
+            "Found soft float functions with the wrong signature for the following operations:\n  {}",
+            mismatches.join("\n  ")
+        );
+    }
     Ok(())
 }
 
-fn change_type(t: &mut ValType) {
+/// Formats a function signature like `(i32, i32) -> i32`
+fn format_signature(params: &[ValType], results: &[ValType]) -> String {
+    let join = |types: &[ValType]| {
+        types
+            .iter()
+            .map(ValType::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    match results {
+        [result] => format!("({}) -> {}", join(params), result),
+        _ => format!("({}) -> ({})", join(params), join(results)),
+    }
+}
+
+/// Adds an import from `import_module` for every soft float operation used in `module`,
+/// returning a map from operation name to the imported function.
+fn add_imports(module: &mut Module, import_module: &str) -> Result<HashMap<String, FunctionId>> {
+    let mut imports = HashMap::new();
+    for op in used_operations(module) {
+        let func = import_operation(module, import_module, op)?;
+        imports.insert(op.to_string(), func);
+    }
+    Ok(imports)
+}
+
+/// Returns the function imported from `import_module` for the soft float operation `op`,
+/// adding the import if it does not exist yet.
+pub(crate) fn import_operation(
+    module: &mut Module,
+    import_module: &str,
+    op: &str,
+) -> Result<FunctionId> {
+    Ok(match module.imports.find(import_module, op) {
+        Some(id) => match module.imports.get(id).kind {
+            ImportKind::Function(func) => func,
+            _ => bail!("import {}.{} is not a function", import_module, op),
+        },
+        None => {
+            let (params, results) = match op_signature(op) {
+                Some(signature) => signature,
+                None => bail!("operation {} is not supported", op),
+            };
+            let ty = module.types.add(&params, &results);
+            module.add_import_func(import_module, op, ty).0
+        }
+    })
+}
+
+/// Returns the names of all soft float operations needed by the functions of `module`
+pub(crate) fn used_operations(module: &Module) -> BTreeSet<&'static str> {
+    module
+        .funcs
+        .iter_local()
+        .flat_map(|(_, func)| {
+            let mut visitor = UsedOperations::default();
+            dfs_in_order(&mut visitor, func, func.entry_block());
+            visitor.0
+        })
+        .collect()
+}
+
+/// Collects the names of all soft float operations needed by a function
+#[derive(Default)]
+struct UsedOperations(BTreeSet<&'static str>);
+
+impl<'instr> Visitor<'instr> for UsedOperations {
+    fn visit_instr(&mut self, instr: &'instr Instr, _: &'instr walrus::InstrLocId) {
+        if let Some(op) = float_operation(instr) {
+            self.0.insert(op);
+        }
+    }
+}
+
+pub(crate) fn change_type(t: &mut ValType) {
     if *t == ValType::F32 {
         *t = ValType::I32;
     } else if *t == ValType::F64 {
@@ -71,50 +232,107 @@ fn change_type(t: &mut ValType) {
     }
 }
 
-struct FloatReplacer<'a> {
-    replacement_funcs: Arc<HashMap<&'a str, FunctionId>>,
+/// Replaces a float constant by its bits
+fn change_value(value: &mut Value) {
+    match value {
+        Value::F32(v) => *value = Value::I32(v.to_bits() as i32),
+        Value::F64(v) => *value = Value::I64(v.to_bits() as i64),
+        _ => {}
+    }
 }
 
-impl VisitorMut for FloatReplacer<'_> {
-    fn visit_instr_mut(&mut self, instr: &mut Instr, _instr_loc: &mut walrus::InstrLocId) {
-        macro_rules! call_fn {
-            ($name: expr) => {{
-                const NAME: &str = $name;
-                *instr = Instr::Call(Call {
-                    func: *self
-                        .replacement_funcs
-                        .get(concatcp!(crate::SOFTFLOAT_PREFIX, NAME))
-                        .expect(concatcp!("no function ", NAME, " found")), // TODO: error message
-                })
-            }};
+/// Returns a description of every float type and float instruction in `module`,
+/// which is empty after [`replace_float_operations`].
+pub fn remaining_floats(module: &Module) -> Vec<String> {
+    let is_float = |ty: &ValType| matches!(ty, ValType::F32 | ValType::F64);
+    let mut floats = Vec::new();
+    for ty in module.types.iter() {
+        if ty.params().iter().chain(ty.results()).any(is_float) {
+            floats.push(format!(
+                "type {}",
+                format_signature(ty.params(), ty.results())
+            ));
         }
-        macro_rules! call_op {
-            ($name: path) => {
-                call_fn!(map_ascii_case!(Case::Snake, stringify!($name)))
-            };
+    }
+    for global in module.globals.iter().filter(|global| is_float(&global.ty)) {
+        floats.push(format!("global of type {}", global.ty));
+    }
+    for local in module.locals.iter().filter(|local| is_float(&local.ty)) {
+        floats.push(format!("local of type {}", local.ty));
+    }
+    for (id, func) in module.funcs.iter_local() {
+        let mut visitor = RemainingFloats::default();
+        dfs_in_order(&mut visitor, func, func.entry_block());
+        let name = module.funcs.get(id).name.as_deref().unwrap_or("<unnamed>");
+        floats.extend(
+            visitor
+                .0
+                .into_iter()
+                .map(|instr| format!("{} in function {}", instr, name)),
+        );
+    }
+    floats
+}
+
+/// Collects the float instructions and blocks with float results of a function
+#[derive(Default)]
+struct RemainingFloats(Vec<String>);
+
+impl<'instr> Visitor<'instr> for RemainingFloats {
+    fn start_instr_seq(&mut self, seq: &'instr InstrSeq) {
+        if let InstrSeqType::Simple(Some(ty @ (ValType::F32 | ValType::F64))) = seq.ty {
+            self.0.push(format!("block with result {}", ty));
         }
-        macro_rules! match_unop {
-            ($name: tt) => {
-                walrus::ir::UnaryOp::$name
-            };
-            ($name: tt { .. }) => {
-                walrus::ir::UnaryOp::$name { .. }
-            };
+    }
+
+    fn visit_instr(&mut self, instr: &'instr Instr, _: &'instr walrus::InstrLocId) {
+        let float = match instr {
+            Instr::Const(Const {
+                value: Value::F32(_) | Value::F64(_),
+            })
+            | Instr::Load(Load {
+                kind: LoadKind::F32 | LoadKind::F64,
+                ..
+            })
+            | Instr::Store(Store {
+                kind: StoreKind::F32 | StoreKind::F64,
+                ..
+            })
+            | Instr::Select(Select {
+                ty: Some(ValType::F32 | ValType::F64),
+            }) => Some(format!("{:?}", instr)),
+            _ => float_operation(instr).map(str::to_string),
+        };
+        self.0.extend(float);
+    }
+}
+
+struct FloatReplacer {
+    replacement_funcs: Arc<HashMap<String, FunctionId>>,
+    /// operations for which no replacement function was found
+    missing: BTreeSet<&'static str>,
+}
+
+impl VisitorMut for FloatReplacer {
+    fn start_instr_seq_mut(&mut self, seq: &mut InstrSeq) {
+        if let InstrSeqType::Simple(Some(ty)) = &mut seq.ty {
+            change_type(ty);
         }
-        macro_rules! match_binop {
-            ($name: tt) => {
-                walrus::ir::BinaryOp::$name
-            };
-            ($name: tt { .. }) => {
-                walrus::ir::BinaryOp::$name { .. }
-            };
+    }
+
+    fn visit_instr_mut(&mut self, instr: &mut Instr, _instr_loc: &mut walrus::InstrLocId) {
+        if let Some(op) = float_operation(instr) {
+            match self.replacement_funcs.get(op) {
+                Some(func) => *instr = Instr::Call(Call { func: *func }),
+                None => {
+                    self.missing.insert(op);
+                }
+            }
+            return;
         }
+
         match instr {
-            Instr::Const(Const { value }) => match value {
-                Value::F32(v) => *value = Value::I32(v.to_bits() as i32),
-                Value::F64(v) => *value = Value::I64(v.to_bits() as i64),
-                _ => {}
-            },
+            Instr::Const(Const { value }) => change_value(value),
             Instr::Load(Load { kind, .. }) => match kind {
                 LoadKind::F32 => *kind = LoadKind::I32 { atomic: false },
                 LoadKind::F64 => *kind = LoadKind::I64 { atomic: false },
@@ -127,151 +345,149 @@ impl VisitorMut for FloatReplacer<'_> {
             },
             Instr::Select(Select { ty: Some(ty) }) => change_type(ty),
             Instr::RefNull(RefNull { ty }) => change_type(ty),
-            Instr::Unop(Unop { op }) => {
-                match op {
-                    match_unop!(F32Abs) => call_op!(F32Abs),
-                    match_unop!(F32Neg) => call_op!(F32Neg),
-                    match_unop!(F32Ceil) => call_op!(F32Ceil),
-                    match_unop!(F32Floor) => call_op!(F32Floor),
-                    match_unop!(F32Trunc) => call_op!(F32Trunc),
-                    match_unop!(F32Nearest) => call_op!(F32Nearest),
-                    match_unop!(F32Sqrt) => call_op!(F32Sqrt),
-                    match_unop!(F64Abs) => call_op!(F64Abs),
-                    match_unop!(F64Neg) => call_op!(F64Neg),
-                    match_unop!(F64Ceil) => call_op!(F64Ceil),
-                    match_unop!(F64Floor) => call_op!(F64Floor),
-                    match_unop!(F64Trunc) => call_op!(F64Trunc),
-                    match_unop!(F64Nearest) => call_op!(F64Nearest),
-                    match_unop!(F64Sqrt) => call_op!(F64Sqrt),
-                    match_unop!(I32TruncSF32) => call_op!(I32TruncSF32),
-                    match_unop!(I32TruncUF32) => call_op!(I32TruncUF32),
-                    match_unop!(I32TruncSF64) => call_op!(I32TruncSF64),
-                    match_unop!(I32TruncUF64) => call_op!(I32TruncUF64),
-                    match_unop!(I64TruncSF32) => call_op!(I64TruncSF32),
-                    match_unop!(I64TruncUF32) => call_op!(I64TruncUF32),
-                    match_unop!(I64TruncSF64) => call_op!(I64TruncSF64),
-                    match_unop!(I64TruncUF64) => call_op!(I64TruncUF64),
-                    match_unop!(F32ConvertSI32) => call_op!(F32ConvertSI32),
-                    match_unop!(F32ConvertUI32) => call_op!(F32ConvertUI32),
-                    match_unop!(F32ConvertSI64) => call_op!(F32ConvertSI64),
-                    match_unop!(F32ConvertUI64) => call_op!(F32ConvertUI64),
-                    match_unop!(F32DemoteF64) => call_op!(F32DemoteF64),
-                    match_unop!(F64ConvertSI32) => call_op!(F64ConvertSI32),
-                    match_unop!(F64ConvertUI32) => call_op!(F64ConvertUI32),
-                    match_unop!(F64ConvertSI64) => call_op!(F64ConvertSI64),
-                    match_unop!(F64ConvertUI64) => call_op!(F64ConvertUI64),
-                    match_unop!(F64PromoteF32) => call_op!(F64PromoteF32),
-                    match_unop!(I32ReinterpretF32) => call_op!(I32ReinterpretF32),
-                    match_unop!(I64ReinterpretF64) => call_op!(I64ReinterpretF64),
-                    match_unop!(F32ReinterpretI32) => call_op!(F32ReinterpretI32),
-                    match_unop!(F64ReinterpretI64) => call_op!(F64ReinterpretI64),
-                    match_unop!(I32TruncSSatF32) => call_op!(I32TruncSSatF32),
-                    match_unop!(I32TruncUSatF32) => call_op!(I32TruncUSatF32),
-                    match_unop!(I32TruncSSatF64) => call_op!(I32TruncSSatF64),
-                    match_unop!(I32TruncUSatF64) => call_op!(I32TruncUSatF64),
-                    match_unop!(I64TruncSSatF32) => call_op!(I64TruncSSatF32),
-                    match_unop!(I64TruncUSatF32) => call_op!(I64TruncUSatF32),
-                    match_unop!(I64TruncSSatF64) => call_op!(I64TruncSSatF64),
-                    match_unop!(I64TruncUSatF64) => call_op!(I64TruncUSatF64),
-                    // the following instructions are currently not supported in the softfloat library,
-                    // but are checked here in order to avoid accidentally keeing them in the wasm without warning.
-                    // This is necessary, since we change all types to i32 / i64).
-                    match_unop!(F32x4Splat) => call_op!(F32x4Splat),
-                    match_unop!(F32x4ExtractLane { .. }) => call_op!(F32x4ExtractLane),
-                    match_unop!(F64x2Splat) => call_op!(F64x2Splat),
-                    match_unop!(F64x2ExtractLane { .. }) => call_op!(F64x2ExtractLane),
-                    match_unop!(F32x4Ceil) => call_op!(F32x4Ceil),
-                    match_unop!(F32x4Floor) => call_op!(F32x4Floor),
-                    match_unop!(F32x4Trunc) => call_op!(F32x4Trunc),
-                    match_unop!(F32x4Nearest) => call_op!(F32x4Nearest),
-                    match_unop!(F64x2Ceil) => call_op!(F64x2Ceil),
-                    match_unop!(F64x2Floor) => call_op!(F64x2Floor),
-                    match_unop!(F64x2Trunc) => call_op!(F64x2Trunc),
-                    match_unop!(F64x2Nearest) => call_op!(F64x2Nearest),
-                    match_unop!(F32x4Abs) => call_op!(F32x4Abs),
-                    match_unop!(F32x4Neg) => call_op!(F32x4Neg),
-                    match_unop!(F32x4Sqrt) => call_op!(F32x4Sqrt),
-                    match_unop!(F64x2Abs) => call_op!(F64x2Abs),
-                    match_unop!(F64x2Neg) => call_op!(F64x2Neg),
-                    match_unop!(F64x2Sqrt) => call_op!(F64x2Sqrt),
-                    match_unop!(I32x4TruncSatF32x4S) => call_op!(I32x4TruncSatF32x4S),
-                    match_unop!(I32x4TruncSatF32x4U) => call_op!(I32x4TruncSatF32x4U),
-                    match_unop!(F32x4ConvertI32x4S) => call_op!(F32x4ConvertI32x4S),
-                    match_unop!(F32x4ConvertI32x4U) => call_op!(F32x4ConvertI32x4U),
-                    _ => {}
-                };
-            }
-            Instr::Binop(Binop { op }) => match op {
-                match_binop!(F32Eq) => call_op!(F32Eq),
-                match_binop!(F32Ne) => call_op!(F32Ne),
-                match_binop!(F32Lt) => call_op!(F32Lt),
-                match_binop!(F32Gt) => call_op!(F32Gt),
-                match_binop!(F32Le) => call_op!(F32Le),
-                match_binop!(F32Ge) => call_op!(F32Ge),
-                match_binop!(F64Eq) => call_op!(F64Eq),
-                match_binop!(F64Ne) => call_op!(F64Ne),
-                match_binop!(F64Lt) => call_op!(F64Lt),
-                match_binop!(F64Gt) => call_op!(F64Gt),
-                match_binop!(F64Le) => call_op!(F64Le),
-                match_binop!(F64Ge) => call_op!(F64Ge),
-                match_binop!(F32Add) => call_op!(F32Add),
-                match_binop!(F32Sub) => call_op!(F32Sub),
-                match_binop!(F32Mul) => call_op!(F32Mul),
-                match_binop!(F32Div) => call_op!(F32Div),
-                match_binop!(F32Min) => call_op!(F32Min),
-                match_binop!(F32Max) => call_op!(F32Max),
-                match_binop!(F32Copysign) => call_op!(F32Copysign),
-                match_binop!(F64Add) => call_op!(F64Add),
-                match_binop!(F64Sub) => call_op!(F64Sub),
-                match_binop!(F64Mul) => call_op!(F64Mul),
-                match_binop!(F64Div) => call_op!(F64Div),
-                match_binop!(F64Min) => call_op!(F64Min),
-                match_binop!(F64Max) => call_op!(F64Max),
-                match_binop!(F64Copysign) => call_op!(F64Copysign),
-                // the following instructions are currently not supported in the softfloat library,
-                // see above for more info
-                match_binop!(F32x4ReplaceLane { .. }) => call_op!(F32x4ReplaceLane),
-                match_binop!(F64x2ReplaceLane { .. }) => call_op!(F64x2ReplaceLane),
-                match_binop!(F32x4Eq) => call_op!(F32x4Eq),
-                match_binop!(F32x4Ne) => call_op!(F32x4Ne),
-                match_binop!(F32x4Lt) => call_op!(F32x4Lt),
-                match_binop!(F32x4Gt) => call_op!(F32x4Gt),
-                match_binop!(F32x4Le) => call_op!(F32x4Le),
-                match_binop!(F32x4Ge) => call_op!(F32x4Ge),
-                match_binop!(F64x2Eq) => call_op!(F64x2Eq),
-                match_binop!(F64x2Ne) => call_op!(F64x2Ne),
-                match_binop!(F64x2Lt) => call_op!(F64x2Lt),
-                match_binop!(F64x2Gt) => call_op!(F64x2Gt),
-                match_binop!(F64x2Le) => call_op!(F64x2Le),
-                match_binop!(F64x2Ge) => call_op!(F64x2Ge),
-                match_binop!(F32x4Add) => call_op!(F32x4Add),
-                match_binop!(F32x4Sub) => call_op!(F32x4Sub),
-                match_binop!(F32x4Mul) => call_op!(F32x4Mul),
-                match_binop!(F32x4Div) => call_op!(F32x4Div),
-                match_binop!(F32x4Min) => call_op!(F32x4Min),
-                match_binop!(F32x4Max) => call_op!(F32x4Max),
-                match_binop!(F32x4PMin) => call_op!(F32x4PMin),
-                match_binop!(F32x4PMax) => call_op!(F32x4PMax),
-                match_binop!(F64x2Add) => call_op!(F64x2Add),
-                match_binop!(F64x2Sub) => call_op!(F64x2Sub),
-                match_binop!(F64x2Mul) => call_op!(F64x2Mul),
-                match_binop!(F64x2Div) => call_op!(F64x2Div),
-                match_binop!(F64x2Min) => call_op!(F64x2Min),
-                match_binop!(F64x2Max) => call_op!(F64x2Max),
-                match_binop!(F64x2PMin) => call_op!(F64x2PMin),
-                match_binop!(F64x2PMax) => call_op!(F64x2PMax),
-                // these are not implemented yet in the `wasmparser` version backing `walrus`
-                // match_binop!(F32x4RelaxedMin) => call_op!(F32x4RelaxedMin),
-                // match_binop!(F32x4RelaxedMax) => call_op!(F32x4RelaxedMax),
-                // match_binop!(F64x2RelaxedMin) => call_op!(F64x2RelaxedMin),
-                // match_binop!(F64x2RelaxedMax) => call_op!(F64x2RelaxedMax),
-                // match_binop!(F32x4Fma) => call_op!(F32x4Fma),
-                // match_binop!(F32x4Fms) => call_op!(F32x4Fms),
-                // match_binop!(F64x2Fma) => call_op!(F64x2Fma),
-                // match_binop!(F64x2Fm) => call_op!(F64x2Fm),
-                _ => {}
-            },
             _ => {}
         }
     }
 }
+
+/// Returns the name of the soft float operation that replaces `instr` (e.g. `f_32_add` for `f32.add`),
+/// or `None` if the instruction does not need to be replaced by a call.
+pub(crate) fn float_operation(instr: &Instr) -> Option<&'static str> {
+    macro_rules! instr_pattern {
+        ($instr: ident ($a: ident)) => {
+            Instr::Unop(Unop {
+                op: UnaryOp::$instr,
+            })
+        };
+        ($instr: ident ($a: ident, $b: ident)) => {
+            Instr::Binop(Binop {
+                op: BinaryOp::$instr,
+            })
+        };
+    }
+    // the registry cannot be expanded in expression position, so this generates a nested function
+    macro_rules! match_operations {
+        ($($name: ident: $instr: ident $text: literal [$($opcode: literal),+] ($($param: ident),*) -> $result: ident, $category: ident;)*) => {
+            fn match_operations(instr: &Instr) -> Option<&'static str> {
+                match instr {
+                    $(instr_pattern!($instr ($($param),*)) => Some(stringify!($name)),)*
+                    _ => unsupported_operation(instr),
+                }
+            }
+        };
+    }
+    for_each_operation!(match_operations);
+    match_operations(instr)
+}
+
+/// The following instructions are currently not supported in the softfloat library,
+/// but are checked here in order to avoid accidentally keeing them in the wasm without warning.
+/// This is necessary, since we change all types to i32 / i64.
+fn unsupported_operation(instr: &Instr) -> Option<&'static str> {
+    macro_rules! op_name {
+        ($name: path) => {
+            Some(map_ascii_case!(Case::Snake, stringify!($name)))
+        };
+    }
+    macro_rules! match_unop {
+        ($name: tt) => {
+            walrus::ir::UnaryOp::$name
+        };
+        ($name: tt { .. }) => {
+            walrus::ir::UnaryOp::$name { .. }
+        };
+    }
+    macro_rules! match_binop {
+        ($name: tt) => {
+            walrus::ir::BinaryOp::$name
+        };
+        ($name: tt { .. }) => {
+            walrus::ir::BinaryOp::$name { .. }
+        };
+    }
+    match instr {
+        Instr::Unop(Unop { op }) => match op {
+            match_unop!(F32x4Splat) => op_name!(F32x4Splat),
+            match_unop!(F32x4ExtractLane { .. }) => op_name!(F32x4ExtractLane),
+            match_unop!(F64x2Splat) => op_name!(F64x2Splat),
+            match_unop!(F64x2ExtractLane { .. }) => op_name!(F64x2ExtractLane),
+            match_unop!(F32x4Ceil) => op_name!(F32x4Ceil),
+            match_unop!(F32x4Floor) => op_name!(F32x4Floor),
+            match_unop!(F32x4Trunc) => op_name!(F32x4Trunc),
+            match_unop!(F32x4Nearest) => op_name!(F32x4Nearest),
+            match_unop!(F64x2Ceil) => op_name!(F64x2Ceil),
+            match_unop!(F64x2Floor) => op_name!(F64x2Floor),
+            match_unop!(F64x2Trunc) => op_name!(F64x2Trunc),
+            match_unop!(F64x2Nearest) => op_name!(F64x2Nearest),
+            match_unop!(F32x4Abs) => op_name!(F32x4Abs),
+            match_unop!(F32x4Neg) => op_name!(F32x4Neg),
+            match_unop!(F32x4Sqrt) => op_name!(F32x4Sqrt),
+            match_unop!(F64x2Abs) => op_name!(F64x2Abs),
+            match_unop!(F64x2Neg) => op_name!(F64x2Neg),
+            match_unop!(F64x2Sqrt) => op_name!(F64x2Sqrt),
+            match_unop!(I32x4TruncSatF32x4S) => op_name!(I32x4TruncSatF32x4S),
+            match_unop!(I32x4TruncSatF32x4U) => op_name!(I32x4TruncSatF32x4U),
+            match_unop!(F32x4ConvertI32x4S) => op_name!(F32x4ConvertI32x4S),
+            match_unop!(F32x4ConvertI32x4U) => op_name!(F32x4ConvertI32x4U),
+            _ => None,
+        },
+        Instr::Binop(Binop { op }) => match op {
+            match_binop!(F32x4ReplaceLane { .. }) => op_name!(F32x4ReplaceLane),
+            match_binop!(F64x2ReplaceLane { .. }) => op_name!(F64x2ReplaceLane),
+            match_binop!(F32x4Eq) => op_name!(F32x4Eq),
+            match_binop!(F32x4Ne) => op_name!(F32x4Ne),
+            match_binop!(F32x4Lt) => op_name!(F32x4Lt),
+            match_binop!(F32x4Gt) => op_name!(F32x4Gt),
+            match_binop!(F32x4Le) => op_name!(F32x4Le),
+            match_binop!(F32x4Ge) => op_name!(F32x4Ge),
+            match_binop!(F64x2Eq) => op_name!(F64x2Eq),
+            match_binop!(F64x2Ne) => op_name!(F64x2Ne),
+            match_binop!(F64x2Lt) => op_name!(F64x2Lt),
+            match_binop!(F64x2Gt) => op_name!(F64x2Gt),
+            match_binop!(F64x2Le) => op_name!(F64x2Le),
+            match_binop!(F64x2Ge) => op_name!(F64x2Ge),
+            match_binop!(F32x4Add) => op_name!(F32x4Add),
+            match_binop!(F32x4Sub) => op_name!(F32x4Sub),
+            match_binop!(F32x4Mul) => op_name!(F32x4Mul),
+            match_binop!(F32x4Div) => op_name!(F32x4Div),
+            match_binop!(F32x4Min) => op_name!(F32x4Min),
+            match_binop!(F32x4Max) => op_name!(F32x4Max),
+            match_binop!(F32x4PMin) => op_name!(F32x4PMin),
+            match_binop!(F32x4PMax) => op_name!(F32x4PMax),
+            match_binop!(F64x2Add) => op_name!(F64x2Add),
+            match_binop!(F64x2Sub) => op_name!(F64x2Sub),
+            match_binop!(F64x2Mul) => op_name!(F64x2Mul),
+            match_binop!(F64x2Div) => op_name!(F64x2Div),
+            match_binop!(F64x2Min) => op_name!(F64x2Min),
+            match_binop!(F64x2Max) => op_name!(F64x2Max),
+            match_binop!(F64x2PMin) => op_name!(F64x2PMin),
+            match_binop!(F64x2PMax) => op_name!(F64x2PMax),
+            // these are not implemented yet in the `wasmparser` version backing `walrus`
+            // match_binop!(F32x4RelaxedMin) => op_name!(F32x4RelaxedMin),
+            // match_binop!(F32x4RelaxedMax) => op_name!(F32x4RelaxedMax),
+            // match_binop!(F64x2RelaxedMin) => op_name!(F64x2RelaxedMin),
+            // match_binop!(F64x2RelaxedMax) => op_name!(F64x2RelaxedMax),
+            // match_binop!(F32x4Fma) => op_name!(F32x4Fma),
+            // match_binop!(F32x4Fms) => op_name!(F32x4Fms),
+            // match_binop!(F64x2Fma) => op_name!(F64x2Fma),
+            // match_binop!(F64x2Fm) => op_name!(F64x2Fm),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Returns the parameter and result types of the soft float function for the given operation.
+/// Floats are passed as their bit representation, so only `i32` and `i64` occur.
+pub fn op_signature(op: &str) -> Option<(Vec<ValType>, Vec<ValType>)> {
+    let op = Operation::by_name(op)?;
+    let params = op.params.iter().map(|&ty| val_type(ty)).collect();
+    Some((params, vec![val_type(op.result)]))
+}
+
+fn val_type(ty: ops::ValType) -> ValType {
+    match ty {
+        ops::ValType::I32 => ValType::I32,
+        ops::ValType::I64 => ValType::I64,
+    }
+}
@@ -0,0 +1,41 @@
+//! Deterministic generation of operands for soft float functions.
+
+/// Returns the bits of a random float with the given number of exponent and fraction bits.
+/// The float is zero, subnormal, normal, infinite or NaN, with 70% normals and 7.5% for each of the others,
+/// and has a random sign.
+pub(crate) fn float(rng: &mut SplitMix64, exponent_bits: u32, fraction_bits: u32) -> u64 {
+    let sign = rng.next() & 1;
+    let max_exponent = (1 << exponent_bits) - 1;
+    let fraction = rng.next() & ((1 << fraction_bits) - 1);
+    let (exponent, fraction) = match rng.next() % 40 {
+        0..=2 => (0, 0),
+        3..=5 => (0, fraction.max(1)),
+        6..=8 => (max_exponent, 0),
+        9..=11 => (max_exponent, fraction.max(1)),
+        _ => (1 + rng.next() % (max_exponent - 1), fraction),
+    };
+    sign << (exponent_bits + fraction_bits) | exponent << fraction_bits | fraction
+}
+
+/// Returns a random integer, which is small in half of the cases
+pub(crate) fn integer(rng: &mut SplitMix64) -> u64 {
+    let v = rng.next();
+    match v % 4 {
+        0 => v >> 56,
+        1 => (v >> 56).wrapping_neg(),
+        _ => rng.next(),
+    }
+}
+
+/// A small deterministic random number generator, so the generated operands are reproducible
+pub(crate) struct SplitMix64(pub(crate) u64);
+
+impl SplitMix64 {
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
@@ -1,7 +1,7 @@
 use std::fmt::Debug;
 
 use walrus::{
-    ir::{Block, IfElse, Instr, InstrSeq, InstrSeqId, Loop},
+    ir::{Instr, InstrSeq, InstrSeqId},
     LocalFunction,
 };
 
@@ -13,12 +13,11 @@ pub fn visit_instructions(
 ) {
     // The stack of instruction sequences we still need to visit, and how far
     // along in the instruction sequence we are.
-    let func_seq = func.block(start);
     let mut stack: Vec<(InstrSeqId, usize, TraversalInstr)> =
-        vec![(start, 0, TraversalInstr::FunctionEnd(func_seq))];
+        vec![(start, 0, TraversalInstr::FunctionEnd)];
 
     // visit function start instruction
-    visitor(TraversalInstr::FunctionStart(func_seq));
+    visitor(TraversalInstr::FunctionStart);
 
     'traversing_blocks: while let Some((seq_id, index, end)) = stack.pop() {
         let seq = func.block(seq_id);
@@ -31,17 +30,17 @@ pub fn visit_instructions(
                 Instr::Block(b) => {
                     let seq = func.block(b.seq);
                     stack.push((seq_id, index + 1, end.clone()));
-                    stack.push((b.seq, 0, TraversalInstr::BlockEnd(seq, b)));
+                    stack.push((b.seq, 0, TraversalInstr::BlockEnd));
                     // visit the start instruction
-                    visitor(TraversalInstr::BlockStart(seq, b));
+                    visitor(TraversalInstr::BlockStart(seq));
                     continue 'traversing_blocks;
                 }
                 Instr::Loop(l) => {
                     let seq = func.block(l.seq);
                     stack.push((seq_id, index + 1, end.clone()));
-                    stack.push((l.seq, 0, TraversalInstr::LoopEnd(seq, l)));
+                    stack.push((l.seq, 0, TraversalInstr::LoopEnd));
                     // visit the start instruction
-                    visitor(TraversalInstr::LoopStart(seq, l));
+                    visitor(TraversalInstr::LoopStart(seq));
                     // then continue with the instruction sequence
                     continue 'traversing_blocks;
                 }
@@ -51,10 +50,10 @@ pub fn visit_instructions(
                     let cons = func.block(ie.consequent);
                     let alt = func.block(ie.alternative);
                     stack.push((seq_id, index + 1, end.clone()));
-                    stack.push((ie.alternative, 0, TraversalInstr::IfEnd(cons, alt, ie)));
-                    stack.push((ie.consequent, 0, TraversalInstr::ElseStart(alt, ie)));
+                    stack.push((ie.alternative, 0, TraversalInstr::IfEnd));
+                    stack.push((ie.consequent, 0, TraversalInstr::ElseStart(alt)));
                     // visit the start instruction
-                    visitor(TraversalInstr::IfStart(cons, ie));
+                    visitor(TraversalInstr::IfStart(cons));
                     // then continue with the instruction sequence
                     continue 'traversing_blocks;
                 }
@@ -73,17 +72,18 @@ pub fn visit_instructions(
     }
 }
 
+/// The start variants carry the instruction sequence that starts, the end variants end the innermost one.
 #[derive(Clone)]
 pub enum TraversalInstr<'instr> {
-    FunctionStart(&'instr InstrSeq),
-    FunctionEnd(&'instr InstrSeq),
-    BlockStart(&'instr InstrSeq, &'instr Block),
-    BlockEnd(&'instr InstrSeq, &'instr Block),
-    LoopStart(&'instr InstrSeq, &'instr Loop),
-    LoopEnd(&'instr InstrSeq, &'instr Loop),
-    IfStart(&'instr InstrSeq, &'instr IfElse),
-    ElseStart(&'instr InstrSeq, &'instr IfElse),
-    IfEnd(&'instr InstrSeq, &'instr InstrSeq, &'instr IfElse),
+    FunctionStart,
+    FunctionEnd,
+    BlockStart(&'instr InstrSeq),
+    BlockEnd,
+    LoopStart(&'instr InstrSeq),
+    LoopEnd,
+    IfStart(&'instr InstrSeq),
+    ElseStart(&'instr InstrSeq),
+    IfEnd,
     /// Called for all instructions, except for those that are covered by the other variants.
     Instr(&'instr Instr),
 }
@@ -91,15 +91,15 @@ pub enum TraversalInstr<'instr> {
 impl<'instr> Debug for TraversalInstr<'instr> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::FunctionStart(_) => f.write_str("FunctionStart"),
-            Self::FunctionEnd(_) => f.write_str("FunctionEnd"),
-            Self::BlockStart(_, _) => f.write_str("BlockStart"),
-            Self::BlockEnd(_, _) => f.write_str("BlockEnd"),
-            Self::LoopStart(_, _) => f.write_str("LoopStart"),
-            Self::LoopEnd(_, _) => f.write_str("LoopEnd"),
-            Self::IfStart(_, _) => f.write_str("IfStart"),
-            Self::ElseStart(_, _) => f.write_str("ElseStart"),
-            Self::IfEnd(_, _, _) => f.write_str("IfEnd"),
+            Self::FunctionStart => f.write_str("FunctionStart"),
+            Self::FunctionEnd => f.write_str("FunctionEnd"),
+            Self::BlockStart(_) => f.write_str("BlockStart"),
+            Self::BlockEnd => f.write_str("BlockEnd"),
+            Self::LoopStart(_) => f.write_str("LoopStart"),
+            Self::LoopEnd => f.write_str("LoopEnd"),
+            Self::IfStart(_) => f.write_str("IfStart"),
+            Self::ElseStart(_) => f.write_str("ElseStart"),
+            Self::IfEnd => f.write_str("IfEnd"),
             Self::Instr(arg0) => f.debug_tuple("Instr").field(arg0).finish(),
         }
     }
@@ -0,0 +1,594 @@
+//! Checks that a transpiled module behaves exactly like the original one.
+//!
+//! Both modules are run in the [`wasmi`] interpreter, which executes the float instructions of the
+//! original module natively. Every exported function is called with the same generated arguments in fresh
+//! instances of both modules, and the results or traps, as well as the contents of all exported memories
+//! and globals afterwards, are compared bit for bit.
+//!
+//! Wasm does not specify the payload of NaNs produced by arithmetic, so float results and globals that are
+//! NaN in both modules are considered equal unless [`VerifyOptions::strict_nans`] is set.
+//! Memories are always compared exactly, since it is unknown where they hold floats.
+
+use std::{collections::BTreeMap, fmt};
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use wasm_soft_float_host::define_soft_float;
+use wasmi::{
+    core::{Trap, TrapCode, ValueType, F32, F64},
+    Config, Engine, Extern, ExternType, Instance, Linker, Module, Store, Value,
+};
+
+use crate::{
+    fold::fold_constants_with_host_imports,
+    peephole,
+    replace::{remaining_floats, replace_float_operations, Mode, DEFAULT_IMPORT_MODULE},
+    sample::{self, SplitMix64},
+    SOFTFLOAT_PREFIX,
+};
+
+/// The default number of calls per exported function
+pub const DEFAULT_SAMPLES: usize = 1000;
+
+/// The default maximum fuel of a call in the original module
+pub const DEFAULT_FUEL: u64 = 10_000_000;
+
+/// How much more fuel a call in the transpiled module may consume than in the original one
+const TRANSPILED_FUEL_FACTOR: u64 = 1000;
+
+/// How the modules are run and compared
+#[derive(Debug, Clone)]
+pub struct VerifyOptions {
+    /// The number of calls per exported function
+    pub samples: usize,
+    /// The seed of the generated arguments
+    pub seed: u64,
+    /// The maximum fuel, i.e. roughly the number of instructions, of a call in the original module.
+    /// Calls running out of fuel are skipped.
+    pub fuel: u64,
+    /// The import module of the soft float functions, if the module was transpiled with `--host-imports`
+    pub host_imports: Option<String>,
+    /// Whether the payloads of NaN results have to be identical as well
+    pub strict_nans: bool,
+}
+
+impl Default for VerifyOptions {
+    fn default() -> Self {
+        Self {
+            samples: DEFAULT_SAMPLES,
+            seed: 0,
+            fuel: DEFAULT_FUEL,
+            host_imports: None,
+            strict_nans: false,
+        }
+    }
+}
+
+/// The result of [`verify_equivalence`]
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    /// The number of compared calls per exported function
+    pub calls: BTreeMap<String, usize>,
+    /// The number of calls that were skipped, because they ran out of fuel in the original module
+    pub skipped: usize,
+    /// Exported functions that were not called, because they take or return references
+    pub unsupported: Vec<String>,
+    /// The first call that behaved differently
+    pub divergence: Option<Divergence>,
+}
+
+/// A call that behaved differently in the original and the transpiled module
+#[derive(Debug)]
+pub struct Divergence {
+    pub export: String,
+    /// The arguments, with the types of the original module
+    pub args: Vec<Value>,
+    pub difference: Difference,
+}
+
+/// What was different about a call
+#[derive(Debug)]
+pub enum Difference {
+    Outcome {
+        original: Outcome,
+        transpiled: Outcome,
+    },
+    /// The first differing byte of an exported memory, `None` if the memory is shorter
+    Memory {
+        name: String,
+        offset: usize,
+        original: Option<u8>,
+        transpiled: Option<u8>,
+    },
+    Global {
+        name: String,
+        original: Value,
+        transpiled: Value,
+    },
+}
+
+/// The outcome of a call, with the types of the original module
+#[derive(Debug)]
+pub enum Outcome {
+    Returned(Vec<Value>),
+    Trapped(String),
+    OutOfFuel,
+}
+
+/// Calls all exported functions of the `original` and the `transpiled` module with generated arguments
+/// and compares their behavior. Returns an error if the modules cannot be run or do not have the same exports,
+/// and a report with the first [`Divergence`] if they behave differently.
+pub fn verify_equivalence(
+    original: &[u8],
+    transpiled: &[u8],
+    options: &VerifyOptions,
+) -> Result<VerifyReport> {
+    let mut config = Config::default();
+    config.consume_fuel(true);
+    let engine = Engine::new(&config);
+    let original =
+        Runner::new(&engine, original, None).context("could not load the original module")?;
+    let transpiled = Runner::new(&engine, transpiled, options.host_imports.as_deref())
+        .context("could not load the transpiled module")?;
+
+    let mut report = VerifyReport::default();
+    for export in original.module.exports() {
+        let (name, ty) = match export.ty() {
+            ExternType::Func(ty) => (export.name(), ty),
+            _ => continue,
+        };
+        // the backend itself does not use floats
+        if name.starts_with(SOFTFLOAT_PREFIX) {
+            continue;
+        }
+        if !ty
+            .params()
+            .iter()
+            .chain(ty.results())
+            .all(|&ty| is_number(ty))
+        {
+            report.unsupported.push(name.to_string());
+            continue;
+        }
+        let transpiled_ty = transpiled
+            .module
+            .get_export(name)
+            .and_then(|ty| ty.func().cloned())
+            .ok_or_else(|| {
+                anyhow!(
+                    "the transpiled module does not export the function `{}`",
+                    name
+                )
+            })?;
+        ensure!(
+            ty.params()
+                .iter()
+                .map(|&ty| integer_type(ty))
+                .eq(transpiled_ty.params().iter().copied())
+                && ty
+                    .results()
+                    .iter()
+                    .map(|&ty| integer_type(ty))
+                    .eq(transpiled_ty.results().iter().copied()),
+            "`{}` has the signature {:?} in the transpiled module, which does not match {:?}",
+            name,
+            transpiled_ty,
+            ty
+        );
+
+        let mut rng = SplitMix64(options.seed);
+        let mut calls = 0;
+        for _ in 0..options.samples {
+            let args: Vec<Value> = ty
+                .params()
+                .iter()
+                .map(|&ty| argument(&mut rng, ty))
+                .collect();
+            let expected = original.run(name, &args, options.fuel)?;
+            if let Outcome::OutOfFuel = expected.outcome {
+                report.skipped += 1;
+                continue;
+            }
+            let integer_args: Vec<Value> = args.iter().map(to_integer).collect();
+            let actual = transpiled.run(
+                name,
+                &integer_args,
+                options.fuel.saturating_mul(TRANSPILED_FUEL_FACTOR),
+            )?;
+            calls += 1;
+            if let Some(difference) = compare(&expected, &actual, options.strict_nans)? {
+                report.calls.insert(name.to_string(), calls);
+                report.divergence = Some(Divergence {
+                    export: name.to_string(),
+                    args,
+                    difference,
+                });
+                return Ok(report);
+            }
+        }
+        report.calls.insert(name.to_string(), calls);
+    }
+    Ok(report)
+}
+
+/// Transpiles `wasm` with host imports, folding constants with the backend of [`wasm_soft_float_host`]
+/// and removing redundant calls, and checks that the result is valid and does not contain any floats.
+pub fn transpile_with_host_imports(wasm: &[u8]) -> Result<Vec<u8>> {
+    let mut module = walrus::Module::from_buffer(wasm)?;
+    let mode = Mode::HostImport(DEFAULT_IMPORT_MODULE.to_string());
+    fold_constants_with_host_imports(&mut module)?;
+    let softfloat_funcs = replace_float_operations(&mut module, &mode)?;
+    peephole::optimize(&mut module, &mode, &softfloat_funcs)?;
+    walrus::passes::gc::run(&mut module);
+
+    let floats = remaining_floats(&module);
+    ensure!(
+        floats.is_empty(),
+        "the transpiled module still contains floats:\n  {}",
+        floats.join("\n  ")
+    );
+    let transpiled = module.emit_wasm();
+    wasmparser::validate(&transpiled).context("the transpiled module is invalid")?;
+    Ok(transpiled)
+}
+
+/// Transpiles `wasm` with [`transpile_with_host_imports`] and checks that the result behaves like the
+/// original module. This is what the fuzzer runs on every generated module.
+pub fn check_transpilation(wasm: &[u8], options: &VerifyOptions) -> Result<VerifyReport> {
+    let transpiled = transpile_with_host_imports(wasm)?;
+    let options = VerifyOptions {
+        host_imports: Some(DEFAULT_IMPORT_MODULE.to_string()),
+        ..options.clone()
+    };
+    verify_equivalence(wasm, &transpiled, &options)
+}
+
+/// Instantiates a module for every call
+struct Runner {
+    engine: Engine,
+    module: Module,
+    linker: Linker<()>,
+}
+
+impl Runner {
+    /// Loads the module and provides its imports. Imports from `host_imports` are the soft float functions,
+    /// all other imported functions trap when they are called.
+    fn new(engine: &Engine, wasm: &[u8], host_imports: Option<&str>) -> Result<Self> {
+        let module = Module::new(engine, wasm)?;
+        let mut linker = Linker::new(engine);
+        if let Some(import_module) = host_imports {
+            define_soft_float(&mut linker, import_module)?;
+        }
+        for import in module.imports() {
+            if Some(import.module()) == host_imports {
+                continue;
+            }
+            let ty = match import.ty() {
+                ExternType::Func(ty) => ty.clone(),
+                _ => bail!(
+                    "cannot provide the import `{}.{}`, only functions can be imported",
+                    import.module(),
+                    import.name()
+                ),
+            };
+            let message = format!("called the import `{}.{}`", import.module(), import.name());
+            linker.func_new(import.module(), import.name(), ty, move |_, _, _| {
+                Err(Trap::new(message.clone()))
+            })?;
+        }
+        Ok(Self {
+            engine: engine.clone(),
+            module,
+            linker,
+        })
+    }
+
+    /// Calls the exported function `name` in a fresh instance with at most `fuel` fuel.
+    fn run(&self, name: &str, args: &[Value], fuel: u64) -> Result<Execution> {
+        let mut store = Store::new(&self.engine, ());
+        store.add_fuel(fuel).map_err(|e| anyhow!("{}", e))?;
+        let instance = self
+            .linker
+            .instantiate(&mut store, &self.module)?
+            .start(&mut store)
+            .context("the start function failed")?;
+        // top up the fuel consumed by the start function
+        let consumed = store.fuel_consumed().expect("fuel metering is enabled");
+        store.add_fuel(consumed).map_err(|e| anyhow!("{}", e))?;
+
+        let func = instance
+            .get_func(&store, name)
+            .expect("only exported functions are called");
+        let mut results: Vec<Value> = func
+            .ty(&store)
+            .results()
+            .iter()
+            .map(|&ty| Value::default(ty))
+            .collect();
+        let outcome = match func.call(&mut store, args, &mut results) {
+            Ok(()) => Outcome::Returned(results),
+            Err(wasmi::Error::Trap(trap))
+                if matches!(trap.trap_code(), Some(TrapCode::OutOfFuel)) =>
+            {
+                Outcome::OutOfFuel
+            }
+            Err(err) => Outcome::Trapped(err.to_string()),
+        };
+        Ok(Execution {
+            store,
+            instance,
+            outcome,
+        })
+    }
+}
+
+/// A finished call and the instance it was made in
+struct Execution {
+    store: Store<()>,
+    instance: Instance,
+    outcome: Outcome,
+}
+
+/// Returns the first difference between the original and the transpiled call
+fn compare(
+    original: &Execution,
+    transpiled: &Execution,
+    strict_nans: bool,
+) -> Result<Option<Difference>> {
+    let same_outcome = match (&original.outcome, &transpiled.outcome) {
+        (Outcome::Returned(expected), Outcome::Returned(actual)) => expected
+            .iter()
+            .zip(actual)
+            .all(|(expected, actual)| same_value(expected, actual, strict_nans)),
+        // the traps are raised by different instructions, so their messages are not compared
+        (Outcome::Trapped(_), Outcome::Trapped(_)) => true,
+        _ => false,
+    };
+    if !same_outcome {
+        return Ok(Some(Difference::Outcome {
+            original: retype_outcome(&original.outcome, &original.outcome),
+            transpiled: retype_outcome(&transpiled.outcome, &original.outcome),
+        }));
+    }
+
+    for export in original.instance.exports(&original.store) {
+        let name = export.name();
+        match export.into_extern() {
+            Extern::Memory(memory) => {
+                let expected = memory.data(&original.store);
+                let actual = transpiled
+                    .instance
+                    .get_memory(&transpiled.store, name)
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "the transpiled module does not export the memory `{}`",
+                            name
+                        )
+                    })?
+                    .data(&transpiled.store);
+                if expected != actual {
+                    let offset = expected
+                        .iter()
+                        .zip(actual)
+                        .position(|(expected, actual)| expected != actual)
+                        .unwrap_or_else(|| expected.len().min(actual.len()));
+                    return Ok(Some(Difference::Memory {
+                        name: name.to_string(),
+                        offset,
+                        original: expected.get(offset).copied(),
+                        transpiled: actual.get(offset).copied(),
+                    }));
+                }
+            }
+            Extern::Global(global) => {
+                let expected = global.get(&original.store);
+                let actual = transpiled
+                    .instance
+                    .get_global(&transpiled.store, name)
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "the transpiled module does not export the global `{}`",
+                            name
+                        )
+                    })?
+                    .get(&transpiled.store);
+                let actual = retype(&actual, expected.ty());
+                if !same_value(&expected, &actual, strict_nans) {
+                    return Ok(Some(Difference::Global {
+                        name: name.to_string(),
+                        original: expected,
+                        transpiled: actual,
+                    }));
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(None)
+}
+
+/// Compares two values of the original types bit for bit, optionally ignoring NaN payloads
+fn same_value(expected: &Value, actual: &Value, strict_nans: bool) -> bool {
+    let actual = retype(actual, expected.ty());
+    match (expected, &actual) {
+        (Value::I32(a), Value::I32(b)) => a == b,
+        (Value::I64(a), Value::I64(b)) => a == b,
+        (Value::F32(a), Value::F32(b)) => {
+            a.to_bits() == b.to_bits() || (!strict_nans && a.is_nan() && b.is_nan())
+        }
+        (Value::F64(a), Value::F64(b)) => {
+            a.to_bits() == b.to_bits() || (!strict_nans && a.is_nan() && b.is_nan())
+        }
+        _ => false,
+    }
+}
+
+/// Converts the results of `outcome` to the types of the `original` outcome, if it returned
+fn retype_outcome(outcome: &Outcome, original: &Outcome) -> Outcome {
+    match (outcome, original) {
+        (Outcome::Returned(values), Outcome::Returned(types)) => Outcome::Returned(
+            values
+                .iter()
+                .zip(types)
+                .map(|(value, ty)| retype(value, ty.ty()))
+                .collect(),
+        ),
+        (Outcome::Returned(values), _) => Outcome::Returned(values.clone()),
+        (Outcome::Trapped(message), _) => Outcome::Trapped(message.clone()),
+        (Outcome::OutOfFuel, _) => Outcome::OutOfFuel,
+    }
+}
+
+/// Reinterprets an integer of the transpiled module as the float type `ty` of the original module
+fn retype(value: &Value, ty: ValueType) -> Value {
+    match (value, ty) {
+        (Value::I32(bits), ValueType::F32) => Value::F32(F32::from_bits(*bits as u32)),
+        (Value::I64(bits), ValueType::F64) => Value::F64(F64::from_bits(*bits as u64)),
+        _ => value.clone(),
+    }
+}
+
+/// Reinterprets a float of the original module as the integer the transpiled module uses instead
+fn to_integer(value: &Value) -> Value {
+    match value {
+        Value::F32(float) => Value::I32(float.to_bits() as i32),
+        Value::F64(float) => Value::I64(float.to_bits() as i64),
+        _ => value.clone(),
+    }
+}
+
+fn integer_type(ty: ValueType) -> ValueType {
+    match ty {
+        ValueType::F32 => ValueType::I32,
+        ValueType::F64 => ValueType::I64,
+        _ => ty,
+    }
+}
+
+fn is_number(ty: ValueType) -> bool {
+    matches!(
+        ty,
+        ValueType::I32 | ValueType::I64 | ValueType::F32 | ValueType::F64
+    )
+}
+
+/// Returns a random argument of type `ty`.
+/// Floats are mostly drawn from the mix of [`sample::float`], and one in ten are random bits.
+fn argument(rng: &mut SplitMix64, ty: ValueType) -> Value {
+    let mut float = |exponent_bits: u32, fraction_bits: u32| match rng.next() % 10 {
+        0 => rng.next() & (u64::MAX >> (63 - exponent_bits - fraction_bits)),
+        _ => sample::float(rng, exponent_bits, fraction_bits),
+    };
+    match ty {
+        ValueType::F32 => Value::F32(F32::from_bits(float(8, 23) as u32)),
+        ValueType::F64 => Value::F64(F64::from_bits(float(11, 52))),
+        ValueType::I32 => Value::I32(sample::integer(rng) as i32),
+        _ => Value::I64(sample::integer(rng) as i64),
+    }
+}
+
+/// Formats a value, with the bits of floats
+struct DisplayValue<'a>(&'a Value);
+
+impl fmt::Display for DisplayValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Value::I32(v) => write!(f, "i32 {}", v),
+            Value::I64(v) => write!(f, "i64 {}", v),
+            Value::F32(v) => write!(f, "f32 {:?} ({:#010x})", f32::from(*v), v.to_bits()),
+            Value::F64(v) => write!(f, "f64 {:?} ({:#018x})", f64::from(*v), v.to_bits()),
+            v => write!(f, "{:?}", v),
+        }
+    }
+}
+
+fn format_values(values: &[Value]) -> String {
+    values
+        .iter()
+        .map(|v| DisplayValue(v).to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Returned(values) => write!(f, "returned ({})", format_values(values)),
+            Outcome::Trapped(message) => write!(f, "trapped: {}", message),
+            Outcome::OutOfFuel => write!(f, "ran out of fuel"),
+        }
+    }
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let byte = |byte: &Option<u8>| match byte {
+            Some(byte) => format!("{:#04x}", byte),
+            None => "out of bounds".to_string(),
+        };
+        match self {
+            Difference::Outcome {
+                original,
+                transpiled,
+            } => write!(f, "original {}, transpiled {}", original, transpiled),
+            Difference::Memory {
+                name,
+                offset,
+                original,
+                transpiled,
+            } => write!(
+                f,
+                "memory `{}` differs at offset {}: original {}, transpiled {}",
+                name,
+                offset,
+                byte(original),
+                byte(transpiled)
+            ),
+            Difference::Global {
+                name,
+                original,
+                transpiled,
+            } => write!(
+                f,
+                "global `{}` differs: original {}, transpiled {}",
+                name,
+                DisplayValue(original),
+                DisplayValue(transpiled)
+            ),
+        }
+    }
+}
+
+impl fmt::Display for VerifyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(divergence) = &self.divergence {
+            writeln!(
+                f,
+                "`{}` diverged when called with ({}):",
+                divergence.export,
+                format_values(&divergence.args)
+            )?;
+            return writeln!(f, "  {}", divergence.difference);
+        }
+        writeln!(
+            f,
+            "{} calls of {} exported functions behaved identically",
+            self.calls.values().sum::<usize>(),
+            self.calls.len()
+        )?;
+        if self.skipped > 0 {
+            writeln!(
+                f,
+                "skipped {} calls that ran out of fuel in the original module",
+                self.skipped
+            )?;
+        }
+        if !self.unsupported.is_empty() {
+            writeln!(
+                f,
+                "functions using references were not called: {}",
+                self.unsupported.join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
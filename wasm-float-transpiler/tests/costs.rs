@@ -0,0 +1,90 @@
+use wasm_float_transpiler::{costs::measure_costs, metering::CostTable};
+
+/// A backend with a constant time `neg`, an `abs` that returns early for positive operands
+/// and a conversion that traps for negative operands
+const BACKEND: &str = r#"
+(module
+    (func (export "__wasm_soft_float_f_32_neg") (param i32) (result i32)
+        local.get 0
+        i32.const 0x80000000
+        i32.xor)
+    (func (export "__wasm_soft_float_f_32_abs") (param i32) (result i32)
+        local.get 0
+        i32.const 0
+        i32.ge_s
+        if
+            local.get 0
+            return
+        end
+        local.get 0
+        i32.const 0x7fffffff
+        i32.and)
+    (func (export "__wasm_soft_float_i_32_trunc_uf_32") (param i32) (result i32)
+        local.get 0
+        i32.const 0
+        i32.lt_s
+        if
+            unreachable
+        end
+        local.get 0))
+"#;
+
+#[test]
+fn measures_exported_operations() {
+    let costs = measure_costs(&wat::parse_str(BACKEND).unwrap(), 1000).unwrap();
+    assert_eq!(
+        costs.keys().copied().collect::<Vec<_>>(),
+        ["f_32_abs", "f_32_neg", "i_32_trunc_uf_32"]
+    );
+
+    let neg = costs["f_32_neg"];
+    assert_eq!(neg.min, neg.max);
+    assert_eq!(neg.mean, neg.min as f64);
+
+    // the operands are positive about half of the time
+    let abs = costs["f_32_abs"];
+    assert!(abs.min < abs.max);
+    assert!(abs.min as f64 + 0.25 * (abs.max - abs.min) as f64 <= abs.mean);
+    assert!(abs.mean <= abs.max as f64 - 0.25 * (abs.max - abs.min) as f64);
+
+    // trapping calls are not measured
+    let trunc = costs["i_32_trunc_uf_32"];
+    assert_eq!(trunc.min, trunc.max);
+
+    // measurements are reproducible
+    assert_eq!(
+        measure_costs(&wat::parse_str(BACKEND).unwrap(), 1000).unwrap(),
+        costs
+    );
+}
+
+#[test]
+fn measurements_are_cost_tables() {
+    let costs = measure_costs(&wat::parse_str(BACKEND).unwrap(), 100).unwrap();
+    let table = CostTable::from_json(&serde_json::to_string_pretty(&costs).unwrap()).unwrap();
+    assert_eq!(table.cost("f_32_abs"), Some(costs["f_32_abs"].max));
+    assert_eq!(table.cost("f_32_neg"), Some(costs["f_32_neg"].max));
+    assert_eq!(table.cost("f_64_add"), None);
+
+    // measured and fixed costs can be mixed
+    let table = CostTable::from_json(
+        r#"{ "default": 5, "f_32_abs": { "min": 3, "mean": 4.5, "max": 7 } }"#,
+    )
+    .unwrap();
+    assert_eq!(table.cost("f_32_abs"), Some(7));
+    assert_eq!(table.cost("f_64_add"), Some(5));
+}
+
+#[test]
+fn rejects_wrong_signatures() {
+    let wat = r#"
+    (module
+        (func (export "__wasm_soft_float_f_64_neg") (param i32) (result i32)
+            local.get 0))
+    "#;
+    let err = measure_costs(&wat::parse_str(wat).unwrap(), 10).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "__wasm_soft_float_f_64_neg has the wrong signature"
+    );
+}
@@ -0,0 +1,34 @@
+//! Runs the modules in `tests/fuzz`, which made the fuzzer fail, through the same checks as the fuzzer.
+//! To add a new one, print the failing input with `cargo fuzz fmt transpile <artifact>`.
+
+use std::{ffi::OsStr, fs};
+
+use wasm_float_transpiler::verify::{check_transpilation, VerifyOptions};
+
+#[test]
+fn regressions() {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fuzz");
+    let mut checked = 0;
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension() != Some(OsStr::new("wat")) {
+            continue;
+        }
+        let wasm = wat::parse_file(&path).unwrap();
+        let options = VerifyOptions {
+            samples: 100,
+            ..VerifyOptions::default()
+        };
+        let report = check_transpilation(&wasm, &options)
+            .unwrap_or_else(|e| panic!("{}: {:?}", path.display(), e));
+        assert!(
+            report.divergence.is_none(),
+            "{}: {}",
+            path.display(),
+            report
+        );
+        assert!(!report.calls.is_empty());
+        checked += 1;
+    }
+    assert!(checked > 0);
+}
@@ -0,0 +1,12 @@
+;; blocks with a single float result kept their type
+(module
+    (func (export "select") (param f32 f32 i32) (result f32)
+        block (result f32)
+            local.get 0
+            local.get 2
+            br_if 0
+            drop
+            local.get 1
+        end
+        f32.const 1
+        f32.add))
@@ -0,0 +1,20 @@
+;; types only used by `call_indirect` kept their float parameters
+(module
+    (type $binop (func (param f64 f64) (result f64)))
+    (table 2 funcref)
+    (elem (i32.const 0) $add $half)
+    (func $add (param i32 i32) (result i32)
+        local.get 0
+        local.get 1
+        i32.add)
+    (func $half (param f64 f64) (result f64)
+        local.get 0
+        f64.const 0.5
+        f64.mul)
+    (func (export "call") (param f64 f64 i32) (result f64)
+        local.get 0
+        local.get 1
+        local.get 2
+        i32.const 1
+        i32.and
+        call_indirect (type $binop)))
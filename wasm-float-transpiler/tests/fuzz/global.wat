@@ -0,0 +1,10 @@
+;; float globals kept their type and initializer
+(module
+    (global $scale (mut f64) (f64.const 1.5))
+    (global $offset (export "offset") f32 (f32.const -0.25))
+    (func (export "scale") (param f64) (result f64)
+        local.get 0
+        global.get $scale
+        f64.mul
+        global.set $scale
+        global.get $scale))
@@ -0,0 +1,11 @@
+;; imported functions kept their float types
+(module
+    (import "env" "log" (func $log (param f32)))
+    (func (export "log_if_negative") (param f32)
+        local.get 0
+        f32.const 0
+        f32.lt
+        if
+            local.get 0
+            call $log
+        end))
@@ -0,0 +1,128 @@
+use quickcheck::quickcheck;
+use wasm_float_transpiler::{
+    inline::inline_soft_float_functions,
+    replace::{replace_float_operations, Mode},
+};
+use wasmi::{Engine, Instance, Linker, Module, Store};
+
+/// A module with its own soft float functions, some of which are small enough to be inlined
+const MODULE: &str = r#"
+(module
+    (func (export "__wasm_soft_float_f_32_abs") (param i32) (result i32)
+        local.get 0
+        i32.const 0x7fffffff
+        i32.and)
+    (func (export "__wasm_soft_float_f_32_neg") (param i32) (result i32)
+        local.get 0
+        i32.const 0x80000000
+        i32.xor)
+    ;; returns early and uses a local
+    (func (export "__wasm_soft_float_f_32_copysign") (param i32 i32) (result i32)
+        (local i32)
+        local.get 1
+        i32.const 0x80000000
+        i32.and
+        local.tee 2
+        i32.eqz
+        if
+            local.get 0
+            i32.const 0x7fffffff
+            i32.and
+            return
+        end
+        local.get 0
+        local.get 2
+        i32.or)
+    ;; not a real subtraction, but its result depends on the local starting at zero in every call
+    (func (export "__wasm_soft_float_f_32_sub") (param i32 i32) (result i32)
+        (local i32)
+        local.get 0
+        local.get 1
+        i32.xor
+        local.get 2
+        i32.add
+        local.get 0
+        local.set 2)
+    (func (export "run") (param f32 f32 i32) (result f32)
+        block
+            loop
+                local.get 2
+                i32.eqz
+                br_if 1
+                local.get 0
+                f32.abs
+                local.get 1
+                f32.copysign
+                f32.neg
+                local.get 1
+                f32.sub
+                local.set 0
+                local.get 2
+                i32.const 1
+                i32.sub
+                local.set 2
+                br 0
+            end
+        end
+        local.get 0))
+"#;
+
+/// Transpiles [`MODULE`], inlining functions with at most `threshold` instructions
+fn transpile(threshold: u64) -> (Vec<u8>, usize) {
+    let mut module = walrus::Module::from_buffer(&wat::parse_str(MODULE).unwrap()).unwrap();
+    let softfloat_funcs = replace_float_operations(&mut module, &Mode::Embedded).unwrap();
+    let inlined = if threshold > 0 {
+        inline_soft_float_functions(&mut module, &softfloat_funcs, threshold)
+            .inlined
+            .values()
+            .sum()
+    } else {
+        0
+    };
+    walrus::passes::gc::run(&mut module);
+    (module.emit_wasm(), inlined)
+}
+
+fn instantiate(wasm: &[u8]) -> (Store<()>, Instance) {
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let module = Module::new(&engine, wasm).unwrap();
+    let instance = Linker::new(&engine)
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    (store, instance)
+}
+
+fn run(wasm: &[u8], a: u32, b: u32, n: u32) -> u32 {
+    let (mut store, instance) = instantiate(wasm);
+    instance
+        .get_typed_func::<(u32, u32, u32), u32>(&store, "run")
+        .unwrap()
+        .call(&mut store, (a, b, n))
+        .unwrap()
+}
+
+#[test]
+fn respects_threshold() {
+    // abs and neg have 3 instructions, sub has 7 and copysign 13
+    assert_eq!(transpile(3).1, 2);
+    assert_eq!(transpile(7).1, 3);
+    assert_eq!(transpile(20).1, 4);
+}
+
+#[test]
+fn removes_inlined_functions() {
+    let module = walrus::Module::from_buffer(&transpile(20).0).unwrap();
+    // only `run` is left
+    assert_eq!(module.funcs.iter().count(), 1);
+}
+
+quickcheck! {
+    fn inlining_is_equivalent(a: u32, b: u32, n: u8) -> bool {
+        let n = n as u32 % 8;
+        let expected = run(&transpile(0).0, a, b, n);
+        [3, 7, 20].iter().all(|&threshold| run(&transpile(threshold).0, a, b, n) == expected)
+    }
+}
@@ -0,0 +1,321 @@
+use std::collections::HashSet;
+
+use wasm_float_transpiler::merge::{
+    merge_modules, ExportConflictPolicy, MergeOptions, MergeReport,
+};
+use wasmi::{Caller, Engine, Instance, Linker, Module, Store};
+
+/// The module the source module is merged into, with its own memory, table and indirect calls
+const TARGET: &str = r#"
+(module
+    (import "env" "log" (func $log (param i32)))
+    (type $constant (func (result i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 0) "\01")
+    (table 2 funcref)
+    (elem (i32.const 0) $one $two)
+    (func $one (result i32)
+        i32.const 1)
+    (func $two (result i32)
+        i32.const 2)
+    (func (export "target_read") (result i32)
+        i32.const 0
+        i32.load8_u)
+    (func (export "target_dispatch") (param i32) (result i32)
+        local.get 0
+        call_indirect (type $constant))
+    (func (export "shared") (result i32)
+        i32.const 100))
+"#;
+
+/// The merged module, using the same import as [`TARGET`] and overlapping memory, table and exports
+const SOURCE: &str = r#"
+(module
+    (import "env" "log" (func $log (param i32)))
+    (type $constant (func (result i32)))
+    (memory 1)
+    (data (i32.const 0) "\2a")
+    (table 2 funcref)
+    (elem (i32.const 0) $ten $twenty)
+    (global $counter (mut i32) (i32.const 5))
+    (func $ten (result i32)
+        i32.const 10)
+    (func $twenty (result i32)
+        i32.const 20)
+    (func (export "read") (result i32)
+        i32.const 0
+        i32.load8_u)
+    (func (export "write") (param i32)
+        i32.const 1
+        local.get 0
+        i32.store8)
+    (func (export "dispatch") (param i32) (result i32)
+        local.get 0
+        call_indirect (type $constant))
+    (func (export "count") (result i32)
+        global.get $counter
+        i32.const 1
+        i32.add
+        global.set $counter
+        global.get $counter
+        call $log
+        global.get $counter)
+    (func (export "shared") (result i32)
+        i32.const 200))
+"#;
+
+fn parse(wat: &str) -> walrus::Module {
+    walrus::Module::from_buffer(&wat::parse_str(wat).unwrap()).unwrap()
+}
+
+/// Merges [`SOURCE`] into [`TARGET`] and checks that the result is valid
+fn merge(options: &MergeOptions) -> anyhow::Result<(Vec<u8>, MergeReport)> {
+    let (mut module, report) = merge_modules(parse(TARGET), parse(SOURCE), options)?;
+    let wasm = module.emit_wasm();
+    wasmparser::validate(&wasm).unwrap();
+    Ok((wasm, report))
+}
+
+/// Instantiates `wasm` with an `env.log` import collecting the logged values in the store
+fn instantiate(wasm: &[u8]) -> (Store<Vec<i32>>, Instance) {
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, Vec::new());
+    let mut linker = Linker::new(&engine);
+    linker
+        .func_wrap("env", "log", |mut caller: Caller<Vec<i32>>, v: i32| {
+            caller.data_mut().push(v);
+        })
+        .unwrap();
+    let module = Module::new(&engine, wasm).unwrap();
+    let instance = linker
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    (store, instance)
+}
+
+fn call<P: wasmi::WasmParams, R: wasmi::WasmResults>(
+    store: &mut Store<Vec<i32>>,
+    instance: Instance,
+    name: &str,
+    params: P,
+) -> R {
+    instance
+        .get_typed_func::<P, R>(&*store, name)
+        .unwrap()
+        .call(store, params)
+        .unwrap()
+}
+
+fn exports(wasm: &[u8]) -> Vec<String> {
+    let mut names: Vec<_> = walrus::Module::from_buffer(wasm)
+        .unwrap()
+        .exports
+        .iter()
+        .map(|e| e.name.clone())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn merges_memories_tables_and_functions() {
+    let (wasm, report) = merge(&MergeOptions::default()).unwrap();
+    assert_eq!(report.memory_offset, 65536);
+    assert_eq!(report.table_offset, 2);
+    assert_eq!(
+        report.dropped,
+        ["export `shared` (name is taken by the target module)"]
+    );
+    // the import is shared with the target module instead of being imported twice
+    let log = report.functions.iter().find(|f| f.imported).unwrap();
+    let module = walrus::Module::from_buffer(&wasm).unwrap();
+    assert_eq!(module.imports.iter().count(), 1);
+    assert_eq!(report.functions.iter().filter(|f| f.imported).count(), 1);
+    assert_eq!(log.target.index(), 0);
+
+    let (mut store, instance) = instantiate(&wasm);
+    // both modules see their own data and table entries
+    assert_eq!(call::<_, i32>(&mut store, instance, "target_read", ()), 1);
+    assert_eq!(call::<_, i32>(&mut store, instance, "read", ()), 42);
+    assert_eq!(
+        call::<_, i32>(&mut store, instance, "target_dispatch", 1),
+        2
+    );
+    assert_eq!(call::<_, i32>(&mut store, instance, "dispatch", 0), 10);
+    assert_eq!(call::<_, i32>(&mut store, instance, "dispatch", 1), 20);
+    assert_eq!(call::<_, i32>(&mut store, instance, "shared", ()), 100);
+
+    // the memory of the source module lives behind the one of the target module
+    call::<_, ()>(&mut store, instance, "write", 7);
+    let memory = instance.get_memory(&store, "memory").unwrap();
+    let mut bytes = [0; 2];
+    memory.read(&store, 65536, &mut bytes).unwrap();
+    assert_eq!(bytes, [42, 7]);
+    memory.read(&store, 0, &mut bytes).unwrap();
+    assert_eq!(bytes, [1, 0]);
+
+    // the global and the import are carried over
+    assert_eq!(call::<_, i32>(&mut store, instance, "count", ()), 6);
+    assert_eq!(call::<_, i32>(&mut store, instance, "count", ()), 7);
+    assert_eq!(store.data(), &[6, 7]);
+}
+
+#[test]
+fn prefixes_conflicting_exports() {
+    let options = MergeOptions {
+        export_conflicts: ExportConflictPolicy::PrefixSource("source_".to_string()),
+        ..Default::default()
+    };
+    let (wasm, report) = merge(&options).unwrap();
+    assert!(report.dropped.is_empty());
+    assert_eq!(
+        exports(&wasm),
+        [
+            "count",
+            "dispatch",
+            "memory",
+            "read",
+            "shared",
+            "source_shared",
+            "target_dispatch",
+            "target_read",
+            "write"
+        ]
+    );
+
+    let (mut store, instance) = instantiate(&wasm);
+    assert_eq!(call::<_, i32>(&mut store, instance, "shared", ()), 100);
+    assert_eq!(
+        call::<_, i32>(&mut store, instance, "source_shared", ()),
+        200
+    );
+}
+
+#[test]
+fn fails_on_conflicting_exports() {
+    let options = MergeOptions {
+        export_conflicts: ExportConflictPolicy::Error,
+        ..Default::default()
+    };
+    let err = merge(&options).unwrap_err();
+    assert_eq!(err.to_string(), "export `shared` exists in both modules");
+}
+
+#[test]
+fn keeps_only_requested_exports() {
+    let options = MergeOptions {
+        export_conflicts: ExportConflictPolicy::Error,
+        source_exports: Some(HashSet::from(["dispatch".to_string()])),
+    };
+    // `shared` is not requested, so it does not conflict
+    let (wasm, mut report) = merge(&options).unwrap();
+    report.dropped.sort();
+    assert_eq!(
+        report.dropped,
+        [
+            "export `count` (not requested)",
+            "export `read` (not requested)",
+            "export `shared` (not requested)",
+            "export `write` (not requested)"
+        ]
+    );
+    assert_eq!(
+        exports(&wasm),
+        [
+            "dispatch",
+            "memory",
+            "shared",
+            "target_dispatch",
+            "target_read"
+        ]
+    );
+
+    let (mut store, instance) = instantiate(&wasm);
+    assert_eq!(call::<_, i32>(&mut store, instance, "dispatch", 1), 20);
+
+    let options = MergeOptions {
+        source_exports: Some(HashSet::from(["missing".to_string()])),
+        ..Default::default()
+    };
+    let err = merge(&options).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "source module has no export named `missing`"
+    );
+}
+
+#[test]
+fn rejects_element_offsets_out_of_range() {
+    let source = r#"
+    (module
+        (table 1 funcref)
+        (elem (i32.const 2147483647) $f)
+        (func $f))
+    "#;
+    let err = merge_modules(parse(TARGET), parse(source), &MergeOptions::default()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "element segment offset 2147483647 is out of range after shifting it by 2"
+    );
+}
+
+#[test]
+fn rejects_memory_access_offsets_out_of_range() {
+    let source = r#"
+    (module
+        (memory 1)
+        (func (export "read") (result i32)
+            i32.const 0
+            i32.load offset=4294967295))
+    "#;
+    let err = merge_modules(parse(TARGET), parse(source), &MergeOptions::default()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "memory access offset 4294967295 is out of range after shifting it by 65536"
+    );
+}
+
+#[test]
+fn rejects_unshiftable_memory_instructions() {
+    for instr in [
+        "i32.const 0 i32.const 0 i32.const 1 memory.fill",
+        "i32.const 0 i32.const 1 i32.const 1 memory.copy",
+        "i32.const 0 i32.const 0 i32.const 1 memory.init $data",
+        "memory.size drop",
+        "i32.const 1 memory.grow drop",
+    ] {
+        let source = format!(
+            r#"
+            (module
+                (memory 1)
+                (data $data "\2a")
+                (func (export "run") {}))
+            "#,
+            instr
+        );
+        let err =
+            merge_modules(parse(TARGET), parse(&source), &MergeOptions::default()).unwrap_err();
+        let name = instr
+            .split(' ')
+            .rev()
+            .find(|i| i.starts_with("memory."))
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "{} in the source module is not supported if its memory is placed behind the target memory",
+                name
+            )
+        );
+    }
+
+    // without a target memory, the source memory is not moved
+    let target = "(module)";
+    let source = "(module (memory 1) (func (export \"size\") (result i32) memory.size))";
+    let (mut module, report) =
+        merge_modules(parse(target), parse(source), &MergeOptions::default()).unwrap();
+    assert_eq!(report.memory_offset, 0);
+    wasmparser::validate(&module.emit_wasm()).unwrap();
+}
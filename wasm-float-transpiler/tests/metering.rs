@@ -0,0 +1,175 @@
+use wasm_float_transpiler::{
+    metering::{inject_metering, CostTable, Meter, MeteredOperation},
+    replace::{replace_float_operations, Mode, DEFAULT_IMPORT_MODULE},
+};
+use wasm_soft_float_host::define_soft_float;
+use wasmi::{core::Trap, Caller, Engine, Instance, Linker, Module, Store, Value};
+
+/// Computes `a / b + a * a` in a loop, `n` times
+const MODULE: &str = r#"
+(module
+    (func (export "run") (param f64 f64 i32) (result f64)
+        (local f64)
+        block
+            loop
+                local.get 2
+                i32.eqz
+                br_if 1
+                local.get 0
+                local.get 1
+                f64.div
+                local.get 0
+                local.get 0
+                f64.mul
+                f64.add
+                local.set 3
+                local.get 2
+                i32.const 1
+                i32.sub
+                local.set 2
+                br 0
+            end
+        end
+        local.get 3))
+"#;
+
+const COSTS: &str = r#"
+default = 10
+f_64_div = 300
+f_64_mul = 100
+"#;
+
+/// Transpiles [`MODULE`] to use host imports and charges the operations with `meter`
+fn transpile(meter: &Meter) -> Vec<u8> {
+    let mut module = walrus::Module::from_buffer(&wat::parse_str(MODULE).unwrap()).unwrap();
+    let mode = Mode::HostImport(DEFAULT_IMPORT_MODULE.to_string());
+    let softfloat_funcs = replace_float_operations(&mut module, &mode).unwrap();
+    let costs = CostTable::from_toml(COSTS).unwrap();
+    let report = inject_metering(&mut module, meter, &costs, &softfloat_funcs).unwrap();
+    assert_eq!(
+        report.metered["f_64_div"],
+        MeteredOperation {
+            calls: 1,
+            cost: 300
+        }
+    );
+    assert_eq!(report.metered["f_64_add"].cost, 10);
+    walrus::passes::gc::run(&mut module);
+    module.emit_wasm()
+}
+
+/// Instantiates `wasm` with a `env.gas` import adding up the charged costs in the store
+fn instantiate(wasm: &[u8]) -> (Store<u64>, Instance) {
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, 0);
+    let mut linker = Linker::new(&engine);
+    define_soft_float(&mut linker, DEFAULT_IMPORT_MODULE).unwrap();
+    linker
+        .func_wrap("env", "gas", |mut caller: Caller<u64>, cost: i64| {
+            *caller.data_mut() += cost as u64;
+        })
+        .unwrap();
+    let module = Module::new(&engine, wasm).unwrap();
+    let instance = linker
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    (store, instance)
+}
+
+fn run(store: &mut Store<u64>, instance: Instance, n: u32) -> Result<u64, Trap> {
+    instance
+        .get_typed_func::<(u64, u64, u32), u64>(&*store, "run")
+        .unwrap()
+        .call(store, (3.0f64.to_bits(), 2.0f64.to_bits(), n))
+}
+
+#[test]
+fn charges_import() {
+    let wasm = transpile(&Meter::Import {
+        module: "env".to_string(),
+        name: "gas".to_string(),
+    });
+    let (mut store, instance) = instantiate(&wasm);
+    let result = run(&mut store, instance, 5).unwrap();
+    assert_eq!(f64::from_bits(result), 10.5);
+    assert_eq!(*store.data(), 5 * (300 + 100 + 10));
+}
+
+#[test]
+fn charges_global() {
+    let wasm = transpile(&Meter::Global("gas".to_string()));
+    let (mut store, instance) = instantiate(&wasm);
+    let gas = instance.get_global(&store, "gas").unwrap();
+    gas.set(&mut store, Value::I64(10_000)).unwrap();
+    run(&mut store, instance, 5).unwrap();
+    assert_eq!(gas.get(&store).i64(), Some(10_000 - 5 * 410));
+
+    // traps as soon as the counter drops below zero
+    gas.set(&mut store, Value::I64(1000)).unwrap();
+    assert!(run(&mut store, instance, 5).is_err());
+    assert!(gas.get(&store).i64().unwrap() < 0);
+}
+
+#[test]
+fn does_not_charge_internal_calls() {
+    // `sub` is implemented by calling `neg` and `add`, which must not be charged again
+    let wat = r#"
+    (module
+        (func $add (export "__wasm_soft_float_f_32_add") (param i32 i32) (result i32)
+            local.get 0
+            local.get 1
+            i32.add)
+        (func $neg (export "__wasm_soft_float_f_32_neg") (param i32) (result i32)
+            local.get 0
+            i32.const 0x80000000
+            i32.xor)
+        (func (export "__wasm_soft_float_f_32_sub") (param i32 i32) (result i32)
+            local.get 0
+            local.get 1
+            call $neg
+            call $add)
+        (func (export "run") (param f32 f32) (result f32)
+            local.get 0
+            local.get 1
+            f32.sub))
+    "#;
+    let mut module = walrus::Module::from_buffer(&wat::parse_str(wat).unwrap()).unwrap();
+    let softfloat_funcs = replace_float_operations(&mut module, &Mode::Embedded).unwrap();
+    let costs = CostTable::from_json(r#"{ "f_32_sub": 5 }"#).unwrap();
+    let meter = Meter::parse("import=env.gas").unwrap();
+    let report = inject_metering(&mut module, &meter, &costs, &softfloat_funcs).unwrap();
+    assert_eq!(report.metered.len(), 1);
+    assert_eq!(report.metered["f_32_sub"].calls, 1);
+}
+
+#[test]
+fn requires_costs_of_called_operations() {
+    let mut module = walrus::Module::from_buffer(&wat::parse_str(MODULE).unwrap()).unwrap();
+    let mode = Mode::HostImport(DEFAULT_IMPORT_MODULE.to_string());
+    let softfloat_funcs = replace_float_operations(&mut module, &mode).unwrap();
+    let costs = CostTable::from_json(r#"{ "f_64_div": 300 }"#).unwrap();
+    let meter = Meter::Global("gas".to_string());
+    let err = inject_metering(&mut module, &meter, &costs, &softfloat_funcs).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "the cost table has no cost for the following operations: f_64_add, f_64_mul"
+    );
+}
+
+#[test]
+fn parses_cost_tables() {
+    let toml = CostTable::from_toml(COSTS).unwrap();
+    let json =
+        CostTable::from_json(r#"{ "default": 10, "f_64_div": 300, "f_64_mul": 100 }"#).unwrap();
+    assert_eq!(toml, json);
+    assert_eq!(toml.cost("f_64_div"), Some(300));
+    assert_eq!(toml.cost("f_32_div"), Some(10));
+    assert_eq!(CostTable::from_json("{}").unwrap().cost("f_32_div"), None);
+
+    assert!(CostTable::from_json(r#"{ "f_64_fma": 1 }"#).is_err());
+    assert!(CostTable::from_json(r#"{ "f_64_div": -1 }"#).is_err());
+    assert!(Meter::parse("import=gas").is_err());
+    assert!(Meter::parse("counter=gas").is_err());
+}
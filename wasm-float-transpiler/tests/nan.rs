@@ -0,0 +1,321 @@
+use quickcheck::quickcheck;
+use wasm_float_transpiler::{
+    nan::canonicalize_nans,
+    peephole,
+    replace::{replace_float_operations, Mode, DEFAULT_IMPORT_MODULE},
+};
+use wasm_soft_float_host::define_soft_float;
+use wasm_soft_float_utils::float::{F32, F64};
+use wasmi::{Engine, Instance, Linker, Module, Store};
+
+/// A module with operations that can return any NaN and are implemented by all backends,
+/// a promotion and demotion replaced by the peephole pass, and `neg`, which keeps the payload
+const MODULE: &str = r#"
+(module
+    (func (export "add") (param f32 f32) (result f32)
+        local.get 0
+        local.get 1
+        f32.add)
+    (func (export "div") (param f64 f64) (result f64)
+        local.get 0
+        local.get 1
+        f64.div)
+    (func (export "promote") (param f32) (result f64)
+        local.get 0
+        f64.promote_f32)
+    (func (export "demote") (param f64) (result f32)
+        local.get 0
+        f32.demote_f64)
+    (func (export "round_trip") (param f32) (result f32)
+        local.get 0
+        f64.promote_f32
+        f32.demote_f64)
+    (func (export "neg") (param f32) (result f32)
+        local.get 0
+        f32.neg))
+"#;
+
+/// A backend returning the given NaNs for every addition and square root.
+/// Subtraction is implemented with an internal call to the addition.
+fn fake_backend(nan_32: u32, nan_64: u64) -> String {
+    format!(
+        r#"
+        (module
+            (func $add (export "__wasm_soft_float_f_32_add") (param i32 i32) (result i32)
+                i32.const {:#x})
+            (func (export "__wasm_soft_float_f_32_sub") (param i32 i32) (result i32)
+                local.get 0
+                local.get 1
+                call $add)
+            (func (export "__wasm_soft_float_f_64_sqrt") (param i64) (result i64)
+                i64.const {:#x})
+            (func (export "__wasm_soft_float_f_32_neg") (param i32) (result i32)
+                local.get 0
+                i32.const 0x80000000
+                i32.xor)
+            (func (export "add") (param f32 f32) (result f32)
+                local.get 0
+                local.get 1
+                f32.add)
+            (func (export "sub") (param f32 f32) (result f32)
+                local.get 0
+                local.get 1
+                f32.sub)
+            (func (export "sqrt") (param f64) (result f64)
+                local.get 0
+                f64.sqrt)
+            (func (export "neg") (param f32) (result f32)
+                local.get 0
+                f32.neg))
+        "#,
+        nan_32, nan_64
+    )
+}
+
+/// Transpiles `wat` with the peephole pass, canonicalizing NaNs before it if `canonical` is true
+fn transpile(wat: &str, mode: &Mode, canonical: bool) -> Vec<u8> {
+    let mut module = walrus::Module::from_buffer(&wat::parse_str(wat).unwrap()).unwrap();
+    let softfloat_funcs = replace_float_operations(&mut module, mode).unwrap();
+    if canonical {
+        canonicalize_nans(&mut module, &softfloat_funcs);
+    }
+    peephole::optimize(&mut module, mode, &softfloat_funcs).unwrap();
+    walrus::passes::gc::run(&mut module);
+    module.emit_wasm()
+}
+
+fn instantiate(wasm: &[u8]) -> (Store<()>, Instance) {
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let mut linker = Linker::new(&engine);
+    define_soft_float(&mut linker, DEFAULT_IMPORT_MODULE).unwrap();
+
+    let module = Module::new(&engine, wasm).unwrap();
+    let instance = linker
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    (store, instance)
+}
+
+fn call<P, R>(wasm: &[u8], name: &str, params: P) -> R
+where
+    P: wasmi::WasmParams,
+    R: wasmi::WasmResults,
+{
+    let (mut store, instance) = instantiate(wasm);
+    instance
+        .get_typed_func::<P, R>(&store, name)
+        .unwrap()
+        .call(&mut store, params)
+        .unwrap()
+}
+
+/// Calls the export `name` of [`MODULE`] using the host imports, without and with canonicalization
+fn call_both<P, R>(name: &str, params: P) -> (R, R)
+where
+    P: wasmi::WasmParams + Copy,
+    R: wasmi::WasmResults,
+{
+    let mode = Mode::HostImport(DEFAULT_IMPORT_MODULE.to_string());
+    (
+        call(&transpile(MODULE, &mode, false), name, params),
+        call(&transpile(MODULE, &mode, true), name, params),
+    )
+}
+
+/// A NaN with the given payload and sign
+fn nan_32(payload: u32) -> u32 {
+    F32::INFINITY.to_bits() | payload & 0x807f_ffff | 1
+}
+
+fn nan_64(payload: u64) -> u64 {
+    F64::INFINITY.to_bits() | payload & 0x800f_ffff_ffff_ffff | 1
+}
+
+/// Whether `actual` is `expected`, except that NaNs are canonical
+fn canonical_32(expected: u32, actual: u32) -> bool {
+    actual == F32::from_bits(expected).canonicalize().to_bits()
+}
+
+fn canonical_64(expected: u64, actual: u64) -> bool {
+    actual == F64::from_bits(expected).canonicalize().to_bits()
+}
+
+#[test]
+fn backends_agree() {
+    // e.g. the default NaN of x86 and a propagated payload
+    let backends = [
+        (0xffc0_0000, 0xfff8_0000_0000_0000),
+        (0x7fc0_1234, 0x7ff0_0000_0000_0001),
+    ];
+    let nan = nan_32(0x1234);
+    let results = |canonical| {
+        backends
+            .iter()
+            .map(|&(nan_32, nan_64)| {
+                let wasm = transpile(&fake_backend(nan_32, nan_64), &Mode::Embedded, canonical);
+                (
+                    call::<_, u32>(&wasm, "add", (0u32, 0u32)),
+                    call::<_, u32>(&wasm, "sub", (0u32, 0u32)),
+                    call::<_, u64>(&wasm, "sqrt", 0u64),
+                    call::<_, u32>(&wasm, "neg", nan),
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    let different = results(false);
+    assert_ne!(different[0], different[1]);
+    for result in results(true) {
+        assert_eq!(
+            result,
+            (
+                F32::NAN.to_bits(),
+                F32::NAN.to_bits(),
+                F64::NAN.to_bits(),
+                // the sign operations keep the payload
+                nan ^ 0x8000_0000
+            )
+        );
+    }
+}
+
+/// The canonicalized results of the host backend are the native results with canonical NaNs, which do not depend
+/// on the backend. Running this with each of the `bs`, `apfloat` and `softfp` features shows that the real backends
+/// agree bit-for-bit, also for the operations that produce NaNs.
+#[test]
+fn real_backends_agree() {
+    let values_32 = [
+        0,
+        0x8000_0000,
+        1,
+        1.5f32.to_bits(),
+        F32::INFINITY.to_bits(),
+        F32::NEG_INFINITY.to_bits(),
+        F32::NAN.to_bits(),
+        nan_32(0x1234),
+        nan_32(0x8000_0001),
+    ];
+    let values_64 = [
+        0,
+        0x8000_0000_0000_0000,
+        1,
+        1.5f64.to_bits(),
+        F64::INFINITY.to_bits(),
+        F64::NEG_INFINITY.to_bits(),
+        F64::NAN.to_bits(),
+        nan_64(0x1234),
+        nan_64(0x8000_0000_0000_0001),
+    ];
+    for a in values_32 {
+        for b in values_32 {
+            let expected = f32::from_bits(a) + f32::from_bits(b);
+            let (_, actual) = call_both::<(u32, u32), u32>("add", (a, b));
+            assert!(
+                canonical_32(expected.to_bits(), actual),
+                "{:#x} + {:#x}",
+                a,
+                b
+            );
+        }
+        let (_, actual) = call_both::<u32, u64>("promote", a);
+        assert!(
+            canonical_64((f32::from_bits(a) as f64).to_bits(), actual),
+            "promote {:#x}",
+            a
+        );
+    }
+    for a in values_64 {
+        for b in values_64 {
+            let expected = f64::from_bits(a) / f64::from_bits(b);
+            let (_, actual) = call_both::<(u64, u64), u64>("div", (a, b));
+            assert!(
+                canonical_64(expected.to_bits(), actual),
+                "{:#x} / {:#x}",
+                a,
+                b
+            );
+        }
+        let (_, actual) = call_both::<u64, u32>("demote", a);
+        assert!(
+            canonical_32((f64::from_bits(a) as f32).to_bits(), actual),
+            "demote {:#x}",
+            a
+        );
+    }
+}
+
+#[test]
+fn skips_internal_calls() {
+    let mut module =
+        walrus::Module::from_buffer(&wat::parse_str(fake_backend(0, 0)).unwrap()).unwrap();
+    let softfloat_funcs = replace_float_operations(&mut module, &Mode::Embedded).unwrap();
+    let report = canonicalize_nans(&mut module, &softfloat_funcs);
+    // the call of `add` in `sub` and the call of `neg` are not canonicalized
+    assert_eq!(
+        report.canonicalized.into_iter().collect::<Vec<_>>(),
+        [
+            ("f_32_add".to_string(), 1),
+            ("f_32_sub".to_string(), 1),
+            ("f_64_sqrt".to_string(), 1)
+        ]
+    );
+}
+
+#[test]
+fn quiets_promotion_and_demotion() {
+    let nan = nan_32(0x1234);
+    // the peephole pass replaces both calls, so the payload is kept and the signaling NaN becomes quiet
+    let (replaced, canonicalized) = call_both::<u32, u32>("round_trip", nan);
+    assert_eq!(replaced, nan | 0x0040_0000);
+    assert_eq!(canonicalized, F32::NAN.to_bits());
+}
+
+quickcheck! {
+    fn add_is_canonical(a: u32, b: u32, payload: u32) -> bool {
+        let infinity = F32::INFINITY.to_bits();
+        [(a, b), (nan_32(payload), b), (a, nan_32(payload)), (infinity, b)]
+            .into_iter()
+            .all(|params| {
+                let (expected, actual) = call_both::<(u32, u32), u32>("add", params);
+                canonical_32(expected, actual)
+            })
+    }
+
+    fn div_is_canonical(a: u64, payload: u64) -> bool {
+        let zero = F64::ZERO.to_bits();
+        [(a, nan_64(payload)), (nan_64(payload), a), (a, a), (zero, zero)]
+            .into_iter()
+            .all(|params| {
+                let (expected, actual) = call_both::<(u64, u64), u64>("div", params);
+                canonical_64(expected, actual)
+            })
+    }
+
+    fn promote_is_canonical(v: u32, payload: u32) -> bool {
+        [v, nan_32(payload)].into_iter().all(|v| {
+            let (expected, actual) = call_both::<u32, u64>("promote", v);
+            canonical_64(expected, actual)
+        })
+    }
+
+    fn demote_is_canonical(v: u64, payload: u64) -> bool {
+        [v, nan_64(payload)].into_iter().all(|v| {
+            let (expected, actual) = call_both::<u64, u32>("demote", v);
+            canonical_32(expected, actual)
+        })
+    }
+
+    fn round_trip_is_canonical(v: u32, payload: u32) -> bool {
+        [v, nan_32(payload)].into_iter().all(|v| {
+            let (expected, actual) = call_both::<u32, u32>("round_trip", v);
+            canonical_32(expected, actual)
+        })
+    }
+
+    fn neg_keeps_payload(payload: u32) -> bool {
+        let (expected, actual) = call_both::<u32, u32>("neg", nan_32(payload));
+        expected == actual
+    }
+}
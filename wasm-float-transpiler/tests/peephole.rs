@@ -0,0 +1,204 @@
+use quickcheck::quickcheck;
+use walrus::ir::{Instr, UnaryOp, Unop};
+use wasm_float_transpiler::{
+    peephole,
+    replace::{replace_float_operations, Mode, DEFAULT_IMPORT_MODULE},
+};
+use wasm_soft_float_host::define_soft_float;
+use wasmi::{Engine, Instance, Linker, Module, Store};
+
+/// A module containing every pattern rewritten by the peephole optimizer
+const MODULE: &str = r#"
+(module
+    (func (export "reinterpret") (param i32) (result i32)
+        local.get 0
+        f32.reinterpret_i32
+        i32.reinterpret_f32)
+    (func (export "neg_neg") (param f64) (result f64)
+        local.get 0
+        f64.neg
+        f64.neg)
+    (func (export "neg_abs") (param f32) (result f32)
+        local.get 0
+        f32.neg
+        f32.abs)
+    (func (export "abs_abs") (param f64) (result f64)
+        local.get 0
+        f64.abs
+        f64.abs)
+    (func (export "nested") (param f32) (result f32)
+        local.get 0
+        f32.neg
+        i32.reinterpret_f32
+        f32.reinterpret_i32
+        f32.neg)
+    (func (export "promote_demote") (param f32) (result f32)
+        local.get 0
+        f64.promote_f32
+        f32.demote_f64)
+    (func (export "not_eq") (param f64 f64) (result i32)
+        local.get 0
+        local.get 1
+        f64.eq
+        i32.eqz)
+    (func (export "not_ne") (param f32 f32) (result i32)
+        local.get 0
+        local.get 1
+        f32.ne
+        i32.eqz)
+    (func (export "not_lt") (param f32 f32) (result i32)
+        local.get 0
+        local.get 1
+        f32.lt
+        i32.eqz))
+"#;
+
+/// Transpiles [`MODULE`] to use host imports, with or without the peephole optimizer
+fn transpile(optimize: bool) -> (Vec<u8>, usize) {
+    let mut module = walrus::Module::from_buffer(&wat::parse_str(MODULE).unwrap()).unwrap();
+    let mode = Mode::HostImport(DEFAULT_IMPORT_MODULE.to_string());
+    let softfloat_funcs = replace_float_operations(&mut module, &mode).unwrap();
+    let removed = if optimize {
+        peephole::optimize(&mut module, &mode, &softfloat_funcs)
+            .unwrap()
+            .removed
+    } else {
+        0
+    };
+    walrus::passes::gc::run(&mut module);
+    (module.emit_wasm(), removed)
+}
+
+fn instantiate(wasm: &[u8]) -> (Store<()>, Instance) {
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let mut linker = Linker::new(&engine);
+    define_soft_float(&mut linker, DEFAULT_IMPORT_MODULE).unwrap();
+
+    let module = Module::new(&engine, wasm).unwrap();
+    let instance = linker
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    (store, instance)
+}
+
+/// Calls the export `name` in the unoptimized and the optimized module
+fn call_both<P, R>(name: &str, params: P) -> (R, R)
+where
+    P: wasmi::WasmParams + Copy,
+    R: wasmi::WasmResults,
+{
+    let call = |optimize| {
+        let (mut store, instance) = instantiate(&transpile(optimize).0);
+        instance
+            .get_typed_func::<P, R>(&store, name)
+            .unwrap()
+            .call(&mut store, params)
+            .unwrap()
+    };
+    (call(false), call(true))
+}
+
+#[test]
+fn removes_calls() {
+    // reinterpret: 2, neg_neg: 2, neg_abs: 1, abs_abs: 1, nested: 4, promote_demote: 2
+    assert_eq!(transpile(true).1, 12);
+}
+
+#[test]
+fn inverts_equality() {
+    let module = walrus::Module::from_buffer(&transpile(true).0).unwrap();
+    let eqz = module
+        .funcs
+        .iter_local()
+        .flat_map(|(_, func)| func.block(func.entry_block()).instrs.clone())
+        .filter(|(instr, _)| {
+            matches!(
+                instr,
+                Instr::Unop(Unop {
+                    op: UnaryOp::I32Eqz
+                })
+            )
+        })
+        .count();
+    // only the one after `f32.lt` is left
+    assert_eq!(eqz, 1);
+}
+
+quickcheck! {
+    fn reinterpret_is_equivalent(v: u32) -> bool {
+        let (expected, actual) = call_both::<u32, u32>("reinterpret", v);
+        expected == actual
+    }
+
+    fn neg_neg_is_equivalent(v: u64) -> bool {
+        let (expected, actual) = call_both::<u64, u64>("neg_neg", v);
+        expected == actual
+    }
+
+    fn neg_abs_is_equivalent(v: u32) -> bool {
+        let (expected, actual) = call_both::<u32, u32>("neg_abs", v);
+        expected == actual
+    }
+
+    fn abs_abs_is_equivalent(v: u64) -> bool {
+        let (expected, actual) = call_both::<u64, u64>("abs_abs", v);
+        expected == actual
+    }
+
+    fn nested_is_equivalent(v: u32) -> bool {
+        let (expected, actual) = call_both::<u32, u32>("nested", v);
+        expected == actual
+    }
+
+    fn promote_demote_is_equivalent(v: u32) -> bool {
+        let (expected, actual) = call_both::<u32, u32>("promote_demote", v);
+        if f32::from_bits(v).is_nan() {
+            // the backend may choose any NaN, the rewrite keeps the payload and quiets it
+            f32::from_bits(expected).is_nan() && actual == v | 0x0040_0000
+        } else {
+            expected == actual
+        }
+    }
+
+    fn not_eq_is_equivalent(a: u64, b: u64) -> bool {
+        let (expected, actual) = call_both::<(u64, u64), u32>("not_eq", (a, b));
+        expected == actual
+    }
+
+    fn not_ne_is_equivalent(a: u32, b: u32) -> bool {
+        let (expected, actual) = call_both::<(u32, u32), u32>("not_ne", (a, b));
+        expected == actual
+    }
+
+    fn not_lt_is_equivalent(a: u32, b: u32) -> bool {
+        let (expected, actual) = call_both::<(u32, u32), u32>("not_lt", (a, b));
+        expected == actual
+    }
+}
+
+#[test]
+fn nan_comparisons_are_equivalent() {
+    let (nan, zero) = (f64::NAN.to_bits(), 0.0f64.to_bits());
+    for (a, b) in [
+        (nan, nan),
+        (nan, zero),
+        (zero, nan),
+        (zero, (-0.0f64).to_bits()),
+    ] {
+        let (expected, actual) = call_both::<(u64, u64), u32>("not_eq", (a, b));
+        assert_eq!(expected, actual);
+    }
+    let (nan, zero) = (f32::NAN.to_bits(), 0.0f32.to_bits());
+    for (a, b) in [
+        (nan, nan),
+        (nan, zero),
+        (zero, nan),
+        (zero, (-0.0f32).to_bits()),
+    ] {
+        let (expected, actual) = call_both::<(u32, u32), u32>("not_ne", (a, b));
+        assert_eq!(expected, actual);
+    }
+}
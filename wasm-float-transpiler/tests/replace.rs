@@ -0,0 +1,24 @@
+use wasm_float_transpiler::replace::{replace_float_operations, Mode};
+
+#[test]
+fn rejects_wrong_signatures() {
+    // `f_32_eq` takes the operands as `i64` instead of `i32`, `f_32_add` is fine
+    let wat = r#"
+    (module
+        (func (export "__wasm_soft_float_f_32_eq") (param i64 i64) (result i32)
+            i32.const 0)
+        (func (export "__wasm_soft_float_f_32_add") (param i32 i32) (result i32)
+            local.get 0)
+        (func (export "eq") (param f32 f32) (result i32)
+            local.get 0
+            local.get 1
+            f32.eq))
+    "#;
+    let mut module = walrus::Module::from_buffer(&wat::parse_str(wat).unwrap()).unwrap();
+    let err = replace_float_operations(&mut module, &Mode::Embedded).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Found soft float functions with the wrong signature for the following operations:\n  \
+         f_32_eq has type (i64, i64) -> i32, expected (i32, i32) -> i32"
+    );
+}
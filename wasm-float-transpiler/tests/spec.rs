@@ -0,0 +1,305 @@
+//! Runs the float related modules of the official WebAssembly spec test suite through the transpiler
+//! and checks their `assert_return` and `assert_trap` directives against the transpiled modules.
+//! The `.wast` files are not part of the repository, `fetch-spec-tests.sh` downloads them into `tests/spec`.
+//! That is why [`spec`] is ignored by default, run it with `cargo test --test spec -- --ignored`.
+
+use std::{collections::HashMap, fs, path::Path};
+
+use wasm_float_transpiler::{replace::DEFAULT_IMPORT_MODULE, verify::transpile_with_host_imports};
+use wasm_soft_float_host::define_soft_float;
+use wasmi::{Engine, Extern, Instance, Linker, Module, Store, Value};
+use wast::{
+    core::{NanPattern, WastArgCore, WastRetCore},
+    parser::{self, ParseBuffer},
+    QuoteWat, Wast, WastArg, WastDirective, WastExecute, WastInvoke, WastRet,
+};
+
+#[derive(Debug, Default)]
+struct Summary {
+    passed: usize,
+    failures: Vec<String>,
+}
+
+/// Executes the directives of a `.wast` file against the transpiled modules
+struct Runner {
+    engine: Engine,
+    store: Store<()>,
+    /// The most recently defined module, `None` if it could not be transpiled
+    current: Option<Instance>,
+    named: HashMap<String, Option<Instance>>,
+    /// Exports registered with `register` that later modules can import
+    registered: Vec<(String, String, Extern)>,
+}
+
+impl Runner {
+    fn new() -> Self {
+        let engine = Engine::default();
+        Self {
+            store: Store::new(&engine, ()),
+            engine,
+            current: None,
+            named: HashMap::new(),
+            registered: Vec::new(),
+        }
+    }
+
+    /// Transpiles and instantiates a module
+    fn instantiate(&mut self, wasm: &[u8]) -> Result<Instance, String> {
+        let transpiled = transpile_with_host_imports(wasm).map_err(|e| format!("{:#}", e))?;
+        let module = Module::new(&self.engine, &transpiled[..]).map_err(|e| e.to_string())?;
+        let mut linker = Linker::new(&self.engine);
+        define_soft_float(&mut linker, DEFAULT_IMPORT_MODULE).map_err(|e| e.to_string())?;
+        for (module, name, item) in &self.registered {
+            linker
+                .define(module, name, *item)
+                .map_err(|e| e.to_string())?;
+        }
+        linker
+            .instantiate(&mut self.store, &module)
+            .and_then(|instance| instance.start(&mut self.store))
+            .map_err(|e| e.to_string())
+    }
+
+    fn instance(&self, id: Option<wast::token::Id>) -> Result<Instance, String> {
+        match id {
+            Some(id) => self.named.get(id.name()).copied().flatten(),
+            None => self.current,
+        }
+        .ok_or_else(|| "the module could not be instantiated".to_string())
+    }
+
+    fn invoke(&mut self, invoke: WastInvoke) -> Result<Result<Vec<Value>, String>, String> {
+        let instance = self.instance(invoke.module)?;
+        let func = instance
+            .get_func(&self.store, invoke.name)
+            .ok_or_else(|| format!("`{}` is not exported", invoke.name))?;
+        let args = invoke.args.iter().map(arg).collect::<Result<Vec<_>, _>>()?;
+        let mut results = func
+            .ty(&self.store)
+            .results()
+            .iter()
+            .map(|ty| Value::default(*ty))
+            .collect::<Vec<_>>();
+        Ok(func
+            .call(&mut self.store, &args, &mut results)
+            .map(|_| results)
+            .map_err(|e| e.to_string()))
+    }
+
+    /// Runs an invocation, a module instantiation or a global lookup.
+    /// The inner error is the trap message.
+    fn execute(&mut self, exec: WastExecute) -> Result<Result<Vec<Value>, String>, String> {
+        match exec {
+            WastExecute::Invoke(invoke) => self.invoke(invoke),
+            WastExecute::Wat(mut wat) => {
+                let wasm = wat.encode().map_err(|e| e.to_string())?;
+                Ok(self.instantiate(&wasm).map(|_| Vec::new()))
+            }
+            WastExecute::Get { module, global, .. } => {
+                let instance = self.instance(module)?;
+                let global = instance
+                    .get_global(&self.store, global)
+                    .ok_or_else(|| format!("`{}` is not exported", global))?;
+                Ok(Ok(vec![global.get(&self.store)]))
+            }
+        }
+    }
+
+    /// Runs a directive, returning whether it passed
+    fn run(&mut self, directive: WastDirective) -> Result<bool, String> {
+        match directive {
+            WastDirective::Module(QuoteWat::Wat(mut wat)) => {
+                let id = match &wat {
+                    wast::Wat::Module(module) => module.id.map(|id| id.name().to_string()),
+                    _ => None,
+                };
+                let wasm = wat.encode().map_err(|e| e.to_string())?;
+                let instance = self.instantiate(&wasm);
+                self.current = instance.as_ref().ok().copied();
+                if let Some(id) = id {
+                    self.named.insert(id, self.current);
+                }
+                instance.map(|_| true)
+            }
+            WastDirective::Register { name, module, .. } => {
+                let instance = self.instance(module)?;
+                let exports = instance
+                    .exports(&self.store)
+                    .map(|export| {
+                        let item = export.clone().into_extern();
+                        (name.to_string(), export.name().to_string(), item)
+                    })
+                    .collect::<Vec<_>>();
+                self.registered.extend(exports);
+                Ok(true)
+            }
+            WastDirective::Invoke(invoke) => self.invoke(invoke)?.map(|_| true),
+            WastDirective::AssertReturn { exec, results, .. } => match self.execute(exec)? {
+                Ok(actual) => {
+                    if actual.len() == results.len()
+                        && actual.iter().zip(&results).all(|(a, e)| matches(a, e))
+                    {
+                        Ok(true)
+                    } else {
+                        Err(format!("returned {:?}, expected {:?}", actual, results))
+                    }
+                }
+                Err(trap) => Err(trap),
+            },
+            WastDirective::AssertTrap { exec, message, .. } => match self.execute(exec)? {
+                Ok(actual) => Err(format!(
+                    "returned {:?}, expected a trap ({})",
+                    actual, message
+                )),
+                // wasmi extends some of the messages of the spec, e.g. the one of `unreachable`
+                Err(trap) if trap.contains(message) => Ok(true),
+                Err(trap) => Err(format!("trapped with `{}`, expected `{}`", trap, message)),
+            },
+            WastDirective::AssertExhaustion { call, .. } => match self.invoke(call)? {
+                Ok(actual) => Err(format!("returned {:?}, expected exhaustion", actual)),
+                Err(_) => Ok(true),
+            },
+            // invalid and malformed modules are never transpiled
+            _ => Ok(false),
+        }
+    }
+}
+
+/// Converts an argument to its transpiled type, i.e. floats are passed as their bits
+fn arg(arg: &WastArg) -> Result<Value, String> {
+    match arg {
+        WastArg::Core(WastArgCore::I32(v)) => Ok(Value::I32(*v)),
+        WastArg::Core(WastArgCore::I64(v)) => Ok(Value::I64(*v)),
+        WastArg::Core(WastArgCore::F32(v)) => Ok(Value::I32(v.bits as i32)),
+        WastArg::Core(WastArgCore::F64(v)) => Ok(Value::I64(v.bits as i64)),
+        arg => Err(format!("unsupported argument {:?}", arg)),
+    }
+}
+
+/// Checks a transpiled result against the expected one, comparing floats by their bits
+fn matches(actual: &Value, expected: &WastRet) -> bool {
+    match (actual, expected) {
+        (Value::I32(a), WastRet::Core(WastRetCore::I32(e))) => a == e,
+        (Value::I64(a), WastRet::Core(WastRetCore::I64(e))) => a == e,
+        (Value::I32(a), WastRet::Core(WastRetCore::F32(e))) => {
+            let bits = *a as u32;
+            match e {
+                NanPattern::CanonicalNan => bits & 0x7fff_ffff == 0x7fc0_0000,
+                NanPattern::ArithmeticNan => bits & 0x7fc0_0000 == 0x7fc0_0000,
+                NanPattern::Value(e) => bits == e.bits,
+            }
+        }
+        (Value::I64(a), WastRet::Core(WastRetCore::F64(e))) => {
+            let bits = *a as u64;
+            match e {
+                NanPattern::CanonicalNan => bits & 0x7fff_ffff_ffff_ffff == 0x7ff8_0000_0000_0000,
+                NanPattern::ArithmeticNan => bits & 0x7ff8_0000_0000_0000 == 0x7ff8_0000_0000_0000,
+                NanPattern::Value(e) => bits == e.bits,
+            }
+        }
+        _ => false,
+    }
+}
+
+fn run_wast(name: &str, text: &str) -> Summary {
+    let buf = ParseBuffer::new(text).unwrap_or_else(|e| panic!("{}: {}", name, e));
+    let wast = parser::parse::<Wast>(&buf).unwrap_or_else(|e| panic!("{}: {}", name, e));
+    let mut runner = Runner::new();
+    let mut summary = Summary::default();
+    for directive in wast.directives {
+        let (line, _) = directive.span().linecol_in(text);
+        match runner.run(directive) {
+            Ok(true) => summary.passed += 1,
+            Ok(false) => {}
+            Err(e) => summary
+                .failures
+                .push(format!("{}:{}: {}", name, line + 1, e)),
+        }
+    }
+    summary
+}
+
+/// Needs the `.wast` files, so it fails instead of passing without them
+#[test]
+#[ignore]
+fn spec() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/spec");
+    let mut paths = fs::read_dir(&dir)
+        .map(|entries| {
+            entries
+                .map(|entry| entry.unwrap().path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "wast"))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    assert!(
+        !paths.is_empty(),
+        "{} does not contain any .wast files, run ./fetch-spec-tests.sh first",
+        dir.display()
+    );
+    paths.sort();
+
+    let mut failures = Vec::new();
+    for path in paths {
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        let summary = run_wast(&name, &fs::read_to_string(&path).unwrap());
+        println!(
+            "{}: {} passed, {} failed",
+            name,
+            summary.passed,
+            summary.failures.len()
+        );
+        failures.extend(summary.failures);
+    }
+    assert!(
+        failures.is_empty(),
+        "{} directives failed:\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn runs_directives() {
+    let wast = r#"
+    (module $m
+        (global (export "zero") f64 (f64.const -0))
+        (func (export "add") (param f32 f32) (result f32)
+            local.get 0
+            local.get 1
+            f32.add)
+        (func (export "div") (param i32 i32) (result i32)
+            local.get 0
+            local.get 1
+            i32.div_s))
+    (assert_return (invoke "add" (f32.const 1.5) (f32.const 0x1p-149)) (f32.const 1.5))
+    (assert_return (invoke "add" (f32.const inf) (f32.const -inf)) (f32.const nan:canonical))
+    (assert_return (invoke "add" (f32.const nan:0x200000) (f32.const 1)) (f32.const nan:arithmetic))
+    (assert_return (get "zero") (f64.const -0))
+    (assert_trap (invoke "div" (i32.const 1) (i32.const 0)) "integer divide by zero")
+    (module
+        (func (export "sub") (param f64) (result f64)
+            local.get 0
+            f64.const 1
+            f64.sub))
+    (assert_return (invoke $m "add" (f32.const 1) (f32.const 2)) (f32.const 3))
+    (assert_return (invoke "sub" (f64.const 1)) (f64.const 1))
+    (assert_trap (invoke "sub" (f64.const 1)) "unreachable")
+    (assert_invalid (module (func (result f32) i32.const 0)) "type mismatch")
+    (assert_trap (invoke $m "div" (i32.const 1) (i32.const 0)) "integer overflow")
+    (module
+        (func (export "saturate") (param f32) (result i32)
+            local.get 0
+            i32.trunc_sat_f32_s))
+    (assert_return (invoke "saturate" (f32.const 1)) (i32.const 1))
+    "#;
+    let summary = run_wast("test.wast", wast);
+    assert_eq!(summary.passed, 10);
+    assert_eq!(summary.failures.len(), 3, "{:#?}", summary.failures);
+    assert!(summary.failures[0].starts_with("test.wast:23: returned [I64(0)]"));
+    assert!(summary.failures[1].starts_with("test.wast:24: returned [I64(0)]"));
+    assert_eq!(
+        summary.failures[2],
+        "test.wast:26: trapped with `integer divide by zero`, expected `integer overflow`"
+    );
+}
@@ -0,0 +1,214 @@
+use wasm_float_transpiler::{
+    replace::{replace_float_operations, Mode, DEFAULT_IMPORT_MODULE},
+    verify::{verify_equivalence, Difference, Outcome, VerifyOptions},
+};
+
+/// Uses arithmetic, comparisons, conversions, traps, loops, memory and globals
+const MODULE: &str = r#"
+(module
+    (memory (export "memory") 1)
+    (global $last (export "last") (mut i32) (i32.const 0))
+    (func (export "arithmetic") (param f64 f64) (result f64)
+        local.get 0
+        local.get 1
+        f64.div
+        local.get 0
+        local.get 0
+        f64.mul
+        f64.add)
+    (func (export "compare") (param f32 f32) (result i32)
+        local.get 0
+        local.get 1
+        f32.lt
+        local.get 0
+        local.get 1
+        f32.ne
+        i32.add)
+    (func (export "divide") (param i32 i32) (result f64)
+        local.get 0
+        local.get 1
+        i32.div_s
+        f64.convert_i32_s)
+    (func (export "truncate") (param f64) (result i32)
+        local.get 0
+        i32.trunc_f64_s)
+    (func (export "store") (param f64 f32)
+        i32.const 8
+        local.get 0
+        f64.abs
+        f64.store
+        local.get 1
+        f32.neg
+        i32.reinterpret_f32
+        global.set $last)
+    (func (export "sum") (param i32 f32) (result f32)
+        (local f32)
+        block
+            loop
+                local.get 0
+                i32.eqz
+                br_if 1
+                local.get 2
+                local.get 1
+                f32.add
+                local.set 2
+                local.get 0
+                i32.const 1
+                i32.sub
+                local.set 0
+                br 0
+            end
+        end
+        local.get 2))
+"#;
+
+fn transpile(wat: &str) -> Vec<u8> {
+    let mut module = walrus::Module::from_buffer(&wat::parse_str(wat).unwrap()).unwrap();
+    let mode = Mode::HostImport(DEFAULT_IMPORT_MODULE.to_string());
+    replace_float_operations(&mut module, &mode).unwrap();
+    module.emit_wasm()
+}
+
+fn host_imports() -> VerifyOptions {
+    VerifyOptions {
+        host_imports: Some(DEFAULT_IMPORT_MODULE.to_string()),
+        fuel: 10_000,
+        ..VerifyOptions::default()
+    }
+}
+
+#[test]
+fn transpiled_module_is_equivalent() {
+    let original = wat::parse_str(MODULE).unwrap();
+    let report = verify_equivalence(&original, &transpile(MODULE), &host_imports()).unwrap();
+    assert!(report.divergence.is_none(), "{}", report);
+    assert_eq!(report.calls.len(), 6);
+    assert_eq!(report.calls["arithmetic"], 1000);
+    // large loop counts run out of fuel
+    assert!(report.skipped > 0);
+    assert_eq!(report.calls["sum"], 1000 - report.skipped);
+}
+
+#[test]
+fn finds_different_results() {
+    let original = r#"
+    (module
+        (func (export "neg") (param f32) (result f32)
+            local.get 0
+            f32.neg))
+    "#;
+    // forgets to flip the sign of zeros
+    let transpiled = r#"
+    (module
+        (func (export "neg") (param i32) (result i32)
+            local.get 0
+            i32.const 0x7fffffff
+            i32.and
+            if (result i32)
+                local.get 0
+                i32.const 0x80000000
+                i32.xor
+            else
+                local.get 0
+            end))
+    "#;
+    let report = verify_equivalence(
+        &wat::parse_str(original).unwrap(),
+        &wat::parse_str(transpiled).unwrap(),
+        &VerifyOptions::default(),
+    )
+    .unwrap();
+    let divergence = report.divergence.as_ref().unwrap();
+    assert_eq!(divergence.export, "neg");
+    assert_eq!(divergence.args[0].f32().unwrap().to_bits() & 0x7fff_ffff, 0);
+    match &divergence.difference {
+        Difference::Outcome {
+            original: Outcome::Returned(original),
+            transpiled: Outcome::Returned(transpiled),
+        } => assert_eq!(
+            original[0].f32().unwrap().to_bits() ^ transpiled[0].f32().unwrap().to_bits(),
+            0x8000_0000
+        ),
+        difference => panic!("unexpected difference {:?}", difference),
+    }
+    assert!(report
+        .to_string()
+        .starts_with("`neg` diverged when called with (f32 "));
+}
+
+#[test]
+fn finds_different_side_effects() {
+    let original = r#"
+    (module
+        (memory (export "memory") 1)
+        (func (export "store") (param f64)
+            i32.const 16
+            local.get 0
+            f64.store))
+    "#;
+    // stores the value at the wrong address
+    let transpiled = r#"
+    (module
+        (memory (export "memory") 1)
+        (func (export "store") (param i64)
+            i32.const 24
+            local.get 0
+            i64.store))
+    "#;
+    let report = verify_equivalence(
+        &wat::parse_str(original).unwrap(),
+        &wat::parse_str(transpiled).unwrap(),
+        &VerifyOptions::default(),
+    )
+    .unwrap();
+    match report.divergence.unwrap().difference {
+        Difference::Memory { name, offset, .. } => {
+            assert_eq!(name, "memory");
+            assert!((16..32).contains(&offset));
+        }
+        difference => panic!("unexpected difference {:?}", difference),
+    }
+}
+
+#[test]
+fn ignores_nan_payloads() {
+    let original = r#"
+    (module
+        (func (export "nan") (param f32) (result f32)
+            f32.const nan:0x200000))
+    "#;
+    let transpiled = r#"
+    (module
+        (func (export "nan") (param i32) (result i32)
+            i32.const 0x7fc00000))
+    "#;
+    let (original, transpiled) = (
+        wat::parse_str(original).unwrap(),
+        wat::parse_str(transpiled).unwrap(),
+    );
+    let report = verify_equivalence(&original, &transpiled, &VerifyOptions::default()).unwrap();
+    assert!(report.divergence.is_none());
+
+    let strict = VerifyOptions {
+        strict_nans: true,
+        ..VerifyOptions::default()
+    };
+    let report = verify_equivalence(&original, &transpiled, &strict).unwrap();
+    assert!(report.divergence.is_some());
+}
+
+#[test]
+fn requires_matching_exports() {
+    let original = wat::parse_str(MODULE).unwrap();
+    let other = transpile(
+        r#"
+        (module
+            (func (export "arithmetic") (param f32 f32) (result f32)
+                local.get 0))
+        "#,
+    );
+    let err = verify_equivalence(&original, &other, &host_imports()).unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("`arithmetic` has the signature"));
+}